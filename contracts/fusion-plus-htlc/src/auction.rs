@@ -0,0 +1,235 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, AccountId, NearSchema};
+
+use crate::{Balance, Timestamp};
+
+// A breakpoint of the auction curve, relative to the auction start
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct AuctionPoint {
+    pub delay: U128, // nanoseconds since start_time
+    pub amount: U128,
+}
+
+// Dutch auction order posted by a maker. Resolvers fill it by creating an
+// HTLC for the maker with the same order_hash; orders are stored per maker,
+// so nobody else can claim a maker's order hash.
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct AuctionOrder {
+    pub maker: AccountId,
+    pub token: Option<AccountId>,
+    pub start_amount: Balance,
+    pub end_amount: Balance,
+    pub start_time: Timestamp,
+    pub duration: u64,
+    pub points: Vec<(u64, Balance)>,
+    pub filled_htlc_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct CreateAuctionOrderArgs {
+    pub order_hash: near_sdk::json_types::Base64VecU8,
+    pub token: Option<AccountId>,
+    pub start_amount: U128,
+    pub end_amount: U128,
+    pub start_time: Option<U128>, // defaults to the current block timestamp
+    pub duration: U128,
+    pub points: Vec<AuctionPoint>,
+}
+
+#[derive(Serialize, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct AuctionOrderView {
+    pub maker: AccountId,
+    pub token: Option<AccountId>,
+    pub start_amount: U128,
+    pub end_amount: U128,
+    pub start_time: U128,
+    pub duration: U128,
+    pub points: Vec<AuctionPoint>,
+    pub current_amount: U128,
+    pub filled_htlc_id: Option<String>,
+}
+
+impl CreateAuctionOrderArgs {
+    pub(crate) fn into_order(self, maker: AccountId, now: Timestamp) -> AuctionOrder {
+        AuctionOrder {
+            maker,
+            token: self.token,
+            start_amount: self.start_amount.0,
+            end_amount: self.end_amount.0,
+            start_time: self.start_time.map_or(now, to_u64),
+            duration: to_u64(self.duration),
+            points: self
+                .points
+                .iter()
+                .map(|p| (to_u64(p.delay), p.amount.0))
+                .collect(),
+            filled_htlc_id: None,
+        }
+    }
+}
+
+// Times are U128 in JSON but u64 nanoseconds on chain
+fn to_u64(value: U128) -> u64 {
+    u64::try_from(value.0).unwrap_or_else(|_| env::panic_str("Auction time exceeds u64"))
+}
+
+impl AuctionOrder {
+    // Validates the curve: amounts never increase and breakpoints are
+    // strictly inside (0, duration) in ascending order.
+    pub fn assert_valid(&self) {
+        assert!(self.end_amount > 0, "End amount must be positive");
+        assert!(
+            self.start_amount >= self.end_amount,
+            "Start amount must not be below end amount"
+        );
+        assert!(self.duration > 0, "Auction duration must be positive");
+
+        let mut prev = (0u64, self.start_amount);
+        for &(delay, amount) in &self.points {
+            assert!(
+                delay > prev.0 && delay < self.duration,
+                "Auction points must be ordered and within duration"
+            );
+            assert!(
+                amount <= prev.1 && amount >= self.end_amount,
+                "Auction points must not increase the price"
+            );
+            prev = (delay, amount);
+        }
+    }
+
+    // Minimum taker amount accepted at `now`, interpolated linearly between
+    // the surrounding breakpoints. Rounds up in favour of the maker.
+    pub fn current_amount(&self, now: Timestamp) -> Balance {
+        if now <= self.start_time {
+            return self.start_amount;
+        }
+        let elapsed = now - self.start_time;
+        if elapsed >= self.duration {
+            return self.end_amount;
+        }
+
+        let mut from = (0u64, self.start_amount);
+        for &to in self.points.iter().chain(std::iter::once(&(self.duration, self.end_amount))) {
+            if elapsed < to.0 {
+                let span = (to.0 - from.0) as u128;
+                let progress = (elapsed - from.0) as u128;
+                let drop = from.1 - to.1;
+                // drop * progress / span without overflowing: the remainder
+                // is below span, which fits in u64
+                return from.1 - (drop / span * progress + drop % span * progress / span);
+            }
+            from = to;
+        }
+        self.end_amount
+    }
+
+    pub fn to_view(&self, now: Timestamp) -> AuctionOrderView {
+        AuctionOrderView {
+            maker: self.maker.clone(),
+            token: self.token.clone(),
+            start_amount: U128(self.start_amount),
+            end_amount: U128(self.end_amount),
+            start_time: U128(self.start_time as u128),
+            duration: U128(self.duration as u128),
+            points: self
+                .points
+                .iter()
+                .map(|&(delay, amount)| AuctionPoint {
+                    delay: U128(delay as u128),
+                    amount: U128(amount),
+                })
+                .collect(),
+            current_amount: U128(self.current_amount(now)),
+            filled_htlc_id: self.filled_htlc_id.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::accounts;
+
+    fn order(points: Vec<(u64, Balance)>) -> AuctionOrder {
+        AuctionOrder {
+            maker: accounts(1),
+            token: None,
+            start_amount: 1_000,
+            end_amount: 500,
+            start_time: 100,
+            duration: 1_000,
+            points,
+            filled_htlc_id: None,
+        }
+    }
+
+    #[test]
+    fn test_linear_curve() {
+        let order = order(vec![]);
+        order.assert_valid();
+        assert_eq!(order.current_amount(0), 1_000);
+        assert_eq!(order.current_amount(100), 1_000);
+        assert_eq!(order.current_amount(600), 750);
+        assert_eq!(order.current_amount(1_100), 500);
+        assert_eq!(order.current_amount(5_000), 500);
+    }
+
+    #[test]
+    fn test_piecewise_curve() {
+        // Steep drop in the first 10%, then a slow decline to the end amount
+        let order = order(vec![(100, 600)]);
+        order.assert_valid();
+        assert_eq!(order.current_amount(150), 800);
+        assert_eq!(order.current_amount(200), 600);
+        assert_eq!(order.current_amount(650), 550);
+        assert_eq!(order.current_amount(1_100), 500);
+    }
+
+    #[test]
+    fn test_large_amount_long_duration() {
+        // 2M NEAR in yocto falling over a day in nanoseconds
+        let order = AuctionOrder {
+            start_amount: 3 * 10u128.pow(30),
+            end_amount: 10u128.pow(30),
+            start_time: 0,
+            duration: 86_400 * 1_000_000_000,
+            ..order(vec![])
+        };
+        order.assert_valid();
+        assert_eq!(order.current_amount(43_200 * 1_000_000_000), 2 * 10u128.pow(30));
+        assert_eq!(
+            order.current_amount(86_400 * 1_000_000_000 - 1),
+            10u128.pow(30) + 23_148_148_148_148_149
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Auction time exceeds u64")]
+    fn test_oversized_duration_rejected() {
+        let args = CreateAuctionOrderArgs {
+            order_hash: near_sdk::json_types::Base64VecU8(vec![7u8; 32]),
+            token: None,
+            start_amount: U128(2_000),
+            end_amount: U128(1_000),
+            start_time: None,
+            duration: U128(u64::MAX as u128 + 1),
+            points: vec![],
+        };
+        args.into_order(accounts(1), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Auction points must not increase the price")]
+    fn test_increasing_point_rejected() {
+        order(vec![(100, 600), (200, 700)]).assert_valid();
+    }
+}
@@ -1,13 +1,68 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::UnorderedMap;
+use near_sdk::collections::{LookupMap, LookupSet, UnorderedMap, UnorderedSet};
 use near_sdk::json_types::{Base64VecU8, U128};
 use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::{env, near_bindgen, AccountId, BorshStorageKey, PanicOnDefault, Promise, NearToken};
+use near_sdk::{
+    env, ext_contract, near_bindgen, AccountId, BorshStorageKey, Gas, NearSchema, NearToken,
+    PanicOnDefault, Promise, PromiseError, PromiseOrValue,
+};
+use chain_signatures::signatures::{parse_sign_result, SignRequest, SignResult, GAS_FOR_SIGN};
 use sha3::{Digest, Keccak256};
 
+mod auction;
+mod migration;
+mod settlement;
+
+pub use auction::{AuctionOrderView, AuctionPoint, CreateAuctionOrderArgs};
+use auction::AuctionOrder;
+pub use settlement::{BaseEscrow, SettlementConfig, SettlementView};
+use settlement::{parse_address, Settlement};
+
 type Balance = u128;
 type Timestamp = u64;
 
+const GAS_FOR_SOLVER_CHECK: Gas = Gas::from_tgas(5);
+const GAS_FOR_RESOLVER_CALLBACK: Gas = Gas::from_tgas(20);
+const GAS_FOR_REJECT: Gas = Gas::from_tgas(3);
+const GAS_FOR_REPORT: Gas = Gas::from_tgas(15);
+const GAS_FOR_REPORT_CALLBACK: Gas = Gas::from_tgas(5);
+const GAS_FOR_OUTCOME: Gas = Gas::from_tgas(10);
+const GAS_FOR_SETTLEMENT_CALLBACK: Gas = Gas::from_tgas(10);
+const GAS_FOR_FT_TRANSFER: Gas = Gas::from_tgas(10);
+const GAS_FOR_TRANSFER_CALLBACK: Gas = Gas::from_tgas(5);
+
+pub const ERR_RESOLVER_NOT_REGISTERED: &str = "Resolver is not an active registered solver";
+
+// Interface of the solver-registry contract
+#[ext_contract(ext_solver_registry)]
+pub trait SolverRegistry {
+    fn is_active_solver(&self, account_id: AccountId) -> bool;
+    fn report_misbehavior(
+        &mut self,
+        solver: AccountId,
+        htlc_id: String,
+        maker: AccountId,
+        token: Option<AccountId>,
+        amount: U128,
+        evidence: String,
+    ) -> u64;
+    fn record_swap_outcome(
+        &mut self,
+        solver: AccountId,
+        counterparty: AccountId,
+        token: Option<AccountId>,
+        amount: U128,
+        completed: bool,
+        settlement_time: U128,
+    );
+}
+
+// Interface of NEP-141 token contracts
+#[ext_contract(ext_ft)]
+pub trait FungibleToken {
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+}
+
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct FusionPlusHTLC {
@@ -15,6 +70,23 @@ pub struct FusionPlusHTLC {
     htlcs: UnorderedMap<String, HTLC>,
     active_htlc_ids: Vec<String>,
     next_htlc_id: u64,
+    // Keyed by (maker, order_hash)
+    auction_orders: LookupMap<(AccountId, Vec<u8>), AuctionOrder>,
+    solver_registry: Option<AccountId>,
+    resolver_exclusive: bool,
+    cached_solvers: UnorderedSet<AccountId>,
+    reported_htlcs: LookupSet<String>,
+    settlement_config: Option<SettlementConfig>,
+    next_settlement_nonce: u64,
+    settlements: LookupMap<String, Settlement>,
+    // BASE escrow addresses the owner allows settlements to
+    approved_escrows: LookupSet<Vec<u8>>,
+    withdrawn_htlcs: u64,
+    refunded_htlcs: u64,
+    // Amount held by active HTLCs per token (None for NEAR)
+    locked_balances: UnorderedMap<Option<AccountId>, Balance>,
+    // Payouts whose transfer failed, keyed by (account, token)
+    claimable: LookupMap<(AccountId, Option<AccountId>), Balance>,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
@@ -30,15 +102,26 @@ pub struct HTLC {
     pub withdrawn: bool,
     pub refunded: bool,
     pub created_at: Timestamp,
+    pub base_escrow: Option<BaseEscrow>,
+    // Settlement sign deposit prepaid by the sender, in NEAR
+    pub settlement_deposit: Balance,
 }
 
 #[derive(BorshSerialize, BorshStorageKey)]
 enum StorageKey {
     HTLCs,
+    AuctionOrders,
+    CachedSolvers,
+    ReportedHTLCs,
+    Settlements,
+    LockedBalances,
+    ApprovedEscrows,
+    Claimable,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, NearSchema)]
 #[serde(crate = "near_sdk::serde")]
+#[abi(json)]
 pub struct HTLCView {
     pub htlc_id: String,
     pub sender: AccountId,
@@ -51,10 +134,16 @@ pub struct HTLCView {
     pub withdrawn: bool,
     pub refunded: bool,
     pub created_at: U128,
+    pub base_escrow: Option<BaseEscrow>,
+    // Absent from contracts that predate the prepaid sign deposit, whose
+    // withdraw of a base_escrow HTLC has to attach it
+    #[serde(default)]
+    pub settlement_deposit: U128,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, NearSchema)]
 #[serde(crate = "near_sdk::serde")]
+#[abi(json)]
 pub struct EventLog {
     pub event_type: String,
     pub htlc_id: String,
@@ -62,13 +151,60 @@ pub struct EventLog {
     pub receiver: Option<AccountId>,
     pub secret: Option<Base64VecU8>,
     pub amount: Option<U128>,
+    // Token of `amount`, None for NEAR; absent from events logged before it
+    #[serde(default)]
+    pub token: Option<AccountId>,
     pub hashlock: Option<Base64VecU8>,
     pub timelock: Option<U128>,
+    pub order_hash: Option<Base64VecU8>,
+    pub signed_tx: Option<String>,
     pub timestamp: U128,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct TokenAmount {
+    pub token: Option<AccountId>,
+    pub amount: U128,
+}
+
+#[derive(Serialize, Deserialize, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct ContractConfig {
+    pub solver_registry: Option<AccountId>,
+    pub resolver_exclusive: bool,
+    pub settlement_config: Option<SettlementConfig>,
+}
+
+#[derive(Serialize, Deserialize, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct ContractInfo {
+    pub owner: AccountId,
+    pub version: String,
+    pub total_htlcs: u64,
+    pub active_htlcs: u64,
+    pub config: ContractConfig,
+}
+
+#[derive(Serialize, Deserialize, NearSchema)]
 #[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct ContractStats {
+    pub version: String,
+    pub total_htlcs: u64,
+    pub active_htlcs: u64,
+    pub withdrawn_htlcs: u64,
+    pub refunded_htlcs: u64,
+    // Value still held by active HTLCs, per token
+    pub locked: Vec<TokenAmount>,
+}
+
+#[derive(Serialize, Deserialize, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
 pub struct CreateHTLCArgs {
     pub receiver: AccountId,
     pub token: Option<AccountId>,
@@ -76,6 +212,8 @@ pub struct CreateHTLCArgs {
     pub hashlock: Base64VecU8,
     pub timelock: U128,
     pub order_hash: Base64VecU8,
+    // Counterpart escrow to withdraw from once the secret is revealed here
+    pub base_escrow: Option<BaseEscrow>,
 }
 
 #[near_bindgen]
@@ -88,65 +226,156 @@ impl FusionPlusHTLC {
             htlcs: UnorderedMap::new(StorageKey::HTLCs),
             active_htlc_ids: Vec::new(),
             next_htlc_id: 1,
+            auction_orders: LookupMap::new(StorageKey::AuctionOrders),
+            solver_registry: None,
+            resolver_exclusive: false,
+            cached_solvers: UnorderedSet::new(StorageKey::CachedSolvers),
+            reported_htlcs: LookupSet::new(StorageKey::ReportedHTLCs),
+            settlement_config: None,
+            next_settlement_nonce: 0,
+            settlements: LookupMap::new(StorageKey::Settlements),
+            approved_escrows: LookupSet::new(StorageKey::ApprovedEscrows),
+            withdrawn_htlcs: 0,
+            refunded_htlcs: 0,
+            locked_balances: UnorderedMap::new(StorageKey::LockedBalances),
+            claimable: LookupMap::new(StorageKey::Claimable),
         }
     }
 
-    // Create HTLC
+    // Create HTLC. In resolver-exclusive mode the caller must be an active
+    // solver in the registry, which turns creation into a cross-contract check.
     #[payable]
-    pub fn create_htlc(&mut self, args: CreateHTLCArgs) -> String {
-        let htlc_id = format!("htlc_{}", self.next_htlc_id);
-        self.next_htlc_id += 1;
+    pub fn create_htlc(&mut self, args: CreateHTLCArgs) -> PromiseOrValue<String> {
+        let sender = env::predecessor_account_id();
+        let deposit = env::attached_deposit();
 
-        let amount: Balance = args.amount.0;
-        let timelock: Timestamp = args.timelock.0 as u64;
+        if let Err(err) = self.check_create_args(&args, &None, deposit.as_yoctonear()) {
+            env::panic_str(err);
+        }
 
-        // Validate inputs
-        assert!(amount > 0, "Amount must be positive");
-        assert!(timelock > env::block_timestamp(), "Timelock must be in future");
-        assert!(args.hashlock.0.len() == 32, "Hashlock must be 32 bytes (SHA-256)");
-
-        // Handle native NEAR token
-        if args.token.is_none() {
-            assert!(
-                env::attached_deposit() >= NearToken::from_yoctonear(amount),
-                "Attached deposit must match amount for NEAR"
-            );
-        } else {
-            // For NEP-141 tokens, implement transfer_from logic here
-            // For now, we'll focus on native NEAR
-            assert!(args.token.is_none(), "NEP-141 tokens not yet implemented");
+        if self.resolver_exclusive {
+            if let Some(registry) = self.solver_registry.clone() {
+                return PromiseOrValue::Promise(
+                    ext_solver_registry::ext(registry)
+                        .with_static_gas(GAS_FOR_SOLVER_CHECK)
+                        .is_active_solver(sender.clone())
+                        .then(
+                            Self::ext(env::current_account_id())
+                                .with_static_gas(GAS_FOR_RESOLVER_CALLBACK)
+                                .on_resolver_checked(sender, args, U128(deposit.as_yoctonear())),
+                        ),
+                );
+            }
+            assert!(self.cached_solvers.contains(&sender), "{}", ERR_RESOLVER_NOT_REGISTERED);
         }
 
-        let htlc = HTLC {
-            sender: env::predecessor_account_id(),
-            receiver: args.receiver.clone(),
-            token: args.token,
-            amount,
-            hashlock: args.hashlock.clone(),
-            timelock,
-            order_hash: args.order_hash,
-            withdrawn: false,
-            refunded: false,
-            created_at: env::block_timestamp(),
+        PromiseOrValue::Value(self.internal_create_htlc(sender, args))
+    }
+
+    // Callback for the solver registry check. The deposit stays with the
+    // contract until here, so every rejection path must refund it.
+    #[private]
+    pub fn on_resolver_checked(
+        &mut self,
+        sender: AccountId,
+        args: CreateHTLCArgs,
+        deposit: U128,
+        #[callback_result] is_active: Result<bool, PromiseError>,
+    ) -> PromiseOrValue<String> {
+        let allowed = self.resolver_allowed(&sender, is_active);
+
+        let deposit = NearToken::from_yoctonear(deposit.0);
+        let result = if allowed {
+            self.check_create_args(&args, &None, deposit.as_yoctonear())
+        } else {
+            Err(ERR_RESOLVER_NOT_REGISTERED)
         };
 
-        self.htlcs.insert(&htlc_id, &htlc);
-        self.active_htlc_ids.push(htlc_id.clone());
+        // Panicking here would also drop the refund, so the call fails in a
+        // receipt scheduled after it instead
+        if let Err(err) = result {
+            if deposit.is_zero() {
+                env::panic_str(err);
+            }
+            return PromiseOrValue::Promise(
+                Promise::new(sender).transfer(deposit).then(
+                    Self::ext(env::current_account_id())
+                        .with_static_gas(GAS_FOR_REJECT)
+                        .on_create_rejected(err.to_string()),
+                ),
+            );
+        }
 
-        // Emit event
-        self.emit_event(EventLog {
-            event_type: "htlc_created".to_string(),
-            htlc_id: htlc_id.clone(),
-            sender: Some(htlc.sender.clone()),
-            receiver: Some(htlc.receiver.clone()),
-            secret: None,
-            amount: Some(U128(amount)),
-            hashlock: Some(args.hashlock),
-            timelock: Some(U128(timelock as u128)),
-            timestamp: U128(env::block_timestamp() as u128),
-        });
+        PromiseOrValue::Value(self.internal_create_htlc(sender, args))
+    }
 
-        htlc_id
+    // Fails create_htlc once a rejected caller's deposit has been refunded
+    #[private]
+    pub fn on_create_rejected(&self, reason: String) {
+        env::panic_str(&reason);
+    }
+
+    // NEP-141 receiver: locks tokens sent with ft_transfer_call. The message
+    // holds the create_htlc arguments as JSON, with the calling token contract
+    // as token. Returns the amount not locked, which the token refunds.
+    pub fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        let token = env::predecessor_account_id();
+        let args: CreateHTLCArgs = serde_json::from_str(&msg)
+            .unwrap_or_else(|_| env::panic_str("Invalid create_htlc message"));
+
+        if let Err(err) = self.check_create_args(&args, &Some(token), amount.0) {
+            env::panic_str(err);
+        }
+
+        if self.resolver_exclusive {
+            if let Some(registry) = self.solver_registry.clone() {
+                return PromiseOrValue::Promise(
+                    ext_solver_registry::ext(registry)
+                        .with_static_gas(GAS_FOR_SOLVER_CHECK)
+                        .is_active_solver(sender_id.clone())
+                        .then(
+                            Self::ext(env::current_account_id())
+                                .with_static_gas(GAS_FOR_RESOLVER_CALLBACK)
+                                .on_ft_resolver_checked(sender_id, args, amount),
+                        ),
+                );
+            }
+            assert!(self.cached_solvers.contains(&sender_id), "{}", ERR_RESOLVER_NOT_REGISTERED);
+        }
+
+        let unused = amount.0 - args.amount.0;
+        self.internal_create_htlc(sender_id, args);
+        PromiseOrValue::Value(U128(unused))
+    }
+
+    // Registry check for tokens locked with ft_transfer_call. A rejection
+    // returns the whole amount as unused, so the token refunds the sender.
+    #[private]
+    pub fn on_ft_resolver_checked(
+        &mut self,
+        sender: AccountId,
+        args: CreateHTLCArgs,
+        amount: U128,
+        #[callback_result] is_active: Result<bool, PromiseError>,
+    ) -> U128 {
+        let result = if self.resolver_allowed(&sender, is_active) {
+            self.check_create_args(&args, &args.token, amount.0)
+        } else {
+            Err(ERR_RESOLVER_NOT_REGISTERED)
+        };
+        if let Err(err) = result {
+            env::log_str(err);
+            return amount;
+        }
+
+        let unused = amount.0 - args.amount.0;
+        self.internal_create_htlc(sender, args);
+        U128(unused)
     }
 
     // Withdraw with secret
@@ -183,13 +412,7 @@ impl FusionPlusHTLC {
         self.htlcs.insert(&htlc_id, &htlc_updated);
 
         // Transfer funds
-        if token.is_none() {
-            // Transfer NEAR
-            Promise::new(receiver.clone()).transfer(NearToken::from_yoctonear(amount));
-        } else {
-            // Handle NEP-141 token transfer
-            panic!("NEP-141 tokens not yet implemented");
-        }
+        self.internal_payout(receiver.clone(), token.clone(), amount);
 
         // Emit events
         self.emit_event(EventLog {
@@ -197,10 +420,13 @@ impl FusionPlusHTLC {
             htlc_id: htlc_id.clone(),
             sender: None,
             receiver: None,
-            secret: Some(secret),
+            secret: Some(secret.clone()),
             amount: None,
+            token: None,
             hashlock: None,
             timelock: None,
+            order_hash: None,
+            signed_tx: None,
             timestamp: U128(env::block_timestamp() as u128),
         });
 
@@ -211,13 +437,22 @@ impl FusionPlusHTLC {
             receiver: Some(receiver.clone()),
             secret: None,
             amount: Some(U128(amount)),
+            token: token.clone(),
             hashlock: None,
             timelock: None,
+            order_hash: None,
+            signed_tx: None,
             timestamp: U128(env::block_timestamp() as u128),
         });
 
+        self.withdrawn_htlcs += 1;
+        self.unlock(&token, amount);
+        self.notify_swap_outcome(&htlc_updated, true);
+
         // Remove from active list
         self.active_htlc_ids.retain(|id| id != &htlc_id);
+
+        self.request_settlement(htlc_id, &htlc_updated, &secret.0);
     }
 
     // Refund after timeout
@@ -247,13 +482,10 @@ impl FusionPlusHTLC {
         htlc_updated.refunded = true;
         self.htlcs.insert(&htlc_id, &htlc_updated);
 
-        // Transfer funds back
-        if token.is_none() {
-            // Transfer NEAR
-            Promise::new(sender.clone()).transfer(NearToken::from_yoctonear(amount));
-        } else {
-            // Handle NEP-141 token transfer
-            panic!("NEP-141 tokens not yet implemented");
+        // Transfer funds back, with the unused settlement sign deposit
+        self.internal_payout(sender.clone(), token.clone(), amount);
+        if htlc.settlement_deposit > 0 {
+            self.internal_payout(sender.clone(), None, htlc.settlement_deposit);
         }
 
         // Emit event
@@ -264,16 +496,380 @@ impl FusionPlusHTLC {
             receiver: Some(receiver.clone()),
             secret: None,
             amount: Some(U128(amount)),
+            token: token.clone(),
             hashlock: None,
             timelock: None,
+            order_hash: None,
+            signed_tx: None,
             timestamp: U128(env::block_timestamp() as u128),
         });
 
+        self.refunded_htlcs += 1;
+        self.unlock(&token, amount);
+        self.notify_swap_outcome(&htlc_updated, false);
+
         // Remove from active list
         self.active_htlc_ids.retain(|id| id != &htlc_id);
     }
 
+    // Records a failed payout, e.g. to an account deleted before the transfer
+    // landed, as claimable by that account instead of leaving it stuck here
+    #[private]
+    pub fn on_transfer_complete(
+        &mut self,
+        account_id: AccountId,
+        token: Option<AccountId>,
+        amount: U128,
+        #[callback_result] result: Result<(), PromiseError>,
+    ) -> bool {
+        if result.is_ok() {
+            return true;
+        }
+
+        let key = (account_id.clone(), token.clone());
+        let claimable = self.claimable.get(&key).unwrap_or(0);
+        self.claimable.insert(&key, &(claimable + amount.0));
+
+        self.emit_event(EventLog {
+            event_type: "payout_failed".to_string(),
+            htlc_id: String::new(),
+            sender: None,
+            receiver: Some(account_id),
+            secret: None,
+            amount: Some(amount),
+            token,
+            hashlock: None,
+            timelock: None,
+            order_hash: None,
+            signed_tx: None,
+            timestamp: U128(env::block_timestamp() as u128),
+        });
+        false
+    }
+
+    // Pay out the caller's failed payouts again, e.g. once a deleted account
+    // has been created again or registered with the token
+    pub fn claim(&mut self, token: Option<AccountId>) -> Promise {
+        let account_id = env::predecessor_account_id();
+        let amount = self.claimable.remove(&(account_id.clone(), token.clone()))
+            .expect("Nothing to claim");
+        self.internal_payout(account_id, token, amount)
+    }
+
+    // Report a refunded HTLC whose counterparty leg the receiver had already
+    // funded. The registry opens a slash case against the HTLC sender; the
+    // evidence (e.g. the BASE escrow and funding tx) is checked during the
+    // registry's dispute window. The attached deposit is the reporter's bond,
+    // forfeited if the registry dismisses the case.
+    #[payable]
+    pub fn report_failed_swap(&mut self, htlc_id: String, evidence: String) -> Promise {
+        let htlc = self.htlcs.get(&htlc_id)
+            .expect("HTLC does not exist");
+        let registry = self.solver_registry.clone()
+            .expect("Solver registry not configured");
+
+        assert!(htlc.refunded, "HTLC was not refunded");
+        assert!(
+            env::predecessor_account_id() == htlc.receiver,
+            "Only receiver can report"
+        );
+        assert!(self.reported_htlcs.insert(&htlc_id), "HTLC already reported");
+        let bond = env::attached_deposit();
+
+        ext_solver_registry::ext(registry)
+            .with_static_gas(GAS_FOR_REPORT)
+            .with_attached_deposit(bond)
+            .report_misbehavior(htlc.sender, htlc_id.clone(), htlc.receiver.clone(), htlc.token, U128(htlc.amount), evidence)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_REPORT_CALLBACK)
+                    .on_failed_swap_reported(htlc_id, htlc.receiver, U128(bond.as_yoctonear())),
+            )
+    }
+
+    // Allow reporting again if the registry rejected the report. The bond
+    // comes back to this contract then and is returned to the reporter.
+    #[private]
+    pub fn on_failed_swap_reported(
+        &mut self,
+        htlc_id: String,
+        reporter: AccountId,
+        bond: U128,
+        #[callback_result] case_id: Result<u64, PromiseError>,
+    ) -> Option<u64> {
+        match case_id {
+            Ok(case_id) => Some(case_id),
+            Err(_) => {
+                self.reported_htlcs.remove(&htlc_id);
+                if bond.0 > 0 {
+                    Promise::new(reporter).transfer(NearToken::from_yoctonear(bond.0));
+                }
+                None
+            }
+        }
+    }
+
+    // Sign the BASE withdrawal again with the current settlement config, e.g.
+    // after a failed signature or to bump fees. The HTLC keeps its nonce, so
+    // the new transaction replaces the previous one. The caller attaches the
+    // sign deposit, and retries are spaced by the configured interval.
+    // Retrying a cancelled settlement re-signs the no-op transfer.
+    #[payable]
+    pub fn retry_settlement(&mut self, htlc_id: String) -> Promise {
+        let htlc = self.htlcs.get(&htlc_id)
+            .expect("HTLC does not exist");
+        let mut settlement = self.settlements.get(&htlc_id)
+            .expect("No settlement for HTLC");
+        let caller = env::predecessor_account_id();
+        assert!(
+            caller == self.owner || caller == htlc.receiver,
+            "Only owner or receiver can retry settlement"
+        );
+        let config = self.settlement_config.clone()
+            .expect("Settlement not configured");
+        assert!(
+            env::block_timestamp() >= settlement.requested_at.saturating_add(config.retry_interval),
+            "Settlement retried too soon"
+        );
+        let escrow = htlc.base_escrow.as_ref()
+            .expect("HTLC has no BASE escrow");
+        assert!(self.internal_is_approved_escrow(escrow), "BASE escrow is not approved");
+        let sign_deposit = self.charge_sign_deposit(&config);
+
+        settlement.requested_at = env::block_timestamp();
+        self.settlements.insert(&htlc_id, &settlement);
+        self.internal_sign_settlement(htlc_id, &htlc, sign_deposit)
+    }
+
+    // Last resort for a settlement that can be neither signed nor mined:
+    // signs a no-op transfer at its nonce so that later settlements are no
+    // longer held back. The taker then withdraws from the escrow on BASE
+    // with the public secret. The owner attaches the sign deposit.
+    #[payable]
+    pub fn cancel_settlement(&mut self, htlc_id: String) -> Promise {
+        self.assert_owner();
+        let htlc = self.htlcs.get(&htlc_id)
+            .expect("HTLC does not exist");
+        let mut settlement = self.settlements.get(&htlc_id)
+            .expect("No settlement for HTLC");
+        assert!(!settlement.cancelled, "Settlement already cancelled");
+        let config = self.settlement_config.clone()
+            .expect("Settlement not configured");
+        let sign_deposit = self.charge_sign_deposit(&config);
+
+        settlement.cancelled = true;
+        settlement.requested_at = env::block_timestamp();
+        self.settlements.insert(&htlc_id, &settlement);
+        self.internal_sign_settlement(htlc_id, &htlc, sign_deposit)
+    }
+
+    // Assembles the signed BASE transaction and publishes it for relayers
+    #[private]
+    pub fn on_settlement_signed(
+        &mut self,
+        htlc_id: String,
+        config: SettlementConfig,
+        #[callback_result] result: Result<SignResult, PromiseError>,
+    ) -> Option<String> {
+        let htlc = self.htlcs.get(&htlc_id)?;
+        let mut settlement = self.settlements.get(&htlc_id)?;
+        let escrow = htlc.base_escrow.as_ref()?;
+
+        let signed_tx = parse_sign_result(result).and_then(|signature| {
+            config
+                .settlement_tx(&settlement, escrow, &htlc)?
+                .encode_signed(&signature)
+        });
+        let signed_tx = match signed_tx {
+            Ok(signed_tx) => signed_tx,
+            Err(err) => {
+                env::log_str(err);
+                return None;
+            }
+        };
+
+        let signed_tx_hex = format!("0x{}", hex::encode(&signed_tx));
+        settlement.signed_tx = Some(signed_tx);
+        self.settlements.insert(&htlc_id, &settlement);
+
+        self.emit_event(EventLog {
+            event_type: "settlement_signed".to_string(),
+            htlc_id,
+            sender: None,
+            receiver: None,
+            secret: None,
+            amount: None,
+            token: None,
+            hashlock: None,
+            timelock: None,
+            order_hash: None,
+            signed_tx: Some(signed_tx_hex.clone()),
+            timestamp: U128(env::block_timestamp() as u128),
+        });
+
+        Some(signed_tx_hex)
+    }
+
+    // Post a Dutch auction order; the caller becomes the maker and pays for
+    // its storage. Any deposit above the storage cost is refunded.
+    #[payable]
+    pub fn create_auction_order(&mut self, args: CreateAuctionOrderArgs) {
+        let maker = env::predecessor_account_id();
+        let key = (maker.clone(), args.order_hash.0.clone());
+        assert!(
+            self.auction_orders.get(&key).is_none(),
+            "Auction order already exists"
+        );
+
+        let order_hash = args.order_hash.clone();
+        let order = args.into_order(maker.clone(), env::block_timestamp());
+        order.assert_valid();
+
+        let storage_before = env::storage_usage();
+        self.auction_orders.insert(&key, &order);
+        let storage_cost = env::storage_byte_cost()
+            .saturating_mul((env::storage_usage() - storage_before) as u128);
+        let deposit = env::attached_deposit();
+        assert!(
+            deposit >= storage_cost,
+            "Attach at least {} yoctoNEAR to cover auction order storage",
+            storage_cost.as_yoctonear()
+        );
+        let refund = deposit.saturating_sub(storage_cost);
+        if !refund.is_zero() {
+            Promise::new(maker).transfer(refund);
+        }
+
+        self.emit_event(EventLog {
+            event_type: "auction_order_created".to_string(),
+            htlc_id: String::new(),
+            sender: Some(order.maker.clone()),
+            receiver: None,
+            secret: None,
+            amount: Some(U128(order.start_amount)),
+            token: order.token.clone(),
+            hashlock: None,
+            timelock: None,
+            order_hash: Some(order_hash),
+            signed_tx: None,
+            timestamp: U128(env::block_timestamp() as u128),
+        });
+    }
+
+    // Remove an auction order and get its storage deposit back, either while
+    // unfilled or once the HTLC that filled it was withdrawn or refunded
+    pub fn cancel_auction_order(&mut self, order_hash: Base64VecU8) {
+        let maker = env::predecessor_account_id();
+        let key = (maker.clone(), order_hash.0);
+        let order = self.auction_orders.get(&key)
+            .expect("Auction order does not exist");
+        if let Some(htlc_id) = &order.filled_htlc_id {
+            let finalized = self
+                .htlcs
+                .get(htlc_id)
+                .is_none_or(|htlc| htlc.withdrawn || htlc.refunded);
+            assert!(finalized, "Auction order is filled by an active HTLC");
+        }
+
+        let storage_before = env::storage_usage();
+        self.auction_orders.remove(&key);
+        let released = env::storage_byte_cost()
+            .saturating_mul((storage_before - env::storage_usage()) as u128);
+        if !released.is_zero() {
+            Promise::new(maker).transfer(released);
+        }
+    }
+
+    // Owner configuration
+    pub fn set_solver_registry(&mut self, solver_registry: Option<AccountId>) {
+        self.assert_owner();
+        self.solver_registry = solver_registry;
+    }
+
+    pub fn set_resolver_exclusive(&mut self, enabled: bool) {
+        self.assert_owner();
+        self.resolver_exclusive = enabled;
+    }
+
+    // Seed or correct the allowlist used when the registry is unreachable
+    pub fn set_cached_solver(&mut self, account_id: AccountId, allowed: bool) {
+        self.assert_owner();
+        if allowed {
+            self.cached_solvers.insert(&account_id);
+        } else {
+            self.cached_solvers.remove(&account_id);
+        }
+    }
+
+    pub fn set_settlement_config(&mut self, config: Option<SettlementConfig>) {
+        self.assert_owner();
+        self.settlement_config = config;
+    }
+
+    pub fn set_approved_escrow(&mut self, escrow: String, approved: bool) {
+        self.assert_owner();
+        let address = parse_address(&escrow).unwrap_or_else(|err| env::panic_str(err));
+        if approved {
+            self.approved_escrows.insert(&address.to_vec());
+        } else {
+            self.approved_escrows.remove(&address.to_vec());
+        }
+    }
+
+    // Resync with the on-chain nonce of the settlement address
+    pub fn set_settlement_nonce(&mut self, nonce: u64) {
+        self.assert_owner();
+        self.next_settlement_nonce = nonce;
+    }
+
     // View methods
+    pub fn get_settlement_config(&self) -> Option<SettlementConfig> {
+        self.settlement_config.clone()
+    }
+
+    pub fn is_approved_escrow(&self, escrow: String) -> bool {
+        parse_address(&escrow)
+            .map(|address| self.approved_escrows.contains(&address.to_vec()))
+            .unwrap_or(false)
+    }
+
+    pub fn get_settlement(&self, htlc_id: String) -> Option<SettlementView> {
+        self.settlements.get(&htlc_id).map(|settlement| SettlementView {
+            htlc_id,
+            nonce: settlement.nonce,
+            signed_tx: settlement.signed_tx.map(|tx| format!("0x{}", hex::encode(tx))),
+            cancelled: settlement.cancelled,
+        })
+    }
+
+    pub fn get_claimable(&self, account_id: AccountId, token: Option<AccountId>) -> U128 {
+        U128(self.claimable.get(&(account_id, token)).unwrap_or(0))
+    }
+
+    pub fn get_solver_registry(&self) -> Option<AccountId> {
+        self.solver_registry.clone()
+    }
+
+    pub fn is_resolver_exclusive(&self) -> bool {
+        self.resolver_exclusive
+    }
+
+    pub fn is_cached_solver(&self, account_id: AccountId) -> bool {
+        self.cached_solvers.contains(&account_id)
+    }
+
+    pub fn get_auction_order(&self, maker: AccountId, order_hash: Base64VecU8) -> Option<AuctionOrderView> {
+        self.auction_orders
+            .get(&(maker, order_hash.0))
+            .map(|order| order.to_view(env::block_timestamp()))
+    }
+
+    pub fn get_auction_price(&self, maker: AccountId, order_hash: Base64VecU8) -> Option<U128> {
+        self.auction_orders
+            .get(&(maker, order_hash.0))
+            .map(|order| U128(order.current_amount(env::block_timestamp())))
+    }
+
     pub fn get_htlc(&self, htlc_id: String) -> Option<HTLCView> {
         self.htlcs.get(&htlc_id).map(|htlc| HTLCView {
             htlc_id,
@@ -287,6 +883,8 @@ impl FusionPlusHTLC {
             withdrawn: htlc.withdrawn,
             refunded: htlc.refunded,
             created_at: U128(htlc.created_at as u128),
+            base_escrow: htlc.base_escrow,
+            settlement_deposit: U128(htlc.settlement_deposit),
         })
     }
 
@@ -310,75 +908,397 @@ impl FusionPlusHTLC {
         self.owner.clone()
     }
 
-    pub fn get_info(&self) -> String {
-        format!(
-            r#"{{"owner":"{}","version":"2.0.0","total_htlcs":{},"active_htlcs":{}}}"#,
-            self.owner,
-            self.htlcs.len(),
-            self.active_htlc_ids.len()
-        )
+    pub fn get_info(&self) -> ContractInfo {
+        ContractInfo {
+            owner: self.owner.clone(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            total_htlcs: self.htlcs.len(),
+            active_htlcs: self.active_htlc_ids.len() as u64,
+            config: ContractConfig {
+                solver_registry: self.solver_registry.clone(),
+                resolver_exclusive: self.resolver_exclusive,
+                settlement_config: self.settlement_config.clone(),
+            },
+        }
     }
 
-    pub fn get_stats(&self) -> String {
-        format!(
-            r#"{{"owner":"{}","version":"2.0.0","total_htlcs":{},"active_htlcs":{}}}"#,
-            self.owner,
-            self.htlcs.len(),
-            self.active_htlc_ids.len()
-        )
+    pub fn get_stats(&self) -> ContractStats {
+        ContractStats {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            total_htlcs: self.htlcs.len(),
+            active_htlcs: self.active_htlc_ids.len() as u64,
+            withdrawn_htlcs: self.withdrawn_htlcs,
+            refunded_htlcs: self.refunded_htlcs,
+            locked: self
+                .locked_balances
+                .iter()
+                .map(|(token, amount)| TokenAmount {
+                    token,
+                    amount: U128(amount),
+                })
+                .collect(),
+        }
     }
 
     // Internal helpers
-    fn emit_event(&self, event: EventLog) {
-        env::log_str(&format!(
-            "EVENT_JSON:{}",
-            near_sdk::serde_json::to_string(&event).unwrap()
-        ));
-    }
-}
 
-// Tests module
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use near_sdk::test_utils::{accounts, VMContextBuilder};
-    use near_sdk::{testing_env, VMContext};
+    // `paid` is what the caller sent in `token`: the attached deposit for NEAR
+    // (None), or the amount received in ft_on_transfer
+    fn check_create_args(
+        &self,
+        args: &CreateHTLCArgs,
+        token: &Option<AccountId>,
+        paid: Balance,
+    ) -> Result<(), &'static str> {
+        let amount: Balance = args.amount.0;
 
-    fn get_context(predecessor_account_id: AccountId) -> VMContext {
-        VMContextBuilder::new()
-            .current_account_id(accounts(0))
-            .signer_account_id(predecessor_account_id.clone())
-            .predecessor_account_id(predecessor_account_id)
-            .build()
-    }
+        // Validate inputs
+        if amount == 0 {
+            return Err("Amount must be positive");
+        }
+        if args.timelock.0 as u64 <= env::block_timestamp() {
+            return Err("Timelock must be in future");
+        }
+        if args.hashlock.0.len() != 32 {
+            return Err("Hashlock must be 32 bytes (SHA-256)");
+        }
 
-    #[test]
-    fn test_new() {
-        let context = get_context(accounts(1));
-        testing_env!(context);
-        let contract = FusionPlusHTLC::new(accounts(1));
-        assert_eq!(contract.get_owner(), accounts(1));
+        if token.is_none() {
+            if args.token.is_some() {
+                return Err("Lock NEP-141 tokens with ft_transfer_call");
+            }
+            if paid < amount {
+                return Err("Attached deposit must match amount for NEAR");
+            }
+            if paid - amount < self.settlement_deposit(args) {
+                return Err("Attach the settlement sign deposit on top of the amount");
+            }
+        } else {
+            if args.token != *token {
+                return Err("Token does not match the transferred token");
+            }
+            if paid < amount {
+                return Err("Transferred amount below HTLC amount");
+            }
+        }
+
+        if let Some(escrow) = &args.base_escrow {
+            // The sign deposit is prepaid in NEAR, which ft_transfer_call
+            // cannot carry
+            if args.token.is_some() {
+                return Err("BASE settlement is only supported for NEAR HTLCs");
+            }
+            escrow.assert_valid()?;
+            if args.order_hash.0.len() != 32 {
+                return Err("Order hash must be 32 bytes for BASE settlement");
+            }
+            if !self.internal_is_approved_escrow(escrow) {
+                return Err("BASE escrow is not approved");
+            }
+        }
+
+        // Orders the receiver posted as Dutch auctions only accept fills at
+        // or above the current point of the auction curve
+        let key = (args.receiver.clone(), args.order_hash.0.clone());
+        if let Some(order) = self.auction_orders.get(&key) {
+            if order.filled_htlc_id.is_some() {
+                return Err("Auction order already filled");
+            }
+            if args.token != order.token {
+                return Err("Token does not match auction order");
+            }
+            if amount < order.current_amount(env::block_timestamp()) {
+                return Err("Fill amount below current auction price");
+            }
+        }
+
+        Ok(())
     }
 
-    #[test]
-    fn test_create_htlc() {
-        let mut context = get_context(accounts(1));
-        context.attached_deposit = 1_000_000_000_000_000_000_000_000; // 1 NEAR
-        testing_env!(context);
-        
-        let mut contract = FusionPlusHTLC::new(accounts(0));
-        
-        let hashlock = Base64VecU8(vec![0u8; 32]); // Mock hashlock
-        let args = CreateHTLCArgs {
+    // Stores a validated HTLC; callers must run check_create_args first
+    fn internal_create_htlc(&mut self, sender: AccountId, args: CreateHTLCArgs) -> String {
+        let htlc_id = format!("htlc_{}", self.next_htlc_id);
+        self.next_htlc_id += 1;
+
+        let amount: Balance = args.amount.0;
+        let timelock: Timestamp = args.timelock.0 as u64;
+
+        let key = (args.receiver.clone(), args.order_hash.0.clone());
+        if let Some(mut order) = self.auction_orders.get(&key) {
+            order.filled_htlc_id = Some(htlc_id.clone());
+            self.auction_orders.insert(&key, &order);
+        }
+
+        let settlement_deposit = self.settlement_deposit(&args);
+        let htlc = HTLC {
+            sender,
+            receiver: args.receiver.clone(),
+            token: args.token,
+            amount,
+            hashlock: args.hashlock.clone(),
+            timelock,
+            order_hash: args.order_hash.clone(),
+            withdrawn: false,
+            refunded: false,
+            created_at: env::block_timestamp(),
+            base_escrow: args.base_escrow,
+            settlement_deposit,
+        };
+
+        self.htlcs.insert(&htlc_id, &htlc);
+        self.active_htlc_ids.push(htlc_id.clone());
+        let locked = self.locked_balances.get(&htlc.token).unwrap_or(0);
+        self.locked_balances.insert(&htlc.token, &(locked + amount));
+
+        // Emit event
+        self.emit_event(EventLog {
+            event_type: "htlc_created".to_string(),
+            htlc_id: htlc_id.clone(),
+            sender: Some(htlc.sender.clone()),
+            receiver: Some(htlc.receiver.clone()),
+            secret: None,
+            amount: Some(U128(amount)),
+            token: htlc.token.clone(),
+            hashlock: Some(args.hashlock),
+            timelock: Some(U128(timelock as u128)),
+            order_hash: Some(args.order_hash),
+            signed_tx: None,
+            timestamp: U128(env::block_timestamp() as u128),
+        });
+
+        htlc_id
+    }
+
+    // Failed transfers end up claimable through on_transfer_complete
+    fn internal_payout(&self, account_id: AccountId, token: Option<AccountId>, amount: Balance) -> Promise {
+        let transfer = match &token {
+            None => Promise::new(account_id.clone()).transfer(NearToken::from_yoctonear(amount)),
+            Some(token) => ext_ft::ext(token.clone())
+                .with_static_gas(GAS_FOR_FT_TRANSFER)
+                .with_attached_deposit(NearToken::from_yoctonear(1))
+                .ft_transfer(account_id.clone(), U128(amount), None),
+        };
+        transfer.then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(GAS_FOR_TRANSFER_CALLBACK)
+                .on_transfer_complete(account_id, token, U128(amount)),
+        )
+    }
+
+    // Updates the cached allowlist from a registry answer, or falls back to
+    // it when the registry is unreachable
+    fn resolver_allowed(&mut self, sender: &AccountId, is_active: Result<bool, PromiseError>) -> bool {
+        match is_active {
+            Ok(true) => {
+                self.cached_solvers.insert(sender);
+                true
+            }
+            Ok(false) => {
+                self.cached_solvers.remove(sender);
+                false
+            }
+            Err(_) => self.cached_solvers.contains(sender),
+        }
+    }
+
+    // Release a finalized HTLC's amount from the locked totals
+    fn unlock(&mut self, token: &Option<AccountId>, amount: Balance) {
+        let remaining = self.locked_balances.get(token).unwrap_or(0) - amount;
+        if remaining == 0 {
+            self.locked_balances.remove(token);
+        } else {
+            self.locked_balances.insert(token, &remaining);
+        }
+    }
+
+    // Feed the registry's reputation counters; fire-and-forget since a failed
+    // notification must not block the payout
+    fn notify_swap_outcome(&self, htlc: &HTLC, completed: bool) {
+        if let Some(registry) = self.solver_registry.clone() {
+            ext_solver_registry::ext(registry)
+                .with_static_gas(GAS_FOR_OUTCOME)
+                .record_swap_outcome(
+                    htlc.sender.clone(),
+                    htlc.receiver.clone(),
+                    htlc.token.clone(),
+                    U128(htlc.amount),
+                    completed,
+                    U128((env::block_timestamp() - htlc.created_at) as u128),
+                );
+        }
+    }
+
+    // Sign deposit the sender prepays with an HTLC that settles on BASE
+    fn settlement_deposit(&self, args: &CreateHTLCArgs) -> Balance {
+        match (&self.settlement_config, &args.base_escrow) {
+            (Some(config), Some(_)) => config.sign_deposit.0,
+            _ => 0,
+        }
+    }
+
+    // Contract-controlled settlement: once the secret is public here, request
+    // an MPC signature for the counterpart BASE withdrawal with the prepaid
+    // sign deposit, taking the HTLC's nonce for good. Never fails the
+    // withdraw; if no settlement is requested the deposit goes back to the
+    // sender.
+    fn request_settlement(&mut self, htlc_id: String, htlc: &HTLC, secret: &[u8]) {
+        let Some(escrow) = &htlc.base_escrow else {
+            return;
+        };
+        let skipped = if self.settlement_config.is_none() {
+            Some("Settlement not configured")
+        } else if !self.internal_is_approved_escrow(escrow) {
+            Some("BASE escrow is not approved")
+        } else if htlc.settlement_deposit == 0 {
+            Some("No settlement sign deposit prepaid")
+        } else {
+            None
+        };
+        let secret = match (skipped, secret.try_into()) {
+            (None, Ok(secret)) => secret,
+            (skipped, _) => {
+                env::log_str(skipped.unwrap_or("Secret must be 32 bytes for BASE settlement"));
+                if htlc.settlement_deposit > 0 {
+                    self.internal_payout(htlc.sender.clone(), None, htlc.settlement_deposit);
+                }
+                return;
+            }
+        };
+
+        let settlement = Settlement {
+            nonce: self.next_settlement_nonce,
+            secret,
+            signed_tx: None,
+            requested_at: env::block_timestamp(),
+            cancelled: false,
+        };
+        self.next_settlement_nonce += 1;
+        self.settlements.insert(&htlc_id, &settlement);
+
+        let sign_deposit = NearToken::from_yoctonear(htlc.settlement_deposit);
+        self.internal_sign_settlement(htlc_id, htlc, sign_deposit);
+    }
+
+    fn internal_is_approved_escrow(&self, escrow: &BaseEscrow) -> bool {
+        escrow
+            .escrow_address()
+            .map(|address| self.approved_escrows.contains(&address.to_vec()))
+            .unwrap_or(false)
+    }
+
+    fn internal_sign_settlement(&self, htlc_id: String, htlc: &HTLC, sign_deposit: NearToken) -> Promise {
+        let config = self.settlement_config.clone()
+            .expect("Settlement not configured");
+        let settlement = self.settlements.get(&htlc_id)
+            .expect("No settlement for HTLC");
+        let escrow = htlc.base_escrow.as_ref()
+            .expect("HTLC has no BASE escrow");
+        let tx = config
+            .settlement_tx(&settlement, escrow, htlc)
+            .unwrap_or_else(|err| env::panic_str(err));
+
+        SignRequest::new(tx.signing_hash(), config.path.clone(), config.key_version)
+            .send(config.mpc_signer.clone(), sign_deposit, GAS_FOR_SIGN)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_SETTLEMENT_CALLBACK)
+                    .on_settlement_signed(htlc_id, config),
+            )
+    }
+
+    // Takes the sign deposit for a retry or cancel from the attached deposit
+    // and refunds the rest to the caller
+    fn charge_sign_deposit(&self, config: &SettlementConfig) -> NearToken {
+        let sign_deposit = NearToken::from_yoctonear(config.sign_deposit.0);
+        let deposit = env::attached_deposit();
+        assert!(deposit >= sign_deposit, "Attach the settlement sign deposit");
+        let refund = deposit.saturating_sub(sign_deposit);
+        if !refund.is_zero() {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+        sign_deposit
+    }
+
+    fn assert_owner(&self) {
+        assert!(
+            env::predecessor_account_id() == self.owner,
+            "Only owner can call this method"
+        );
+    }
+
+    fn emit_event(&self, event: EventLog) {
+        env::log_str(&format!(
+            "EVENT_JSON:{}",
+            near_sdk::serde_json::to_string(&event).unwrap()
+        ));
+    }
+}
+
+// Tests module
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, get_created_receipts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, VMContext};
+
+    fn get_context(predecessor_account_id: AccountId) -> VMContext {
+        VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .signer_account_id(predecessor_account_id.clone())
+            .predecessor_account_id(predecessor_account_id)
+            .build()
+    }
+
+    fn unwrap_htlc_id(result: PromiseOrValue<String>) -> String {
+        match result {
+            PromiseOrValue::Value(htlc_id) => htlc_id,
+            PromiseOrValue::Promise(_) => panic!("Expected HTLC to be created synchronously"),
+        }
+    }
+
+    #[test]
+    fn test_new() {
+        let context = get_context(accounts(1));
+        testing_env!(context);
+        let contract = FusionPlusHTLC::new(accounts(1));
+        assert_eq!(contract.get_owner(), accounts(1));
+    }
+
+    #[test]
+    fn test_contract_source_metadata() {
+        let metadata: serde_json::Value = serde_json::from_str(CONTRACT_SOURCE_METADATA).unwrap();
+        assert_eq!(metadata["version"], env!("CARGO_PKG_VERSION"));
+        assert!(metadata["standards"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|standard| standard["standard"] == "nep330"));
+        if let Some(abi_sha256) = option_env!("NEP330_ABI_SHA256_FUSION_PLUS_HTLC") {
+            let link = metadata["link"].as_str().unwrap();
+            assert!(link.ends_with(&format!("#abi-sha256={abi_sha256}")));
+        }
+    }
+
+    #[test]
+    fn test_create_htlc() {
+        let mut context = get_context(accounts(1));
+        context.attached_deposit = NearToken::from_near(1); // 1 NEAR
+        testing_env!(context);
+        
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        
+        let hashlock = Base64VecU8(vec![0u8; 32]); // Mock hashlock
+        let args = CreateHTLCArgs {
             receiver: accounts(2),
             token: None,
             amount: U128(1_000_000_000_000_000_000_000_000),
             hashlock: hashlock.clone(),
-            timelock: U128(env::block_timestamp() + 3600_000_000_000), // 1 hour
+            timelock: U128(env::block_timestamp() as u128 + 3_600_000_000_000), // 1 hour
             order_hash: Base64VecU8(vec![1u8; 32]),
+            base_escrow: None,
         };
         
-        let htlc_id = contract.create_htlc(args);
+        let htlc_id = unwrap_htlc_id(contract.create_htlc(args));
         assert_eq!(htlc_id, "htlc_1");
         
         let htlc = contract.get_htlc(htlc_id).unwrap();
@@ -387,4 +1307,681 @@ mod tests {
         assert!(!htlc.withdrawn);
         assert!(!htlc.refunded);
     }
-}
\ No newline at end of file
+
+    fn auction_args(order_hash: Base64VecU8) -> CreateAuctionOrderArgs {
+        CreateAuctionOrderArgs {
+            order_hash,
+            token: None,
+            start_amount: U128(2_000),
+            end_amount: U128(1_000),
+            start_time: Some(U128(0)),
+            duration: U128(1_000),
+            points: vec![],
+        }
+    }
+
+    fn fill_args(order_hash: Base64VecU8, amount: u128) -> CreateHTLCArgs {
+        CreateHTLCArgs {
+            receiver: accounts(1),
+            token: None,
+            amount: U128(amount),
+            hashlock: Base64VecU8(vec![0u8; 32]),
+            timelock: U128(3_600_000_000_000),
+            order_hash,
+            base_escrow: None,
+        }
+    }
+
+    // Posts auction_args as `maker`, paying for storage
+    fn post_auction_order(contract: &mut FusionPlusHTLC, maker: AccountId, args: CreateAuctionOrderArgs) {
+        let mut context = get_context(maker);
+        context.attached_deposit = NearToken::from_millinear(100);
+        testing_env!(context);
+        contract.create_auction_order(args);
+    }
+
+    #[test]
+    fn test_auction_fill_at_current_price() {
+        testing_env!(get_context(accounts(1)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        let order_hash = Base64VecU8(vec![7u8; 32]);
+        post_auction_order(&mut contract, accounts(1), auction_args(order_hash.clone()));
+
+        let mut context = get_context(accounts(2));
+        context.block_timestamp = 250;
+        context.attached_deposit = NearToken::from_yoctonear(1_750);
+        testing_env!(context);
+        assert_eq!(contract.get_auction_price(accounts(1), order_hash.clone()), Some(U128(1_750)));
+
+        let htlc_id = unwrap_htlc_id(contract.create_htlc(fill_args(order_hash.clone(), 1_750)));
+        let order = contract.get_auction_order(accounts(1), order_hash).unwrap();
+        assert_eq!(order.filled_htlc_id, Some(htlc_id));
+    }
+
+    #[test]
+    #[should_panic(expected = "Fill amount below current auction price")]
+    fn test_auction_fill_below_price_rejected() {
+        testing_env!(get_context(accounts(1)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        let order_hash = Base64VecU8(vec![7u8; 32]);
+        post_auction_order(&mut contract, accounts(1), auction_args(order_hash.clone()));
+
+        // Someone else posting a cheaper order under the same hash does not
+        // replace the maker's order
+        let mut cheap = auction_args(order_hash.clone());
+        cheap.start_amount = U128(1);
+        cheap.end_amount = U128(1);
+        post_auction_order(&mut contract, accounts(3), cheap);
+
+        let mut context = get_context(accounts(2));
+        context.block_timestamp = 250;
+        context.attached_deposit = NearToken::from_yoctonear(1_749);
+        testing_env!(context);
+        contract.create_htlc(fill_args(order_hash, 1_749));
+    }
+
+    #[test]
+    #[should_panic(expected = "to cover auction order storage")]
+    fn test_auction_order_requires_storage_deposit() {
+        testing_env!(get_context(accounts(1)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        contract.create_auction_order(auction_args(Base64VecU8(vec![7u8; 32])));
+    }
+
+    // Auction order of accounts(1) filled by an HTLC from accounts(2)
+    fn filled_auction_order(order_hash: &Base64VecU8) -> (FusionPlusHTLC, String) {
+        testing_env!(get_context(accounts(1)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        post_auction_order(&mut contract, accounts(1), auction_args(order_hash.clone()));
+
+        let mut context = get_context(accounts(2));
+        context.attached_deposit = NearToken::from_yoctonear(2_000);
+        testing_env!(context);
+        let htlc_id = unwrap_htlc_id(contract.create_htlc(fill_args(order_hash.clone(), 2_000)));
+        (contract, htlc_id)
+    }
+
+    #[test]
+    #[should_panic(expected = "Auction order is filled by an active HTLC")]
+    fn test_cancel_filled_auction_order_rejected() {
+        let order_hash = Base64VecU8(vec![7u8; 32]);
+        let (mut contract, _) = filled_auction_order(&order_hash);
+        testing_env!(get_context(accounts(1)));
+        contract.cancel_auction_order(order_hash);
+    }
+
+    #[test]
+    fn test_cancel_auction_order_after_refund() {
+        let order_hash = Base64VecU8(vec![7u8; 32]);
+        let (mut contract, htlc_id) = filled_auction_order(&order_hash);
+
+        let mut context = get_context(accounts(2));
+        context.block_timestamp = 3_600_000_000_000;
+        testing_env!(context);
+        contract.refund(htlc_id);
+
+        // The order is gone and its storage deposit goes back to the maker
+        testing_env!(get_context(accounts(1)));
+        contract.cancel_auction_order(order_hash.clone());
+        assert!(contract.get_auction_order(accounts(1), order_hash).is_none());
+        assert!(get_created_receipts().iter().any(|r| r.receiver_id == accounts(1)));
+    }
+
+    fn resolver_contract() -> FusionPlusHTLC {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        contract.set_resolver_exclusive(true);
+        contract
+    }
+
+    #[test]
+    #[should_panic(expected = "Resolver is not an active registered solver")]
+    fn test_resolver_exclusive_rejects_unknown_resolver() {
+        let mut contract = resolver_contract();
+
+        let mut context = get_context(accounts(2));
+        context.attached_deposit = NearToken::from_yoctonear(1_000);
+        testing_env!(context);
+        contract.create_htlc(fill_args(Base64VecU8(vec![1u8; 32]), 1_000));
+    }
+
+    #[test]
+    fn test_resolver_exclusive_uses_cached_allowlist() {
+        let mut contract = resolver_contract();
+        contract.set_cached_solver(accounts(2), true);
+
+        let mut context = get_context(accounts(2));
+        context.attached_deposit = NearToken::from_yoctonear(1_000);
+        testing_env!(context);
+        let htlc_id = unwrap_htlc_id(contract.create_htlc(fill_args(Base64VecU8(vec![1u8; 32]), 1_000)));
+        assert_eq!(contract.get_htlc(htlc_id).unwrap().sender, accounts(2));
+    }
+
+    #[test]
+    fn test_resolver_check_callback() {
+        let mut contract = resolver_contract();
+        contract.set_cached_solver(accounts(2), true);
+
+        // Registry says the solver is no longer active: refund and drop from cache
+        testing_env!(get_context(accounts(0)));
+        let result = contract.on_resolver_checked(
+            accounts(2),
+            fill_args(Base64VecU8(vec![1u8; 32]), 1_000),
+            U128(1_000),
+            Ok(false),
+        );
+        assert!(matches!(result, PromiseOrValue::Promise(_)));
+        assert!(!contract.is_cached_solver(accounts(2)));
+        // The refund, then the receipt failing the call
+        drop(result);
+        assert_eq!(get_created_receipts().len(), 2);
+
+        // Registry unreachable: fall back to the cached allowlist
+        contract.set_cached_solver(accounts(3), true);
+        let result = contract.on_resolver_checked(
+            accounts(3),
+            fill_args(Base64VecU8(vec![2u8; 32]), 1_000),
+            U128(1_000),
+            Err(PromiseError::Failed),
+        );
+        assert_eq!(unwrap_htlc_id(result), "htlc_1");
+    }
+
+    #[test]
+    #[should_panic(expected = "Resolver is not an active registered solver")]
+    fn test_rejected_resolver_call_fails() {
+        let contract = resolver_contract();
+        contract.on_create_rejected(ERR_RESOLVER_NOT_REGISTERED.to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "HTLC was not refunded")]
+    fn test_report_requires_refund() {
+        let mut context = get_context(accounts(2));
+        context.attached_deposit = NearToken::from_yoctonear(1_000);
+        testing_env!(context);
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        let htlc_id = unwrap_htlc_id(contract.create_htlc(fill_args(Base64VecU8(vec![1u8; 32]), 1_000)));
+
+        testing_env!(get_context(accounts(0)));
+        contract.set_solver_registry(Some(accounts(3)));
+        testing_env!(get_context(accounts(1)));
+        contract.report_failed_swap(htlc_id, "base:0xescrow".to_string());
+    }
+
+    #[test]
+    fn test_report_failed_swap_once() {
+        let mut context = get_context(accounts(2));
+        context.attached_deposit = NearToken::from_yoctonear(1_000);
+        testing_env!(context);
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        let htlc_id = unwrap_htlc_id(contract.create_htlc(fill_args(Base64VecU8(vec![1u8; 32]), 1_000)));
+
+        let mut context = get_context(accounts(2));
+        context.block_timestamp = 3_600_000_000_000;
+        testing_env!(context);
+        contract.refund(htlc_id.clone());
+
+        testing_env!(get_context(accounts(0)));
+        contract.set_solver_registry(Some(accounts(3)));
+        testing_env!(get_context(accounts(1)));
+        contract.report_failed_swap(htlc_id.clone(), "base:0xescrow".to_string());
+
+        // A rejected report can be retried, and its bond is returned
+        contract.on_failed_swap_reported(htlc_id.clone(), accounts(1), U128(5), Err(PromiseError::Failed));
+        assert_eq!(get_created_receipts().last().unwrap().receiver_id, accounts(1));
+        contract.report_failed_swap(htlc_id, "base:0xescrow".to_string());
+    }
+
+    #[test]
+    fn test_withdraw_notifies_registry() {
+        let secret = b"fusion_secret".to_vec();
+        let hashlock = Keccak256::digest(&secret).to_vec();
+
+        let mut context = get_context(accounts(2));
+        context.attached_deposit = NearToken::from_yoctonear(1_000);
+        testing_env!(context);
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        let mut args = fill_args(Base64VecU8(vec![1u8; 32]), 1_000);
+        args.hashlock = Base64VecU8(hashlock);
+        let htlc_id = unwrap_htlc_id(contract.create_htlc(args));
+
+        testing_env!(get_context(accounts(0)));
+        contract.set_solver_registry(Some(accounts(3)));
+        testing_env!(get_context(accounts(1)));
+        contract.withdraw(htlc_id, Base64VecU8(secret));
+
+        // Payout to the receiver and its callback, plus the outcome report to
+        // the registry
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 3);
+        assert!(receipts.iter().any(|r| r.receiver_id == accounts(3)));
+    }
+
+    const SIGN_DEPOSIT: u128 = 250_000_000_000_000_000_000_000;
+
+    fn settlement_config() -> SettlementConfig {
+        SettlementConfig {
+            mpc_signer: "v1.signer-prod.testnet".parse().unwrap(),
+            path: "base-1".to_string(),
+            key_version: 0,
+            chain_id: 84532,
+            gas_limit: 150_000,
+            max_fee_per_gas: U128(1_500_000_000),
+            max_priority_fee_per_gas: U128(1_000_000),
+            sign_deposit: U128(SIGN_DEPOSIT),
+            retry_interval: 60_000_000_000,
+        }
+    }
+
+    fn base_escrow() -> BaseEscrow {
+        BaseEscrow {
+            escrow: format!("0x{}", "11".repeat(20)),
+            maker: format!("0x{}", "10".repeat(20)),
+            taker: format!("0x{}", "20".repeat(20)),
+            token: format!("0x{}", "00".repeat(20)),
+            amount: U128(1_000_000_000_000_000_000),
+            safety_deposit: U128(10_000_000_000_000_000),
+            timelocks: "00".repeat(32),
+        }
+    }
+
+    // HTLC with an approved BASE counterpart, created by accounts(2) for
+    // accounts(1) with the sign deposit prepaid if settlement is configured
+    fn settlement_htlc(contract: &mut FusionPlusHTLC, secret: &[u8]) -> String {
+        testing_env!(get_context(accounts(0)));
+        contract.set_approved_escrow(base_escrow().escrow, true);
+        let prepaid = contract.get_settlement_config().map_or(0, |config| config.sign_deposit.0);
+        let mut context = get_context(accounts(2));
+        context.attached_deposit = NearToken::from_yoctonear(1_000 + prepaid);
+        testing_env!(context);
+        let mut args = fill_args(Base64VecU8(vec![1u8; 32]), 1_000);
+        args.hashlock = Base64VecU8(Keccak256::digest(secret).to_vec());
+        args.base_escrow = Some(base_escrow());
+        unwrap_htlc_id(contract.create_htlc(args))
+    }
+
+    // Context for accounts(1) retrying with a deposit
+    fn receiver_context(deposit: u128, timestamp: u64) -> VMContext {
+        let mut context = get_context(accounts(1));
+        context.attached_deposit = NearToken::from_yoctonear(deposit);
+        context.block_timestamp = timestamp;
+        context
+    }
+
+    #[test]
+    fn test_withdraw_requests_base_settlement() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        contract.set_settlement_config(Some(settlement_config()));
+        contract.set_settlement_nonce(7);
+        let secret = vec![0x5e; 32];
+        let htlc_id = settlement_htlc(&mut contract, &secret);
+
+        // The receiver attaches nothing; the sender prepaid the signature
+        testing_env!(get_context(accounts(1)));
+        contract.withdraw(htlc_id.clone(), Base64VecU8(secret));
+        assert!(get_created_receipts()
+            .iter()
+            .any(|r| r.receiver_id.as_str() == "v1.signer-prod.testnet"));
+        let settlement = contract.get_settlement(htlc_id.clone()).unwrap();
+        assert_eq!(settlement.nonce, 7);
+        assert_eq!(settlement.signed_tx, None);
+
+        let result = SignResult {
+            big_r: chain_signatures::signatures::AffinePoint {
+                affine_point: "0208b2a8c29506cdf27fe61b47f6f0852e0ad0abc1fcb50ebce19d2fd9eed93ed7"
+                    .to_string(),
+            },
+            s: chain_signatures::signatures::Scalar {
+                scalar: "32a6d94b368afa2ab2079ce0acc59de847ceeec59aca748c16d0b5ba1462227b"
+                    .to_string(),
+            },
+            recovery_id: 0,
+        };
+        let signed_tx = contract
+            .on_settlement_signed(htlc_id.clone(), settlement_config(), Ok(result))
+            .unwrap();
+        assert!(signed_tx.starts_with("0x02"));
+        // Escrow address and withdraw selector are in the transaction
+        assert!(signed_tx.contains(&"11".repeat(20)));
+        assert!(signed_tx.contains("23305703"));
+        assert!(get_logs().iter().any(|log| log.contains("settlement_signed")));
+        assert_eq!(contract.get_settlement(htlc_id).unwrap().signed_tx, Some(signed_tx));
+    }
+
+    #[test]
+    fn test_failed_settlement_signature() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        contract.set_settlement_config(Some(settlement_config()));
+        let secret = vec![0x5e; 32];
+        let htlc_id = settlement_htlc(&mut contract, &secret);
+
+        testing_env!(get_context(accounts(1)));
+        contract.withdraw(htlc_id.clone(), Base64VecU8(secret.clone()));
+        let result = contract.on_settlement_signed(
+            htlc_id.clone(),
+            settlement_config(),
+            Err(PromiseError::Failed),
+        );
+        assert_eq!(result, None);
+        assert!(get_logs().contains(&"Signature request failed".to_string()));
+
+        // Once the retry interval has passed, the receiver can request a new
+        // signature for the same nonce without taking a new one
+        testing_env!(receiver_context(SIGN_DEPOSIT, settlement_config().retry_interval));
+        drop(contract.retry_settlement(htlc_id.clone()));
+        assert!(get_created_receipts()
+            .iter()
+            .any(|r| r.receiver_id.as_str() == "v1.signer-prod.testnet"));
+        assert_eq!(contract.get_settlement(htlc_id).unwrap().nonce, 0);
+
+        let next_id = settlement_htlc(&mut contract, &secret);
+        testing_env!(get_context(accounts(1)));
+        contract.withdraw(next_id.clone(), Base64VecU8(secret));
+        assert_eq!(contract.get_settlement(next_id).unwrap().nonce, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Settlement retried too soon")]
+    fn test_settlement_retry_rate_limited() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        contract.set_settlement_config(Some(settlement_config()));
+        let secret = vec![0x5e; 32];
+        let htlc_id = settlement_htlc(&mut contract, &secret);
+
+        testing_env!(get_context(accounts(1)));
+        contract.withdraw(htlc_id.clone(), Base64VecU8(secret));
+        testing_env!(receiver_context(SIGN_DEPOSIT, 1_000_000_000));
+        contract.retry_settlement(htlc_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attach the settlement sign deposit")]
+    fn test_settlement_retry_needs_deposit() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        contract.set_settlement_config(Some(settlement_config()));
+        let secret = vec![0x5e; 32];
+        let htlc_id = settlement_htlc(&mut contract, &secret);
+
+        testing_env!(get_context(accounts(1)));
+        contract.withdraw(htlc_id.clone(), Base64VecU8(secret));
+        testing_env!(receiver_context(0, settlement_config().retry_interval));
+        contract.retry_settlement(htlc_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attach the settlement sign deposit on top of the amount")]
+    fn test_create_needs_sign_deposit() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        contract.set_settlement_config(Some(settlement_config()));
+        contract.set_approved_escrow(base_escrow().escrow, true);
+        let mut context = get_context(accounts(2));
+        context.attached_deposit = NearToken::from_yoctonear(1_000 + SIGN_DEPOSIT - 1);
+        testing_env!(context);
+        let mut args = fill_args(Base64VecU8(vec![1u8; 32]), 1_000);
+        args.base_escrow = Some(base_escrow());
+        contract.create_htlc(args);
+    }
+
+    // Transfers of `amount` to `account` created so far
+    fn has_transfer(account: AccountId, amount: u128) -> bool {
+        get_created_receipts().iter().any(|r| {
+            r.receiver_id == account
+                && r.actions.iter().any(|action| matches!(
+                    action,
+                    near_sdk::mock::MockAction::Transfer { deposit, .. }
+                        if *deposit == NearToken::from_yoctonear(amount)
+                ))
+        })
+    }
+
+    #[test]
+    fn test_refund_returns_sign_deposit() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        contract.set_settlement_config(Some(settlement_config()));
+        let htlc_id = settlement_htlc(&mut contract, &[0x5e; 32]);
+
+        let mut context = get_context(accounts(2));
+        context.block_timestamp = u64::MAX;
+        testing_env!(context);
+        contract.refund(htlc_id);
+        assert!(has_transfer(accounts(2), 1_000));
+        assert!(has_transfer(accounts(2), SIGN_DEPOSIT));
+    }
+
+    #[test]
+    fn test_cancel_settlement() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        contract.set_settlement_config(Some(settlement_config()));
+        contract.set_settlement_nonce(3);
+        let secret = vec![0x5e; 32];
+        let htlc_id = settlement_htlc(&mut contract, &secret);
+        testing_env!(get_context(accounts(1)));
+        contract.withdraw(htlc_id.clone(), Base64VecU8(secret));
+
+        // The owner replaces the withdraw with a no-op at the same nonce
+        let mut context = get_context(accounts(0));
+        context.attached_deposit = NearToken::from_yoctonear(SIGN_DEPOSIT);
+        testing_env!(context);
+        drop(contract.cancel_settlement(htlc_id.clone()));
+        assert!(get_created_receipts()
+            .iter()
+            .any(|r| r.receiver_id.as_str() == "v1.signer-prod.testnet"));
+        let settlement = contract.get_settlement(htlc_id.clone()).unwrap();
+        assert_eq!((settlement.nonce, settlement.cancelled), (3, true));
+
+        let result = SignResult {
+            big_r: chain_signatures::signatures::AffinePoint {
+                affine_point: "0208b2a8c29506cdf27fe61b47f6f0852e0ad0abc1fcb50ebce19d2fd9eed93ed7"
+                    .to_string(),
+            },
+            s: chain_signatures::signatures::Scalar {
+                scalar: "32a6d94b368afa2ab2079ce0acc59de847ceeec59aca748c16d0b5ba1462227b"
+                    .to_string(),
+            },
+            recovery_id: 0,
+        };
+        let signed_tx = contract
+            .on_settlement_signed(htlc_id, settlement_config(), Ok(result))
+            .unwrap();
+        // 21000 gas to the zero address, without the escrow call
+        assert!(signed_tx.contains(&format!("825208{}", "94".to_owned() + &"00".repeat(20))));
+        assert!(!signed_tx.contains(&"11".repeat(20)));
+    }
+
+    #[test]
+    #[should_panic(expected = "BASE settlement is only supported for NEAR HTLCs")]
+    fn test_ft_htlc_with_base_escrow_rejected() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        contract.set_approved_escrow(base_escrow().escrow, true);
+        testing_env!(get_context(accounts(4)));
+        let mut args = fill_args(Base64VecU8(vec![1u8; 32]), 1_000);
+        args.token = Some(accounts(4));
+        args.base_escrow = Some(base_escrow());
+        contract.ft_on_transfer(accounts(2), U128(1_000), serde_json::to_string(&args).unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "BASE escrow is not approved")]
+    fn test_unapproved_escrow_rejected() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        let mut context = get_context(accounts(2));
+        context.attached_deposit = NearToken::from_yoctonear(1_000);
+        testing_env!(context);
+        let mut args = fill_args(Base64VecU8(vec![1u8; 32]), 1_000);
+        args.base_escrow = Some(base_escrow());
+        contract.create_htlc(args);
+    }
+
+    #[test]
+    fn test_revoked_escrow_not_settled() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        contract.set_settlement_config(Some(settlement_config()));
+        let secret = vec![0x5e; 32];
+        let htlc_id = settlement_htlc(&mut contract, &secret);
+        testing_env!(get_context(accounts(0)));
+        contract.set_approved_escrow(base_escrow().escrow, false);
+        assert!(!contract.is_approved_escrow(base_escrow().escrow));
+
+        // The payout goes through and the sender gets the sign deposit back
+        testing_env!(get_context(accounts(1)));
+        contract.withdraw(htlc_id.clone(), Base64VecU8(secret));
+        assert!(contract.get_settlement(htlc_id).is_none());
+        assert!(get_logs().contains(&"BASE escrow is not approved".to_string()));
+        assert!(has_transfer(accounts(2), SIGN_DEPOSIT));
+        assert!(!get_created_receipts()
+            .iter()
+            .any(|r| r.receiver_id.as_str() == "v1.signer-prod.testnet"));
+    }
+
+    #[test]
+    fn test_withdraw_without_settlement_config() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        let secret = vec![0x5e; 32];
+        let htlc_id = settlement_htlc(&mut contract, &secret);
+
+        testing_env!(get_context(accounts(1)));
+        contract.withdraw(htlc_id.clone(), Base64VecU8(secret));
+        assert!(contract.get_settlement(htlc_id).is_none());
+    }
+
+    #[test]
+    fn test_stats_track_status_and_locked_value() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        let secret = vec![0x5e; 32];
+        let htlc_id = settlement_htlc(&mut contract, &secret);
+        settlement_htlc(&mut contract, &secret);
+
+        let stats = contract.get_stats();
+        assert_eq!(stats.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!((stats.total_htlcs, stats.active_htlcs), (2, 2));
+        assert_eq!(stats.locked.len(), 1);
+        assert_eq!(stats.locked[0].token, None);
+        assert_eq!(stats.locked[0].amount, U128(2_000));
+
+        testing_env!(get_context(accounts(1)));
+        contract.withdraw(htlc_id, Base64VecU8(secret));
+        let stats = contract.get_stats();
+        assert_eq!(stats.active_htlcs, 1);
+        assert_eq!((stats.withdrawn_htlcs, stats.refunded_htlcs), (1, 0));
+        assert_eq!(stats.locked[0].amount, U128(1_000));
+
+        let info = contract.get_info();
+        assert_eq!(info.owner, accounts(0));
+        assert_eq!((info.total_htlcs, info.active_htlcs), (2, 1));
+        assert!(!info.config.resolver_exclusive);
+        assert!(info.config.settlement_config.is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid BASE escrow address")]
+    fn test_invalid_base_escrow_rejected() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+
+        let mut context = get_context(accounts(2));
+        context.attached_deposit = NearToken::from_yoctonear(1_000);
+        testing_env!(context);
+        let mut args = fill_args(Base64VecU8(vec![1u8; 32]), 1_000);
+        args.base_escrow = Some(BaseEscrow {
+            escrow: "0x1234".to_string(),
+            ..base_escrow()
+        });
+        contract.create_htlc(args);
+    }
+
+    // Locks 1_000 tokens of accounts(4) sent by accounts(2) for accounts(1)
+    fn ft_htlc(contract: &mut FusionPlusHTLC, secret: &[u8], sent: u128) -> PromiseOrValue<U128> {
+        testing_env!(get_context(accounts(4)));
+        let mut args = fill_args(Base64VecU8(vec![1u8; 32]), 1_000);
+        args.token = Some(accounts(4));
+        args.hashlock = Base64VecU8(Keccak256::digest(secret).to_vec());
+        let msg = serde_json::to_string(&args).unwrap();
+        contract.ft_on_transfer(accounts(2), U128(sent), msg)
+    }
+
+    #[test]
+    fn test_ft_transfer_call_locks_tokens() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        let secret = vec![0x5e; 32];
+        // Anything above the HTLC amount is handed back to the token
+        match ft_htlc(&mut contract, &secret, 1_200) {
+            PromiseOrValue::Value(unused) => assert_eq!(unused, U128(200)),
+            PromiseOrValue::Promise(_) => panic!("Expected HTLC to be created synchronously"),
+        }
+        let htlc = contract.get_htlc("htlc_1".to_string()).unwrap();
+        assert_eq!((htlc.sender, htlc.token.clone()), (accounts(2), Some(accounts(4))));
+        assert_eq!(contract.get_stats().locked[0].token, Some(accounts(4)));
+
+        // The payout is an ft_transfer on the token contract
+        testing_env!(get_context(accounts(1)));
+        contract.withdraw("htlc_1".to_string(), Base64VecU8(secret));
+        assert!(get_created_receipts().iter().any(|r| r.receiver_id == accounts(4)));
+        assert!(contract.get_stats().locked.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Token does not match the transferred token")]
+    fn test_ft_transfer_call_from_other_token() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        testing_env!(get_context(accounts(3)));
+        let mut args = fill_args(Base64VecU8(vec![1u8; 32]), 1_000);
+        args.token = Some(accounts(4));
+        contract.ft_on_transfer(accounts(2), U128(1_000), serde_json::to_string(&args).unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "Lock NEP-141 tokens with ft_transfer_call")]
+    fn test_create_htlc_with_token_rejected() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        let mut context = get_context(accounts(2));
+        context.attached_deposit = NearToken::from_yoctonear(1_000);
+        testing_env!(context);
+        let mut args = fill_args(Base64VecU8(vec![1u8; 32]), 1_000);
+        args.token = Some(accounts(4));
+        contract.create_htlc(args);
+    }
+
+    #[test]
+    fn test_failed_payout_is_claimable() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        assert!(contract.on_transfer_complete(accounts(1), None, U128(1_000), Ok(())));
+        assert_eq!(contract.get_claimable(accounts(1), None), U128(0));
+
+        assert!(!contract.on_transfer_complete(accounts(1), None, U128(1_000), Err(PromiseError::Failed)));
+        assert!(!contract.on_transfer_complete(accounts(1), None, U128(500), Err(PromiseError::Failed)));
+        assert!(get_logs().iter().any(|log| log.contains("payout_failed")));
+        assert_eq!(contract.get_claimable(accounts(1), None), U128(1_500));
+        assert_eq!(contract.get_claimable(accounts(1), Some(accounts(4))), U128(0));
+
+        testing_env!(get_context(accounts(1)));
+        drop(contract.claim(None));
+        assert!(get_created_receipts().iter().any(|r| r.receiver_id == accounts(1)));
+        assert_eq!(contract.get_claimable(accounts(1), None), U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Nothing to claim")]
+    fn test_claim_without_failed_payout() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = FusionPlusHTLC::new(accounts(0));
+        testing_env!(get_context(accounts(1)));
+        contract.claim(None);
+    }
+}
@@ -0,0 +1,148 @@
+// Upgrade from the layout deployed before auctions, resolver checks,
+// settlements and locked-balance stats were added. Deploy the new code with
+// migrate as its init call (`MIGRATE=1 make redeploy-testnet`); it fails, and
+// the deploy with it, if the state is already in the current layout.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, LookupSet, UnorderedMap, UnorderedSet};
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::{Balance, FusionPlusHTLC, FusionPlusHTLCExt, StorageKey, Timestamp, HTLC};
+
+#[derive(BorshDeserialize, BorshSerialize)]
+struct OldFusionPlusHTLC {
+    owner: AccountId,
+    htlcs: UnorderedMap<String, OldHTLC>,
+    active_htlc_ids: Vec<String>,
+    next_htlc_id: u64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+struct OldHTLC {
+    sender: AccountId,
+    receiver: AccountId,
+    token: Option<AccountId>,
+    amount: Balance,
+    hashlock: Base64VecU8,
+    timelock: Timestamp,
+    order_hash: Base64VecU8,
+    withdrawn: bool,
+    refunded: bool,
+    created_at: Timestamp,
+}
+
+#[near_bindgen]
+impl FusionPlusHTLC {
+    // Rewrites every HTLC with the fields added since, in the same order, and
+    // rebuilds the stats counters from them
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let mut old: OldFusionPlusHTLC = env::state_read().expect("No state to migrate");
+        let old_htlcs = old.htlcs.to_vec();
+        old.htlcs.clear();
+
+        let mut contract = Self {
+            owner: old.owner,
+            htlcs: UnorderedMap::new(StorageKey::HTLCs),
+            active_htlc_ids: old.active_htlc_ids,
+            next_htlc_id: old.next_htlc_id,
+            auction_orders: LookupMap::new(StorageKey::AuctionOrders),
+            solver_registry: None,
+            resolver_exclusive: false,
+            cached_solvers: UnorderedSet::new(StorageKey::CachedSolvers),
+            reported_htlcs: LookupSet::new(StorageKey::ReportedHTLCs),
+            settlement_config: None,
+            next_settlement_nonce: 0,
+            settlements: LookupMap::new(StorageKey::Settlements),
+            approved_escrows: LookupSet::new(StorageKey::ApprovedEscrows),
+            withdrawn_htlcs: 0,
+            refunded_htlcs: 0,
+            locked_balances: UnorderedMap::new(StorageKey::LockedBalances),
+            claimable: LookupMap::new(StorageKey::Claimable),
+        };
+
+        for (htlc_id, old) in old_htlcs {
+            let htlc = HTLC {
+                sender: old.sender,
+                receiver: old.receiver,
+                token: old.token,
+                amount: old.amount,
+                hashlock: old.hashlock,
+                timelock: old.timelock,
+                order_hash: old.order_hash,
+                withdrawn: old.withdrawn,
+                refunded: old.refunded,
+                created_at: old.created_at,
+                base_escrow: None,
+                settlement_deposit: 0,
+            };
+            if htlc.withdrawn {
+                contract.withdrawn_htlcs += 1;
+            } else if htlc.refunded {
+                contract.refunded_htlcs += 1;
+            } else {
+                let locked = contract.locked_balances.get(&htlc.token).unwrap_or(0);
+                contract.locked_balances.insert(&htlc.token, &(locked + htlc.amount));
+            }
+            contract.htlcs.insert(&htlc_id, &htlc);
+        }
+
+        contract
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn old_htlc(amount: Balance, withdrawn: bool, refunded: bool) -> OldHTLC {
+        OldHTLC {
+            sender: accounts(1),
+            receiver: accounts(2),
+            token: None,
+            amount,
+            hashlock: Base64VecU8(vec![0u8; 32]),
+            timelock: 1_000,
+            order_hash: Base64VecU8(vec![1u8; 32]),
+            withdrawn,
+            refunded,
+            created_at: 10,
+        }
+    }
+
+    #[test]
+    fn test_migrate_from_old_layout() {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build());
+
+        let mut old = OldFusionPlusHTLC {
+            owner: accounts(3),
+            htlcs: UnorderedMap::new(StorageKey::HTLCs),
+            active_htlc_ids: vec!["htlc_3".to_string()],
+            next_htlc_id: 4,
+        };
+        old.htlcs.insert(&"htlc_1".to_string(), &old_htlc(100, true, false));
+        old.htlcs.insert(&"htlc_2".to_string(), &old_htlc(200, false, true));
+        old.htlcs.insert(&"htlc_3".to_string(), &old_htlc(300, false, false));
+        env::state_write(&old);
+
+        let contract = FusionPlusHTLC::migrate();
+        assert_eq!(contract.get_owner(), accounts(3));
+        let htlc = contract.get_htlc("htlc_3".to_string()).unwrap();
+        assert_eq!(htlc.amount.0, 300);
+        assert!(htlc.base_escrow.is_none());
+
+        let stats = contract.get_stats();
+        assert_eq!(stats.total_htlcs, 3);
+        assert_eq!(stats.active_htlcs, 1);
+        assert_eq!((stats.withdrawn_htlcs, stats.refunded_htlcs), (1, 1));
+        assert_eq!(stats.locked.len(), 1);
+        assert_eq!(stats.locked[0].amount.0, 300);
+    }
+}
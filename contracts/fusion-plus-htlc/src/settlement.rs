@@ -0,0 +1,170 @@
+use chain_signatures::escrow::{withdraw_calldata, Immutables};
+use chain_signatures::evm::{Address, Eip1559Transaction};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{AccountId, NearSchema};
+
+use crate::HTLC;
+
+const CANCEL_GAS_LIMIT: u64 = 21_000;
+
+// Counterpart escrow on BASE for an HTLC. Order hash and hashlock are taken
+// from the HTLC itself; the remaining immutables are hex encoded as on BASE.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct BaseEscrow {
+    pub escrow: String,
+    pub maker: String,
+    pub taker: String,
+    pub token: String, // zero address for ETH
+    pub amount: U128,
+    pub safety_deposit: U128,
+    pub timelocks: String, // packed uint256, 32 bytes
+}
+
+// Owner settings for signing BASE withdrawals through the MPC signer. The
+// address derived for (this contract, path) sends the transactions and must
+// hold ETH for gas and be allowed to withdraw from the escrows. Only escrows
+// the owner approved are settled.
+//
+// All settlements share that address, so BASE mines them in nonce order: a
+// settlement whose transaction is never broadcast holds back every later
+// one. Signed transactions are public, so any relayer can push them through;
+// a settlement that cannot be signed or mined is retried at the same nonce,
+// and as a last resort cancelled, which signs a no-op transfer in its place.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct SettlementConfig {
+    pub mpc_signer: AccountId,
+    pub path: String,
+    pub key_version: u32,
+    pub chain_id: u64,
+    pub gas_limit: u64,
+    pub max_fee_per_gas: U128,
+    pub max_priority_fee_per_gas: U128,
+    pub sign_deposit: U128, // prepaid by the HTLC sender, per signature
+    pub retry_interval: u64, // minimum ns between signature requests per HTLC
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct Settlement {
+    pub nonce: u64,
+    pub secret: [u8; 32],
+    pub signed_tx: Option<Vec<u8>>,
+    pub requested_at: u64,
+    // Signs a no-op transfer instead of the withdraw, see cancel_tx
+    pub cancelled: bool,
+}
+
+#[derive(Serialize, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct SettlementView {
+    pub htlc_id: String,
+    pub nonce: u64,
+    pub signed_tx: Option<String>,
+    pub cancelled: bool,
+}
+
+impl BaseEscrow {
+    pub fn escrow_address(&self) -> Result<Address, &'static str> {
+        parse_address(&self.escrow)
+    }
+
+    pub fn assert_valid(&self) -> Result<(), &'static str> {
+        for address in [&self.escrow, &self.maker, &self.taker, &self.token] {
+            parse_hex::<20>(address).map_err(|_| "Invalid BASE escrow address")?;
+        }
+        parse_hex::<32>(&self.timelocks).map_err(|_| "Invalid BASE escrow timelocks")?;
+        Ok(())
+    }
+
+    fn immutables(&self, htlc: &HTLC) -> Result<Immutables, &'static str> {
+        Ok(Immutables {
+            order_hash: htlc
+                .order_hash
+                .0
+                .as_slice()
+                .try_into()
+                .map_err(|_| "Order hash must be 32 bytes")?,
+            hashlock: htlc
+                .hashlock
+                .0
+                .as_slice()
+                .try_into()
+                .map_err(|_| "Hashlock must be 32 bytes")?,
+            maker: parse_hex(&self.maker)?,
+            taker: parse_hex(&self.taker)?,
+            token: parse_hex(&self.token)?,
+            amount: self.amount.0,
+            safety_deposit: self.safety_deposit.0,
+            timelocks: parse_hex(&self.timelocks)?,
+        })
+    }
+}
+
+impl SettlementConfig {
+    pub fn settlement_tx(
+        &self,
+        settlement: &Settlement,
+        escrow: &BaseEscrow,
+        htlc: &HTLC,
+    ) -> Result<Eip1559Transaction, &'static str> {
+        if settlement.cancelled {
+            Ok(self.cancel_tx(settlement))
+        } else {
+            self.withdraw_tx(settlement, escrow, htlc)
+        }
+    }
+
+    // EIP-1559 call to the escrow's withdraw(secret, immutables)
+    pub fn withdraw_tx(
+        &self,
+        settlement: &Settlement,
+        escrow: &BaseEscrow,
+        htlc: &HTLC,
+    ) -> Result<Eip1559Transaction, &'static str> {
+        let to: Address = parse_hex(&escrow.escrow)?;
+        Ok(Eip1559Transaction {
+            chain_id: self.chain_id,
+            nonce: settlement.nonce,
+            max_priority_fee_per_gas: self.max_priority_fee_per_gas.0,
+            max_fee_per_gas: self.max_fee_per_gas.0,
+            gas_limit: self.gas_limit,
+            to: Some(to),
+            value: 0,
+            data: withdraw_calldata(&settlement.secret, &escrow.immutables(htlc)?),
+            access_list: Vec::new(),
+        })
+    }
+
+    // Zero-value transfer to the zero address that only uses up the nonce,
+    // so a stuck settlement stops blocking later ones
+    fn cancel_tx(&self, settlement: &Settlement) -> Eip1559Transaction {
+        Eip1559Transaction {
+            chain_id: self.chain_id,
+            nonce: settlement.nonce,
+            max_priority_fee_per_gas: self.max_priority_fee_per_gas.0,
+            max_fee_per_gas: self.max_fee_per_gas.0,
+            gas_limit: CANCEL_GAS_LIMIT,
+            to: Some([0u8; 20]),
+            value: 0,
+            data: Vec::new(),
+            access_list: Vec::new(),
+        }
+    }
+}
+
+pub fn parse_address(value: &str) -> Result<Address, &'static str> {
+    parse_hex(value).map_err(|_| "Invalid BASE escrow address")
+}
+
+fn parse_hex<const N: usize>(value: &str) -> Result<[u8; N], &'static str> {
+    let mut bytes = [0u8; N];
+    hex::decode_to_slice(value.strip_prefix("0x").unwrap_or(value), &mut bytes)
+        .map_err(|_| "Invalid hex value")?;
+    Ok(bytes)
+}
@@ -0,0 +1,26 @@
+// Helpers shared by the sandbox test binaries
+
+use sha3::{Digest, Keccak256};
+
+pub const SECRET: [u8; 32] = [0x5e; 32];
+
+// Release wasm build of a workspace contract
+pub fn wasm(name: &str) -> Vec<u8> {
+    let path = format!(
+        "{}/../../target/wasm32-unknown-unknown/release/{name}.wasm",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    std::fs::read(&path).unwrap_or_else(|_| {
+        panic!("{path} not found; build with `cargo build --target wasm32-unknown-unknown --release`")
+    })
+}
+
+// Base64, as Base64VecU8 arguments are passed in JSON
+pub fn encode(bytes: &[u8]) -> String {
+    use near_sdk::base64::Engine;
+    near_sdk::base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+pub fn hashlock() -> String {
+    encode(&Keccak256::digest(SECRET))
+}
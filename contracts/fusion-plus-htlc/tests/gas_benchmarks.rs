@@ -0,0 +1,355 @@
+// Gas and storage benchmarks for the HTLC operations against a sandbox node.
+//
+// active_htlc_ids is a Vec stored with the contract state, so the cost of
+// every call grows with the number of live HTLCs. For each size the contract
+// is filled with live HTLCs, then one create_htlc, withdraw, refund and
+// get_active_htlcs (as a transaction, to get its gas) are measured. Results
+// are compared against tests/gas_baseline.json and the run fails if one
+// regresses past the thresholds in that file, or has no recorded baseline.
+//
+//   cargo build --target wasm32-unknown-unknown --release
+//   NEAR_SANDBOX_BIN_PATH=/path/to/near-sandbox \
+//       cargo test -p fusion-plus-htlc --test gas_benchmarks -- --ignored --nocapture
+//
+// BENCH_SIZES=10,1000 limits the sizes (filling 10k HTLCs takes a while) and
+// BENCH_UPDATE_BASELINE=1 rewrites the baseline with the measured values.
+
+use std::collections::BTreeMap;
+
+use near_workspaces::network::Sandbox;
+use near_workspaces::operations::Function;
+use near_workspaces::result::ExecutionFinalResult;
+use near_workspaces::types::{Gas, NearToken};
+use near_workspaces::{Account, Contract, Worker};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+mod common;
+use common::{encode, hashlock, wasm, SECRET};
+
+const BASELINE_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/gas_baseline.json");
+const DEFAULT_SIZES: [u64; 3] = [10, 1_000, 10_000];
+const HOUR: u64 = 3_600_000_000_000;
+
+// Filler HTLCs per transaction and transactions in flight while filling
+const FILL_BATCH: u64 = 10;
+const FILL_WINDOW: usize = 20;
+const GAS_PER_FILL: Gas = Gas::from_tgas(25);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+struct Measurement {
+    gas_burnt: u64,
+    // Change of the contract's storage usage
+    storage_bytes: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Thresholds {
+    gas_regression_percent: u64,
+    storage_regression_bytes: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Baseline {
+    thresholds: Thresholds,
+    // operation -> number of live HTLCs -> measurement; null until recorded
+    results: BTreeMap<String, BTreeMap<String, Option<Measurement>>>,
+}
+
+struct Bench {
+    worker: Worker<Sandbox>,
+    htlc: Contract,
+    sender: Account,
+    receiver: Account,
+    live: u64,
+}
+
+fn sizes() -> Vec<u64> {
+    match std::env::var("BENCH_SIZES") {
+        Ok(sizes) => sizes
+            .split(',')
+            .map(|size| {
+                size.trim()
+                    .parse()
+                    .expect("BENCH_SIZES is a list of numbers")
+            })
+            .collect(),
+        Err(_) => DEFAULT_SIZES.to_vec(),
+    }
+}
+
+fn create_args(bench: &Bench, timelock: u64, order: u64) -> serde_json::Value {
+    let mut order_hash = [0u8; 32];
+    order_hash[..8].copy_from_slice(&order.to_be_bytes());
+    json!({
+        "args": {
+            "receiver": bench.receiver.id(),
+            "amount": "1",
+            "hashlock": hashlock(),
+            "timelock": timelock.to_string(),
+            "order_hash": encode(&order_hash),
+        }
+    })
+}
+
+async fn setup() -> anyhow::Result<Bench> {
+    let worker = near_workspaces::sandbox().await?;
+    let root = worker.root_account()?;
+    // Enough balance to stake storage for 10k HTLCs
+    let htlc_account = root
+        .create_subaccount("htlc")
+        .initial_balance(NearToken::from_near(1_000))
+        .transact()
+        .await?
+        .into_result()?;
+    let htlc = htlc_account
+        .deploy(&wasm("fusion_plus_htlc"))
+        .await?
+        .into_result()?;
+    htlc.call("new")
+        .args_json(json!({ "owner": htlc.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let sender = root
+        .create_subaccount("sender")
+        .initial_balance(NearToken::from_near(500))
+        .transact()
+        .await?
+        .into_result()?;
+    let receiver = root
+        .create_subaccount("receiver")
+        .initial_balance(NearToken::from_near(10))
+        .transact()
+        .await?
+        .into_result()?;
+
+    Ok(Bench {
+        worker,
+        htlc,
+        sender,
+        receiver,
+        live: 0,
+    })
+}
+
+async fn block_timestamp(bench: &Bench) -> anyhow::Result<u64> {
+    Ok(bench.worker.view_block().await?.timestamp())
+}
+
+async fn storage_usage(bench: &Bench) -> anyhow::Result<i64> {
+    Ok(bench.htlc.view_account().await?.storage_usage as i64)
+}
+
+// Adds live HTLCs with 1 yoctoNEAR each until `target` are active
+async fn fill(bench: &mut Bench, target: u64) -> anyhow::Result<()> {
+    let timelock = block_timestamp(bench).await? + 24 * HOUR;
+    while bench.live < target {
+        let mut pending = Vec::new();
+        while bench.live < target && pending.len() < FILL_WINDOW {
+            let count = FILL_BATCH.min(target - bench.live);
+            let mut tx = bench.sender.batch(bench.htlc.id());
+            for i in 0..count {
+                tx = tx.call(
+                    Function::new("create_htlc")
+                        .args_json(create_args(bench, timelock, bench.live + i))
+                        .deposit(NearToken::from_yoctonear(1))
+                        .gas(GAS_PER_FILL),
+                );
+            }
+            pending.push(tx.transact_async().await?);
+            bench.live += count;
+        }
+        for status in pending {
+            status.await?.into_result()?;
+        }
+    }
+    Ok(())
+}
+
+async fn measure(
+    bench: &Bench,
+    result: impl std::future::Future<Output = near_workspaces::Result<ExecutionFinalResult>>,
+) -> anyhow::Result<(Measurement, ExecutionFinalResult)> {
+    let storage_before = storage_usage(bench).await?;
+    let result = result.await?;
+    assert!(result.is_success(), "{:?}", result.clone().into_result());
+    let measurement = Measurement {
+        gas_burnt: result.total_gas_burnt.as_gas(),
+        storage_bytes: storage_usage(bench).await? - storage_before,
+    };
+    Ok((measurement, result))
+}
+
+// One measurement per operation at the current number of live HTLCs
+async fn measure_operations(bench: &Bench) -> anyhow::Result<Vec<(&'static str, Measurement)>> {
+    let now = block_timestamp(bench).await?;
+    let order = u64::MAX - bench.live;
+
+    let create = bench
+        .sender
+        .call(bench.htlc.id(), "create_htlc")
+        .args_json(create_args(bench, now + HOUR, order))
+        .deposit(NearToken::from_yoctonear(1))
+        .max_gas()
+        .transact();
+    let (create_htlc, result) = measure(bench, create).await?;
+    let htlc_id: String = result.json()?;
+
+    let withdraw = bench
+        .receiver
+        .call(bench.htlc.id(), "withdraw")
+        .args_json(json!({ "htlc_id": htlc_id, "secret": encode(&SECRET) }))
+        .max_gas()
+        .transact();
+    let (withdraw, _) = measure(bench, withdraw).await?;
+
+    // Short timelock so it can be refunded right away
+    let timelock = block_timestamp(bench).await? + 2_000_000_000;
+    let htlc_id: String = bench
+        .sender
+        .call(bench.htlc.id(), "create_htlc")
+        .args_json(create_args(bench, timelock, order - 1))
+        .deposit(NearToken::from_yoctonear(1))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?
+        .json()?;
+    while block_timestamp(bench).await? <= timelock {
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    }
+    let refund = bench
+        .sender
+        .call(bench.htlc.id(), "refund")
+        .args_json(json!({ "htlc_id": htlc_id }))
+        .max_gas()
+        .transact();
+    let (refund, _) = measure(bench, refund).await?;
+
+    let list = bench
+        .sender
+        .call(bench.htlc.id(), "get_active_htlcs")
+        .args_json(json!({ "from_index": 0, "limit": 100 }))
+        .max_gas()
+        .transact();
+    let (get_active_htlcs, _) = measure(bench, list).await?;
+
+    Ok(vec![
+        ("create_htlc", create_htlc),
+        ("withdraw", withdraw),
+        ("refund", refund),
+        ("get_active_htlcs", get_active_htlcs),
+    ])
+}
+
+// Regressions past the thresholds, and missing baselines, as messages
+fn regressions(
+    baseline: &Baseline,
+    operation: &str,
+    size: u64,
+    measured: &Measurement,
+) -> Vec<String> {
+    let Some(Some(expected)) = baseline
+        .results
+        .get(operation)
+        .and_then(|sizes| sizes.get(&size.to_string()))
+    else {
+        return vec![format!(
+            "{operation} at {size}: no baseline recorded, run scripts/test/run-benchmarks.sh --update"
+        )];
+    };
+
+    let mut failures = Vec::new();
+    let gas_limit =
+        expected.gas_burnt + expected.gas_burnt * baseline.thresholds.gas_regression_percent / 100;
+    if measured.gas_burnt > gas_limit {
+        failures.push(format!(
+            "{operation} at {size}: gas {} > baseline {} + {}%",
+            measured.gas_burnt, expected.gas_burnt, baseline.thresholds.gas_regression_percent
+        ));
+    }
+    let storage_limit = expected.storage_bytes + baseline.thresholds.storage_regression_bytes;
+    if measured.storage_bytes > storage_limit {
+        failures.push(format!(
+            "{operation} at {size}: storage {} > baseline {} + {} bytes",
+            measured.storage_bytes,
+            expected.storage_bytes,
+            baseline.thresholds.storage_regression_bytes
+        ));
+    }
+    failures
+}
+
+#[tokio::test]
+#[ignore]
+async fn bench_htlc_operations() -> anyhow::Result<()> {
+    let mut baseline: Baseline = serde_json::from_str(&std::fs::read_to_string(BASELINE_PATH)?)?;
+    let update = std::env::var("BENCH_UPDATE_BASELINE").is_ok_and(|v| v == "1");
+    let mut bench = setup().await?;
+
+    let mut failures = Vec::new();
+    println!(
+        "{:<18} {:>8} {:>12} {:>14}",
+        "operation", "live", "Tgas", "storage bytes"
+    );
+    let mut sizes = sizes();
+    sizes.sort_unstable();
+    for size in sizes {
+        fill(&mut bench, size).await?;
+        for (operation, measured) in measure_operations(&bench).await? {
+            println!(
+                "{:<18} {:>8} {:>12.3} {:>14}",
+                operation,
+                size,
+                measured.gas_burnt as f64 / 1e12,
+                measured.storage_bytes
+            );
+            failures.extend(regressions(&baseline, operation, size, &measured));
+            if update {
+                baseline
+                    .results
+                    .entry(operation.to_string())
+                    .or_default()
+                    .insert(size.to_string(), Some(measured));
+            }
+        }
+    }
+
+    if update {
+        std::fs::write(
+            BASELINE_PATH,
+            serde_json::to_string_pretty(&baseline)? + "\n",
+        )?;
+        println!("Baseline written to {BASELINE_PATH}");
+    } else {
+        assert!(failures.is_empty(), "regressions:\n{}", failures.join("\n"));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_missing_baseline_fails() {
+    let baseline: Baseline = serde_json::from_str(
+        r#"{
+            "thresholds": { "gas_regression_percent": 10, "storage_regression_bytes": 0 },
+            "results": { "withdraw": { "10": null, "1000": { "gas_burnt": 100, "storage_bytes": 0 } } }
+        }"#,
+    )
+    .unwrap();
+    let measured = Measurement {
+        gas_burnt: 105,
+        storage_bytes: 0,
+    };
+
+    assert!(regressions(&baseline, "withdraw", 1000, &measured).is_empty());
+    assert_eq!(regressions(&baseline, "withdraw", 10, &measured).len(), 1);
+    assert_eq!(regressions(&baseline, "withdraw", 10_000, &measured).len(), 1);
+    assert_eq!(regressions(&baseline, "refund", 1000, &measured).len(), 1);
+    let slower = Measurement {
+        gas_burnt: 111,
+        storage_bytes: 1,
+    };
+    assert_eq!(regressions(&baseline, "withdraw", 1000, &slower).len(), 2);
+}
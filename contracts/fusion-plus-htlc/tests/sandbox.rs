@@ -0,0 +1,506 @@
+// End-to-end tests against a local sandbox node. Unlike the unit tests these
+// execute the real receipts, so they observe transfers, balances and gas.
+//
+// They need the contracts built for wasm and a sandbox binary, so they are
+// ignored by default:
+//
+//   cargo build --target wasm32-unknown-unknown --release
+//   NEAR_SANDBOX_BIN_PATH=/path/to/near-sandbox \
+//       cargo test -p fusion-plus-htlc --test sandbox -- --ignored
+
+use near_workspaces::network::Sandbox;
+use near_workspaces::result::ExecutionFinalResult;
+use near_workspaces::types::{Gas, NearToken};
+use near_workspaces::{Account, AccountId, Contract, Worker};
+use serde_json::{json, Value};
+
+mod common;
+use common::{encode, hashlock, wasm, SECRET};
+
+const AMOUNT: NearToken = NearToken::from_near(5);
+const HOUR: u64 = 3_600_000_000_000;
+
+// Rounding allowance for gas paid by the account whose balance is checked
+const GAS_COST_MARGIN: NearToken = NearToken::from_millinear(10);
+
+struct Env {
+    worker: Worker<Sandbox>,
+    htlc: Contract,
+    alice: Account,
+    bob: Account,
+}
+
+async fn setup() -> anyhow::Result<Env> {
+    let worker = near_workspaces::sandbox().await?;
+    let htlc = worker.dev_deploy(&wasm("fusion_plus_htlc")).await?;
+    htlc.call("new")
+        .args_json(json!({ "owner": htlc.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let root = worker.root_account()?;
+    let alice = root
+        .create_subaccount("alice")
+        .initial_balance(NearToken::from_near(50))
+        .transact()
+        .await?
+        .into_result()?;
+    let bob = root
+        .create_subaccount("bob")
+        .initial_balance(NearToken::from_near(10))
+        .transact()
+        .await?
+        .into_result()?;
+
+    Ok(Env {
+        worker,
+        htlc,
+        alice,
+        bob,
+    })
+}
+
+async fn block_timestamp(worker: &Worker<Sandbox>) -> anyhow::Result<u64> {
+    Ok(worker.view_block().await?.timestamp())
+}
+
+async fn balance(account: &Account) -> anyhow::Result<NearToken> {
+    Ok(account.view_account().await?.balance)
+}
+
+async fn create_htlc(
+    env: &Env,
+    token: Option<&AccountId>,
+    timelock: u64,
+) -> anyhow::Result<ExecutionFinalResult> {
+    create_htlc_for(env, env.bob.id(), token, timelock).await
+}
+
+fn htlc_args(receiver: &AccountId, token: Option<&AccountId>, amount: u128, timelock: u64) -> Value {
+    json!({
+        "receiver": receiver,
+        "token": token,
+        "amount": amount.to_string(),
+        "hashlock": hashlock(),
+        "timelock": timelock.to_string(),
+        "order_hash": encode(&[1u8; 32]),
+    })
+}
+
+async fn create_htlc_for(
+    env: &Env,
+    receiver: &AccountId,
+    token: Option<&AccountId>,
+    timelock: u64,
+) -> anyhow::Result<ExecutionFinalResult> {
+    Ok(env
+        .alice
+        .call(env.htlc.id(), "create_htlc")
+        .args_json(json!({
+            "args": htlc_args(receiver, token, AMOUNT.as_yoctonear(), timelock)
+        }))
+        .deposit(AMOUNT)
+        .max_gas()
+        .transact()
+        .await?)
+}
+
+async fn get_claimable(
+    env: &Env,
+    account_id: &AccountId,
+    token: Option<&AccountId>,
+) -> anyhow::Result<String> {
+    Ok(env
+        .htlc
+        .view("get_claimable")
+        .args_json(json!({ "account_id": account_id, "token": token }))
+        .await?
+        .json()?)
+}
+
+async fn get_htlc(env: &Env, htlc_id: &str) -> anyhow::Result<Value> {
+    Ok(env
+        .htlc
+        .view("get_htlc")
+        .args_json(json!({ "htlc_id": htlc_id }))
+        .await?
+        .json()?)
+}
+
+fn assert_failure(result: ExecutionFinalResult, message: &str) {
+    assert!(
+        result.is_failure(),
+        "expected failure containing {message:?}"
+    );
+    let failure = format!("{:?}", result.into_result().unwrap_err());
+    assert!(failure.contains(message), "{failure}");
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_create_and_withdraw() -> anyhow::Result<()> {
+    let env = setup().await?;
+    let timelock = block_timestamp(&env.worker).await? + HOUR;
+
+    let alice_before = balance(&env.alice).await?;
+    let htlc_id: String = create_htlc(&env, None, timelock)
+        .await?
+        .into_result()?
+        .json()?;
+    assert_eq!(htlc_id, "htlc_1");
+    assert!(balance(&env.alice).await? < alice_before.saturating_sub(AMOUNT));
+
+    // Wrong secret first
+    let result = env
+        .bob
+        .call(env.htlc.id(), "withdraw")
+        .args_json(json!({ "htlc_id": htlc_id, "secret": encode(&[0u8; 32]) }))
+        .transact()
+        .await?;
+    assert_failure(result, "Invalid secret");
+
+    let contract_before = env.htlc.view_account().await?.balance;
+    let bob_before = balance(&env.bob).await?;
+    let result = env
+        .bob
+        .call(env.htlc.id(), "withdraw")
+        .args_json(json!({ "htlc_id": htlc_id, "secret": encode(&SECRET) }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(result.total_gas_burnt < Gas::from_tgas(20));
+    assert!(result
+        .logs()
+        .iter()
+        .any(|log| log.contains("\"event_type\":\"htlc_withdrawn\"")));
+    result.into_result()?;
+
+    let bob_after = balance(&env.bob).await?;
+    assert!(
+        bob_after
+            > bob_before
+                .saturating_add(AMOUNT)
+                .saturating_sub(GAS_COST_MARGIN)
+    );
+    // The contract earns a share of the gas, so allow the margin here too
+    assert!(
+        env.htlc.view_account().await?.balance
+            < contract_before
+                .saturating_sub(AMOUNT)
+                .saturating_add(GAS_COST_MARGIN)
+    );
+
+    let htlc = get_htlc(&env, &htlc_id).await?;
+    assert_eq!(htlc["withdrawn"], true);
+
+    // Second withdrawal is rejected and pays nothing
+    let result = env
+        .bob
+        .call(env.htlc.id(), "withdraw")
+        .args_json(json!({ "htlc_id": htlc_id, "secret": encode(&SECRET) }))
+        .transact()
+        .await?;
+    assert_failure(result, "Already withdrawn");
+    assert!(balance(&env.bob).await? <= bob_after);
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_create_and_refund() -> anyhow::Result<()> {
+    let env = setup().await?;
+    let timelock = block_timestamp(&env.worker).await? + 3_000_000_000;
+    let htlc_id: String = create_htlc(&env, None, timelock)
+        .await?
+        .into_result()?
+        .json()?;
+
+    let result = env
+        .alice
+        .call(env.htlc.id(), "refund")
+        .args_json(json!({ "htlc_id": htlc_id }))
+        .transact()
+        .await?;
+    assert_failure(result, "Timelock not expired");
+
+    while block_timestamp(&env.worker).await? <= timelock {
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    }
+
+    let alice_before = balance(&env.alice).await?;
+    env.alice
+        .call(env.htlc.id(), "refund")
+        .args_json(json!({ "htlc_id": htlc_id }))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?;
+    assert!(
+        balance(&env.alice).await?
+            > alice_before
+                .saturating_add(AMOUNT)
+                .saturating_sub(GAS_COST_MARGIN)
+    );
+    assert_eq!(get_htlc(&env, &htlc_id).await?["refunded"], true);
+
+    // The receiver can no longer claim
+    let result = env
+        .bob
+        .call(env.htlc.id(), "withdraw")
+        .args_json(json!({ "htlc_id": htlc_id, "secret": encode(&SECRET) }))
+        .transact()
+        .await?;
+    assert_failure(result, "Already refunded");
+    Ok(())
+}
+
+// Mock NEP-141 token with alice holding 1000 and the HTLC registered
+async fn setup_ft(env: &Env) -> anyhow::Result<Contract> {
+    let ft = env.worker.dev_deploy(&wasm("mock_ft")).await?;
+    ft.call("new").transact().await?.into_result()?;
+    for account_id in [env.alice.id(), env.htlc.id()] {
+        register_ft(&ft, account_id).await?;
+    }
+    ft.call("mint")
+        .args_json(json!({ "account_id": env.alice.id(), "amount": "1000" }))
+        .transact()
+        .await?
+        .into_result()?;
+    Ok(ft)
+}
+
+async fn register_ft(ft: &Contract, account_id: &AccountId) -> anyhow::Result<()> {
+    ft.call("register")
+        .args_json(json!({ "account_id": account_id }))
+        .transact()
+        .await?
+        .into_result()?;
+    Ok(())
+}
+
+async fn ft_balance(ft: &Contract, account_id: &AccountId) -> anyhow::Result<String> {
+    Ok(ft
+        .view("ft_balance_of")
+        .args_json(json!({ "account_id": account_id }))
+        .await?
+        .json()?)
+}
+
+// Locks tokens with ft_transfer_call; `msg` carries the create_htlc arguments
+async fn ft_transfer_call(env: &Env, ft: &Contract, msg: String) -> anyhow::Result<String> {
+    Ok(env
+        .alice
+        .call(ft.id(), "ft_transfer_call")
+        .args_json(json!({
+            "receiver_id": env.htlc.id(),
+            "amount": "400",
+            "msg": msg,
+        }))
+        .deposit(NearToken::from_yoctonear(1))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?
+        .json()?)
+}
+
+// NEP-141 tokens are locked with ft_transfer_call, not create_htlc, and paid
+// out with ft_transfer
+#[tokio::test]
+#[ignore]
+async fn test_fungible_token_flows() -> anyhow::Result<()> {
+    let env = setup().await?;
+    let ft = setup_ft(&env).await?;
+    register_ft(&ft, env.bob.id()).await?;
+
+    let timelock = block_timestamp(&env.worker).await? + HOUR;
+    let result = create_htlc(&env, Some(ft.id()), timelock).await?;
+    assert_failure(result, "Lock NEP-141 tokens with ft_transfer_call");
+
+    // An invalid message fails the receiver call, and the resolver refunds
+    assert_eq!(ft_transfer_call(&env, &ft, String::new()).await?, "0");
+    assert_eq!(ft_balance(&ft, env.alice.id()).await?, "1000");
+    assert_eq!(ft_balance(&ft, env.htlc.id()).await?, "0");
+
+    let args = htlc_args(env.bob.id(), Some(ft.id()), 400, timelock);
+    assert_eq!(ft_transfer_call(&env, &ft, args.to_string()).await?, "400");
+    assert_eq!(ft_balance(&ft, env.alice.id()).await?, "600");
+    assert_eq!(ft_balance(&ft, env.htlc.id()).await?, "400");
+    let htlc = get_htlc(&env, "htlc_1").await?;
+    assert_eq!(htlc["token"], json!(ft.id()));
+
+    let result = env
+        .bob
+        .call(env.htlc.id(), "withdraw")
+        .args_json(json!({ "htlc_id": "htlc_1", "secret": encode(&SECRET) }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(result.receipt_failures().is_empty());
+    result.into_result()?;
+    assert_eq!(ft_balance(&ft, env.bob.id()).await?, "400");
+    assert_eq!(ft_balance(&ft, env.htlc.id()).await?, "0");
+    assert_eq!(get_htlc(&env, "htlc_1").await?["withdrawn"], true);
+    Ok(())
+}
+
+// ft_transfer to an account not registered with the token fails; the tokens
+// become claimable and are paid out once the receiver registers
+#[tokio::test]
+#[ignore]
+async fn test_ft_payout_to_unregistered_account() -> anyhow::Result<()> {
+    let env = setup().await?;
+    let ft = setup_ft(&env).await?;
+    let timelock = block_timestamp(&env.worker).await? + HOUR;
+    let args = htlc_args(env.bob.id(), Some(ft.id()), 400, timelock);
+    ft_transfer_call(&env, &ft, args.to_string()).await?;
+
+    let result = env
+        .bob
+        .call(env.htlc.id(), "withdraw")
+        .args_json(json!({ "htlc_id": "htlc_1", "secret": encode(&SECRET) }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(!result.receipt_failures().is_empty());
+    assert!(result
+        .logs()
+        .iter()
+        .any(|log| log.contains("\"event_type\":\"payout_failed\"")));
+    assert_eq!(ft_balance(&ft, env.htlc.id()).await?, "400");
+    assert_eq!(get_claimable(&env, env.bob.id(), Some(ft.id())).await?, "400");
+
+    register_ft(&ft, env.bob.id()).await?;
+    env.bob
+        .call(env.htlc.id(), "claim")
+        .args_json(json!({ "token": ft.id() }))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?;
+    assert_eq!(ft_balance(&ft, env.bob.id()).await?, "400");
+    assert_eq!(get_claimable(&env, env.bob.id(), Some(ft.id())).await?, "0");
+    Ok(())
+}
+
+// The payout is a separate receipt: a receiver that deletes its account
+// before it lands makes the transfer fail. The NEAR comes back to the HTLC
+// contract, is recorded as claimable and is paid out once the account is
+// created again.
+#[tokio::test]
+#[ignore]
+async fn test_payout_to_deleted_account() -> anyhow::Result<()> {
+    let env = setup().await?;
+    let root = env.worker.root_account()?;
+    let carol = root
+        .create_subaccount("carol")
+        .initial_balance(NearToken::from_near(10))
+        .transact()
+        .await?
+        .into_result()?;
+    carol
+        .deploy(&wasm("mock_receiver"))
+        .await?
+        .into_result()?;
+
+    let timelock = block_timestamp(&env.worker).await? + HOUR;
+    let htlc_id: String = create_htlc_for(&env, carol.id(), None, timelock)
+        .await?
+        .into_result()?
+        .json()?;
+
+    // Withdraws and deletes carol in the same block, ahead of the payout
+    let result = carol
+        .call(carol.id(), "withdraw_and_delete")
+        .args_json(json!({
+            "htlc": env.htlc.id(),
+            "htlc_id": htlc_id,
+            "secret": encode(&SECRET),
+            "beneficiary": root.id(),
+        }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(result.is_success());
+    assert!(!result.receipt_failures().is_empty());
+    assert!(result
+        .logs()
+        .iter()
+        .any(|log| log.contains("\"event_type\":\"payout_failed\"")));
+    assert!(carol.view_account().await.is_err());
+    assert_eq!(get_htlc(&env, &htlc_id).await?["withdrawn"], true);
+    assert_eq!(
+        get_claimable(&env, carol.id(), None).await?,
+        AMOUNT.as_yoctonear().to_string()
+    );
+
+    let carol = root
+        .create_subaccount("carol")
+        .initial_balance(NearToken::from_near(1))
+        .transact()
+        .await?
+        .into_result()?;
+    let carol_before = balance(&carol).await?;
+    carol
+        .call(env.htlc.id(), "claim")
+        .args_json(json!({ "token": null }))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?;
+    assert!(
+        balance(&carol).await?
+            > carol_before
+                .saturating_add(AMOUNT)
+                .saturating_sub(GAS_COST_MARGIN)
+    );
+    assert_eq!(get_claimable(&env, carol.id(), None).await?, "0");
+
+    // Nothing is paid twice
+    let result = carol
+        .call(env.htlc.id(), "claim")
+        .args_json(json!({ "token": null }))
+        .transact()
+        .await?;
+    assert_failure(result, "Nothing to claim");
+    Ok(())
+}
+
+// Registry notifications are fire-and-forget, so a missing registry account
+// must not block the payout
+#[tokio::test]
+#[ignore]
+async fn test_withdraw_with_unreachable_registry() -> anyhow::Result<()> {
+    let env = setup().await?;
+    env.htlc
+        .call("set_solver_registry")
+        .args_json(json!({ "solver_registry": "missing-registry.test.near" }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let timelock = block_timestamp(&env.worker).await? + HOUR;
+    let htlc_id: String = create_htlc(&env, None, timelock)
+        .await?
+        .into_result()?
+        .json()?;
+
+    let bob_before = balance(&env.bob).await?;
+    let result = env
+        .bob
+        .call(env.htlc.id(), "withdraw")
+        .args_json(json!({ "htlc_id": htlc_id, "secret": encode(&SECRET) }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(result.is_success());
+    assert!(!result.receipt_failures().is_empty());
+    assert!(
+        balance(&env.bob).await?
+            > bob_before
+                .saturating_add(AMOUNT)
+                .saturating_sub(GAS_COST_MARGIN)
+    );
+    Ok(())
+}
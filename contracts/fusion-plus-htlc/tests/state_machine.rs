@@ -0,0 +1,323 @@
+// Property-based tests of the HTLC state machine. Random sequences of
+// create/withdraw/refund calls, time advances and callers run against the
+// contract in the mocked runtime and are checked against a simple model:
+//
+// - every call succeeds exactly when the model says it should, and a
+//   rejected call leaves the contract state untouched
+// - funds are conserved: the deposits the contract accepted equal the
+//   transfers it made plus the locked amount get_stats reports, and that
+//   amount matches the model
+// - each HTLC pays out once, to its receiver on withdraw or its sender on
+//   refund, and refunds only happen once the timelock has passed
+// - get_active_htlcs lists exactly the HTLCs that are not finalized
+
+use std::collections::{BTreeSet, HashMap};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use fusion_plus_htlc::{CreateHTLCArgs, FusionPlusHTLC};
+use near_sdk::json_types::{Base64VecU8, U128};
+use near_sdk::mock::MockAction;
+use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+use near_sdk::{
+    env, test_vm_config, AccountId, MockedBlockchain, NearToken, PromiseOrValue, RuntimeFeesConfig,
+};
+use proptest::prelude::*;
+use sha3::{Digest, Keccak256};
+
+const START_TIME: u64 = 1_000;
+const NUM_ACCOUNTS: usize = 3;
+const NUM_SECRETS: u8 = 3;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Create {
+        sender: usize,
+        receiver: usize,
+        amount: u128,
+        timelock_offset: u64,
+        secret: u8,
+        short_deposit: bool,
+    },
+    Withdraw {
+        caller: usize,
+        htlc: usize,
+        secret: u8,
+    },
+    Refund {
+        caller: usize,
+        htlc: usize,
+    },
+    AdvanceTime(u64),
+}
+
+#[derive(Clone, Debug)]
+struct ModelHtlc {
+    sender: AccountId,
+    receiver: AccountId,
+    amount: u128,
+    secret: u8,
+    timelock: u64,
+    finalized: bool,
+}
+
+#[derive(Default)]
+struct Model {
+    now: u64,
+    htlcs: Vec<ModelHtlc>,
+    // Attached deposits of accepted calls and transfer receipts of all calls
+    deposited: u128,
+    transferred: u128,
+}
+
+impl Model {
+    fn locked(&self) -> u128 {
+        self.htlcs
+            .iter()
+            .filter(|h| !h.finalized)
+            .map(|h| h.amount)
+            .sum()
+    }
+
+    fn active_ids(&self) -> BTreeSet<String> {
+        (0..self.htlcs.len())
+            .filter(|&i| !self.htlcs[i].finalized)
+            .map(htlc_id)
+            .collect()
+    }
+}
+
+fn account(index: usize) -> AccountId {
+    accounts(index + 1)
+}
+
+fn htlc_id(index: usize) -> String {
+    format!("htlc_{}", index + 1)
+}
+
+fn secret(index: u8) -> Vec<u8> {
+    vec![index; 32]
+}
+
+fn op_strategy() -> impl Strategy<Value = Op> {
+    let caller = 0..NUM_ACCOUNTS;
+    // Indexes past the created HTLCs exercise unknown ids
+    let htlc = 0..8usize;
+    prop_oneof![
+        3 => (caller.clone(), caller.clone(), 1..1_000u128, 1..100u64, 0..NUM_SECRETS, prop::bool::weighted(0.1))
+            .prop_map(|(sender, receiver, amount, timelock_offset, secret, short_deposit)| Op::Create {
+                sender,
+                receiver,
+                amount,
+                timelock_offset,
+                secret,
+                short_deposit,
+            }),
+        3 => (caller.clone(), htlc.clone(), 0..NUM_SECRETS)
+            .prop_map(|(caller, htlc, secret)| Op::Withdraw { caller, htlc, secret }),
+        2 => (caller, htlc).prop_map(|(caller, htlc)| Op::Refund { caller, htlc }),
+        2 => (0..60u64).prop_map(Op::AdvanceTime),
+    ]
+}
+
+// Fresh mocked runtime for the next call; storage is carried over unless
+// `reset` is set, which starts a new contract
+fn set_context(caller: &AccountId, now: u64, deposit: u128, reset: bool) {
+    let context = VMContextBuilder::new()
+        .current_account_id(accounts(0))
+        .signer_account_id(caller.clone())
+        .predecessor_account_id(caller.clone())
+        .block_timestamp(now)
+        .attached_deposit(NearToken::from_yoctonear(deposit))
+        .build();
+    let storage = if reset {
+        HashMap::new()
+    } else {
+        near_sdk::mock::with_mocked_blockchain(|b| b.take_storage())
+    };
+    env::set_blockchain_interface(MockedBlockchain::new(
+        context,
+        test_vm_config(),
+        RuntimeFeesConfig::test(),
+        vec![],
+        storage,
+        HashMap::new(),
+        None,
+    ));
+}
+
+// Transfers made by the last call as (receiver, amount)
+fn transfers() -> Vec<(AccountId, u128)> {
+    get_created_receipts()
+        .into_iter()
+        .flat_map(|receipt| {
+            let receiver_id = receipt.receiver_id.clone();
+            receipt
+                .actions
+                .into_iter()
+                .filter_map(move |action| match action {
+                    MockAction::Transfer { deposit, .. } => {
+                        Some((receiver_id.clone(), deposit.as_yoctonear()))
+                    }
+                    _ => None,
+                })
+        })
+        .collect()
+}
+
+// Withdrawn/refunded flags of every HTLC id the model knows of, plus one
+// past the end
+fn snapshot(contract: &FusionPlusHTLC, model: &Model) -> Vec<Option<(bool, bool)>> {
+    (0..=model.htlcs.len())
+        .map(|i| {
+            contract
+                .get_htlc(htlc_id(i))
+                .map(|h| (h.withdrawn, h.refunded))
+        })
+        .collect()
+}
+
+fn active_ids(contract: &FusionPlusHTLC) -> BTreeSet<String> {
+    contract
+        .get_active_htlcs(0, 1_000)
+        .into_iter()
+        .map(|h| h.htlc_id)
+        .collect()
+}
+
+fn run(ops: Vec<Op>) -> Result<(), TestCaseError> {
+    let owner = accounts(0);
+    set_context(&owner, START_TIME, 0, true);
+    let mut contract = FusionPlusHTLC::new(owner);
+    let mut model = Model {
+        now: START_TIME,
+        ..Default::default()
+    };
+
+    for op in ops {
+        let before = snapshot(&contract, &model);
+        // Expected payout (receiver, amount) and model update on success
+        let (succeeded, expected_payout) = match op {
+            Op::Create {
+                sender,
+                receiver,
+                amount,
+                timelock_offset,
+                secret: secret_index,
+                short_deposit,
+            } => {
+                let sender = account(sender);
+                let deposit = if short_deposit { amount - 1 } else { amount };
+                set_context(&sender, model.now, deposit, false);
+                let args = CreateHTLCArgs {
+                    receiver: account(receiver),
+                    token: None,
+                    amount: U128(amount),
+                    hashlock: Base64VecU8(Keccak256::digest(secret(secret_index)).to_vec()),
+                    timelock: U128((model.now + timelock_offset) as u128),
+                    order_hash: Base64VecU8(vec![1u8; 32]),
+                    base_escrow: None,
+                };
+                let result = catch_unwind(AssertUnwindSafe(|| contract.create_htlc(args)));
+
+                prop_assert_eq!(result.is_ok(), !short_deposit);
+                if let Ok(result) = result {
+                    match result {
+                        PromiseOrValue::Value(id) => {
+                            prop_assert_eq!(id, htlc_id(model.htlcs.len()))
+                        }
+                        PromiseOrValue::Promise(_) => prop_assert!(false, "unexpected promise"),
+                    }
+                    model.deposited += deposit;
+                    model.htlcs.push(ModelHtlc {
+                        sender,
+                        receiver: account(receiver),
+                        amount,
+                        secret: secret_index,
+                        timelock: model.now + timelock_offset,
+                        finalized: false,
+                    });
+                }
+                (!short_deposit, None)
+            }
+            Op::Withdraw {
+                caller,
+                htlc,
+                secret: secret_index,
+            } => {
+                let caller = account(caller);
+                set_context(&caller, model.now, 0, false);
+                let allowed = model.htlcs.get(htlc).is_some_and(|h| {
+                    !h.finalized && h.receiver == caller && h.secret == secret_index
+                });
+                let result = catch_unwind(AssertUnwindSafe(|| {
+                    contract.withdraw(htlc_id(htlc), Base64VecU8(secret(secret_index)))
+                }));
+                prop_assert_eq!(result.is_ok(), allowed, "withdraw {:?}", op);
+
+                if allowed {
+                    let h = &mut model.htlcs[htlc];
+                    h.finalized = true;
+                    (true, Some((h.receiver.clone(), h.amount)))
+                } else {
+                    (false, None)
+                }
+            }
+            Op::Refund { caller, htlc } => {
+                let caller = account(caller);
+                set_context(&caller, model.now, 0, false);
+                let allowed = model
+                    .htlcs
+                    .get(htlc)
+                    .is_some_and(|h| !h.finalized && h.sender == caller && model.now >= h.timelock);
+                let result = catch_unwind(AssertUnwindSafe(|| contract.refund(htlc_id(htlc))));
+                prop_assert_eq!(result.is_ok(), allowed, "refund {:?}", op);
+
+                if allowed {
+                    let h = &mut model.htlcs[htlc];
+                    prop_assert!(model.now >= h.timelock);
+                    h.finalized = true;
+                    (true, Some((h.sender.clone(), h.amount)))
+                } else {
+                    (false, None)
+                }
+            }
+            Op::AdvanceTime(dt) => {
+                model.now += dt;
+                continue;
+            }
+        };
+
+        // Exactly the expected payout, nothing on rejected calls
+        let transfers = transfers();
+        model.transferred += transfers.iter().map(|(_, amount)| amount).sum::<u128>();
+        match expected_payout {
+            Some(payout) => prop_assert_eq!(&transfers, &vec![payout]),
+            None => prop_assert!(transfers.is_empty(), "unexpected transfers {:?}", transfers),
+        }
+        if !succeeded {
+            prop_assert_eq!(&snapshot(&contract, &model)[..before.len()], &before[..]);
+        }
+
+        let locked: u128 = contract.get_stats().locked.iter().map(|t| t.amount.0).sum();
+        prop_assert_eq!(model.deposited, model.transferred + locked);
+        prop_assert_eq!(locked, model.locked());
+        prop_assert_eq!(active_ids(&contract), model.active_ids());
+        for (i, h) in model.htlcs.iter().enumerate() {
+            let view = contract.get_htlc(htlc_id(i)).unwrap();
+            prop_assert_eq!(view.withdrawn || view.refunded, h.finalized);
+            prop_assert!(!(view.withdrawn && view.refunded));
+        }
+    }
+    Ok(())
+}
+
+// Every call sets up a fresh mocked runtime, which dominates the run time;
+// raise the case count for longer fuzzing sessions
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn test_htlc_state_machine(ops in prop::collection::vec(op_strategy(), 1..40)) {
+        run(ops)?;
+    }
+}
@@ -0,0 +1,146 @@
+// Solidity ABI encoding of call arguments.
+//
+// Static values take one 32-byte word in the head. Dynamic values (bytes and
+// tuples containing them) put an offset in the head and their contents in
+// the tail, as laid out in the Solidity ABI specification.
+
+use alloc::vec::Vec;
+use sha3::{Digest, Keccak256};
+
+use crate::evm::Address;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Token {
+    // Big-endian uint256
+    Uint([u8; 32]),
+    Address(Address),
+    FixedBytes32([u8; 32]),
+    Bool(bool),
+    Bytes(Vec<u8>),
+    Tuple(Vec<Token>),
+}
+
+impl Token {
+    pub fn uint(value: u128) -> Self {
+        let mut word = [0u8; 32];
+        word[16..].copy_from_slice(&value.to_be_bytes());
+        Token::Uint(word)
+    }
+
+    fn is_dynamic(&self) -> bool {
+        match self {
+            Token::Bytes(_) => true,
+            Token::Tuple(tokens) => tokens.iter().any(Token::is_dynamic),
+            _ => false,
+        }
+    }
+
+    // Bytes taken in the head of the enclosing tuple
+    fn head_len(&self) -> usize {
+        match self {
+            Token::Tuple(tokens) if !self.is_dynamic() => tokens.iter().map(Token::head_len).sum(),
+            _ => 32,
+        }
+    }
+
+    // Word for single-word values; left-padded for numbers and addresses
+    fn to_word(&self) -> [u8; 32] {
+        let mut word = [0u8; 32];
+        match self {
+            Token::Uint(value) | Token::FixedBytes32(value) => word = *value,
+            Token::Address(address) => word[12..].copy_from_slice(address),
+            Token::Bool(value) => word[31] = *value as u8,
+            Token::Bytes(_) | Token::Tuple(_) => unreachable!("not a single-word value"),
+        }
+        word
+    }
+}
+
+// First four bytes of keccak256 of the canonical signature,
+// e.g. "transfer(address,uint256)"
+pub fn selector(signature: &str) -> [u8; 4] {
+    let hash = Keccak256::digest(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+// Arguments are encoded like the members of a tuple
+pub fn encode(tokens: &[Token]) -> Vec<u8> {
+    let head_len: usize = tokens.iter().map(Token::head_len).sum();
+    let mut head = Vec::with_capacity(head_len);
+    let mut tail = Vec::new();
+
+    for token in tokens {
+        if token.is_dynamic() {
+            head.extend_from_slice(&Token::uint((head_len + tail.len()) as u128).to_word());
+            encode_into(&mut tail, token);
+        } else {
+            encode_into(&mut head, token);
+        }
+    }
+    head.extend_from_slice(&tail);
+    head
+}
+
+pub fn encode_call(signature: &str, tokens: &[Token]) -> Vec<u8> {
+    let mut out = Vec::from(selector(signature));
+    out.extend_from_slice(&encode(tokens));
+    out
+}
+
+fn encode_into(out: &mut Vec<u8>, token: &Token) {
+    match token {
+        Token::Uint(_) | Token::FixedBytes32(_) | Token::Address(_) | Token::Bool(_) => {
+            out.extend_from_slice(&token.to_word())
+        }
+        Token::Bytes(bytes) => {
+            out.extend_from_slice(&Token::uint(bytes.len() as u128).to_word());
+            out.extend_from_slice(bytes);
+            out.resize(out.len() + (32 - bytes.len() % 32) % 32, 0);
+        }
+        Token::Tuple(tokens) => out.extend_from_slice(&encode(tokens)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selectors() {
+        assert_eq!(hex::encode(selector("transfer(address,uint256)")), "a9059cbb");
+        assert_eq!(hex::encode(selector("baz(uint32,bool)")), "cdcd77c0");
+    }
+
+    // Example from the Solidity ABI specification
+    #[test]
+    fn test_encode_static_call() {
+        let calldata = encode_call("baz(uint32,bool)", &[Token::uint(69), Token::Bool(true)]);
+        assert_eq!(
+            hex::encode(calldata),
+            "cdcd77c0\
+             0000000000000000000000000000000000000000000000000000000000000045\
+             0000000000000000000000000000000000000000000000000000000000000001"
+        );
+    }
+
+    #[test]
+    fn test_encode_dynamic_arguments() {
+        // f(uint256,bytes,(address,bytes)) with (7, "hello", (0x33.., ""))
+        let encoded = encode(&[
+            Token::uint(7),
+            Token::Bytes(b"hello".to_vec()),
+            Token::Tuple(vec![Token::Address([0x33; 20]), Token::Bytes(Vec::new())]),
+        ]);
+        assert_eq!(
+            hex::encode(encoded),
+            "0000000000000000000000000000000000000000000000000000000000000007\
+             0000000000000000000000000000000000000000000000000000000000000060\
+             00000000000000000000000000000000000000000000000000000000000000a0\
+             0000000000000000000000000000000000000000000000000000000000000005\
+             68656c6c6f000000000000000000000000000000000000000000000000000000\
+             0000000000000000000000003333333333333333333333333333333333333333\
+             0000000000000000000000000000000000000000000000000000000000000040\
+             0000000000000000000000000000000000000000000000000000000000000000"
+        );
+    }
+}
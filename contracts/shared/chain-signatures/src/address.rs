@@ -0,0 +1,163 @@
+// Foreign-chain addresses for derived secp256k1 keys.
+
+use bech32::{hrp, segwit, Hrp};
+use near_sdk::PublicKey;
+use ripemd::Ripemd160;
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+
+use crate::kdf::{compressed_key, to_affine};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BitcoinNetwork {
+    Mainnet,
+    // Also used by signet
+    Testnet,
+    Regtest,
+}
+
+impl BitcoinNetwork {
+    pub(crate) fn hrp(self) -> Hrp {
+        match self {
+            BitcoinNetwork::Mainnet => hrp::BC,
+            BitcoinNetwork::Testnet => hrp::TB,
+            BitcoinNetwork::Regtest => hrp::BCRT,
+        }
+    }
+}
+
+// Last 20 bytes of keccak256 over the uncompressed key without its 0x04 tag
+pub fn ethereum_address(key: &PublicKey) -> Result<[u8; 20], &'static str> {
+    to_affine(key)?;
+    let hash = Keccak256::digest(&key.as_bytes()[1..]);
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash[12..]);
+    Ok(address)
+}
+
+// 0x-prefixed EIP-55 mixed-case checksum encoding
+pub fn to_checksum_address(address: &[u8; 20]) -> String {
+    let lower = hex::encode(address);
+    let hash = Keccak256::digest(lower.as_bytes());
+    let checksummed: String = lower
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0x0f;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+    format!("0x{checksummed}")
+}
+
+// ripemd160(sha256(data))
+pub fn hash160(data: &[u8]) -> [u8; 20] {
+    Ripemd160::digest(Sha256::digest(data)).into()
+}
+
+// Native segwit v0 address paying to the compressed key
+pub fn bitcoin_p2wpkh_address(
+    key: &PublicKey,
+    network: BitcoinNetwork,
+) -> Result<String, &'static str> {
+    let program = hash160(&compressed_key(key)?);
+    segwit::encode_v0(network.hrp(), &program).map_err(|_| "Invalid witness program")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kdf::tests::{point_key, scalar, G, G2, TESTNET_ROOT_KEY};
+    use crate::kdf::{derive_epsilon, derive_key};
+
+    #[test]
+    fn test_generator_addresses() {
+        let key = point_key(G);
+        // Ethereum address of private key 1
+        assert_eq!(
+            to_checksum_address(&ethereum_address(&key).unwrap()),
+            "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf"
+        );
+        // BIP-173 P2WPKH examples for the compressed key
+        // 0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798,
+        // https://github.com/bitcoin/bips/blob/master/bip-0173.mediawiki#examples
+        assert_eq!(
+            bitcoin_p2wpkh_address(&key, BitcoinNetwork::Mainnet).unwrap(),
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+        );
+        assert_eq!(
+            bitcoin_p2wpkh_address(&key, BitcoinNetwork::Testnet).unwrap(),
+            "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"
+        );
+    }
+
+    #[test]
+    fn test_derived_addresses() {
+        // Deriving from G with epsilon 1 and 2 gives the keys of the private
+        // keys 2 and 3, whose addresses are the well-known ones that any
+        // Ethereum tool reproduces, e.g. `cast wallet address 0x…02`
+        let root = point_key(G);
+        let cases = [
+            (1, "0x2B5AD5c4795c026514f8317c7a215E218DcCD6cF"),
+            (2, "0x6813Eb9362372EEF6200f3b1dbC3f819671cBA69"),
+        ];
+        for (epsilon, expected) in cases {
+            let key = derive_key(&root, &scalar(epsilon)).unwrap();
+            assert_eq!(to_checksum_address(&ethereum_address(&key).unwrap()), expected);
+        }
+        assert_eq!(derive_key(&root, &scalar(1)), Ok(point_key(G2)));
+    }
+
+    #[test]
+    fn test_testnet_addresses() {
+        // (predecessor, path) -> epsilon -> derived key -> addresses, see
+        // TESTNET_ROOT_KEY for where the expected values come from
+        let root = TESTNET_ROOT_KEY.parse().unwrap();
+        let cases = [
+            (
+                "alice.testnet",
+                "ethereum-1",
+                "0xfe306917CD4Ee89a2AE69514f167222aCe0eAa7D",
+                "tb1qkvssj4hmaqrhcraa358qdxqmmrvgnmych74s43",
+            ),
+            (
+                "alice.testnet",
+                "bitcoin-1",
+                "0xE58E36c0431B2bC64889f9A5cFDB7Ffd6d3a6b6B",
+                "tb1qd7hxdsqaa6l6lllaa9gqccvez9744r8z93dka5",
+            ),
+            (
+                "fusion-htlc.testnet",
+                "base-1",
+                "0x4DE6e9fBd5F0d9708dd9c84cd4C380c8d2D387a4",
+                "tb1q5l9ql0c74dvklf2xnk3xzf5adkkg6c0rexcq6a",
+            ),
+        ];
+        for (predecessor, path, eth, btc) in cases {
+            let epsilon = derive_epsilon(&predecessor.parse().unwrap(), path);
+            let key = derive_key(&root, &epsilon).unwrap();
+            assert_eq!(to_checksum_address(&ethereum_address(&key).unwrap()), eth);
+            assert_eq!(bitcoin_p2wpkh_address(&key, BitcoinNetwork::Testnet).unwrap(), btc);
+        }
+    }
+
+    #[test]
+    fn test_checksum_address() {
+        // EIP-55 test cases,
+        // https://github.com/ethereum/ercs/blob/master/ERCS/erc-55.md#test-cases
+        let cases = [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ];
+        for expected in cases {
+            let address: [u8; 20] = hex::decode(&expected[2..]).unwrap().try_into().unwrap();
+            assert_eq!(to_checksum_address(&address), expected);
+        }
+    }
+}
@@ -0,0 +1,632 @@
+// Bitcoin HTLCs for NEAR<->BTC swaps.
+//
+// The redeem script is the standard BIP-199 HTLC, paid to as P2WSH. Spends
+// are segwit v0, so the payload to sign is the BIP-143 sighash of the input;
+// the MPC signature then goes into the witness as DER plus the sighash type.
+// Bitcoin has no keccak opcode: the same secret unlocks both chains, but the
+// Bitcoin side commits to its SHA-256 or HASH160 digest.
+
+use bech32::segwit;
+use near_sdk::PublicKey;
+use sha2::{Digest, Sha256};
+
+use crate::address::{hash160, BitcoinNetwork};
+use crate::ecdsa::Signature;
+use crate::kdf::compressed_key;
+
+pub const SIGHASH_ALL: u32 = 0x01;
+pub const SIGHASH_NONE: u32 = 0x02;
+pub const SIGHASH_SINGLE: u32 = 0x03;
+pub const SIGHASH_ANYONECANPAY: u32 = 0x80;
+
+// nLockTime values below this are block heights, the rest unix timestamps
+pub const LOCKTIME_THRESHOLD: u32 = 500_000_000;
+// Final inputs disable nLockTime; refunds must use a lower sequence
+pub const SEQUENCE_FINAL: u32 = 0xffff_ffff;
+// Opts in to replace-by-fee and keeps nLockTime enforced
+pub const SEQUENCE_RBF: u32 = 0xffff_fffd;
+
+const OP_0: u8 = 0x00;
+const OP_1: u8 = 0x51;
+const OP_IF: u8 = 0x63;
+const OP_ELSE: u8 = 0x67;
+const OP_ENDIF: u8 = 0x68;
+const OP_DROP: u8 = 0x75;
+const OP_DUP: u8 = 0x76;
+const OP_EQUALVERIFY: u8 = 0x88;
+const OP_SHA256: u8 = 0xa8;
+const OP_HASH160: u8 = 0xa9;
+const OP_CHECKSIG: u8 = 0xac;
+const OP_CHECKLOCKTIMEVERIFY: u8 = 0xb1;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Hashlock {
+    Sha256([u8; 32]),
+    Hash160([u8; 20]),
+}
+
+// BIP-199 HTLC. The recipient claims with the preimage at any time; the
+// refund key can spend once the chain passes the locktime.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BitcoinHtlc {
+    pub hashlock: Hashlock,
+    // hash160 of the recipient's compressed key
+    pub recipient: [u8; 20],
+    // hash160 of the refund compressed key
+    pub refund: [u8; 20],
+    // Block height, or unix timestamp from LOCKTIME_THRESHOLD on
+    pub locktime: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct OutPoint {
+    // Internal byte order, i.e. reversed from the hex shown by explorers
+    pub txid: [u8; 32],
+    pub vout: u32,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct TxIn {
+    pub previous_output: OutPoint,
+    pub sequence: u32,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct TxOut {
+    // Satoshis
+    pub value: u64,
+    pub script_pubkey: Vec<u8>,
+}
+
+// Unsigned transaction; witnesses are supplied when encoding
+#[derive(Clone, PartialEq, Debug)]
+pub struct Transaction {
+    pub version: i32,
+    pub inputs: Vec<TxIn>,
+    pub outputs: Vec<TxOut>,
+    pub lock_time: u32,
+}
+
+// hash160 of the compressed key, as committed to by the HTLC and P2WPKH
+pub fn pubkey_hash(key: &PublicKey) -> Result<[u8; 20], &'static str> {
+    Ok(hash160(&compressed_key(key)?))
+}
+
+// OP_0 <sha256(script)>
+pub fn p2wsh_script_pubkey(script: &[u8]) -> Vec<u8> {
+    let mut out = Vec::from([OP_0]);
+    push_slice(&mut out, &Sha256::digest(script));
+    out
+}
+
+pub fn p2wsh_address(script: &[u8], network: BitcoinNetwork) -> Result<String, &'static str> {
+    segwit::encode_v0(network.hrp(), &Sha256::digest(script)).map_err(|_| "Invalid witness program")
+}
+
+// OP_0 <hash160(key)>
+pub fn p2wpkh_script_pubkey(pubkey_hash: &[u8; 20]) -> Vec<u8> {
+    let mut out = Vec::from([OP_0]);
+    push_slice(&mut out, pubkey_hash);
+    out
+}
+
+impl BitcoinHtlc {
+    // OP_IF
+    //     [OP_SHA256|OP_HASH160] <digest> OP_EQUALVERIFY OP_DUP OP_HASH160 <recipient>
+    // OP_ELSE
+    //     <locktime> OP_CHECKLOCKTIMEVERIFY OP_DROP OP_DUP OP_HASH160 <refund>
+    // OP_ENDIF
+    // OP_EQUALVERIFY OP_CHECKSIG
+    pub fn redeem_script(&self) -> Vec<u8> {
+        let mut script = Vec::with_capacity(92);
+        script.push(OP_IF);
+        match &self.hashlock {
+            Hashlock::Sha256(digest) => {
+                script.push(OP_SHA256);
+                push_slice(&mut script, digest);
+            }
+            Hashlock::Hash160(digest) => {
+                script.push(OP_HASH160);
+                push_slice(&mut script, digest);
+            }
+        }
+        script.extend_from_slice(&[OP_EQUALVERIFY, OP_DUP, OP_HASH160]);
+        push_slice(&mut script, &self.recipient);
+        script.push(OP_ELSE);
+        push_int(&mut script, self.locktime);
+        script.extend_from_slice(&[OP_CHECKLOCKTIMEVERIFY, OP_DROP, OP_DUP, OP_HASH160]);
+        push_slice(&mut script, &self.refund);
+        script.extend_from_slice(&[OP_ENDIF, OP_EQUALVERIFY, OP_CHECKSIG]);
+        script
+    }
+
+    pub fn script_pubkey(&self) -> Vec<u8> {
+        p2wsh_script_pubkey(&self.redeem_script())
+    }
+
+    pub fn address(&self, network: BitcoinNetwork) -> Result<String, &'static str> {
+        p2wsh_address(&self.redeem_script(), network)
+    }
+
+    // Payload for the recipient's signature over input `index`, which
+    // spends `value` satoshis locked in this HTLC
+    pub fn claim_sighash(
+        &self,
+        tx: &Transaction,
+        index: usize,
+        value: u64,
+    ) -> Result<[u8; 32], &'static str> {
+        tx.segwit_v0_sighash(index, &self.redeem_script(), value, SIGHASH_ALL)
+    }
+
+    // As claim_sighash, but fails if the transaction could never satisfy
+    // OP_CHECKLOCKTIMEVERIFY
+    pub fn refund_sighash(
+        &self,
+        tx: &Transaction,
+        index: usize,
+        value: u64,
+    ) -> Result<[u8; 32], &'static str> {
+        let input = tx.inputs.get(index).ok_or("Input index out of range")?;
+        if input.sequence == SEQUENCE_FINAL {
+            return Err("Refund input must not be final");
+        }
+        if (tx.lock_time < LOCKTIME_THRESHOLD) != (self.locktime < LOCKTIME_THRESHOLD) {
+            return Err("Lock time kind does not match the HTLC");
+        }
+        if tx.lock_time < self.locktime {
+            return Err("Lock time is before the HTLC locktime");
+        }
+        tx.segwit_v0_sighash(index, &self.redeem_script(), value, SIGHASH_ALL)
+    }
+
+    // <signature> <pubkey> <preimage> 1 <redeem script>
+    pub fn claim_witness(
+        &self,
+        signature: &Signature,
+        pubkey: &[u8; 33],
+        preimage: &[u8],
+    ) -> Vec<Vec<u8>> {
+        Vec::from([
+            witness_signature(signature, SIGHASH_ALL),
+            pubkey.to_vec(),
+            preimage.to_vec(),
+            Vec::from([0x01]),
+            self.redeem_script(),
+        ])
+    }
+
+    // <signature> <pubkey> <empty> <redeem script>
+    pub fn refund_witness(&self, signature: &Signature, pubkey: &[u8; 33]) -> Vec<Vec<u8>> {
+        Vec::from([
+            witness_signature(signature, SIGHASH_ALL),
+            pubkey.to_vec(),
+            Vec::new(),
+            self.redeem_script(),
+        ])
+    }
+}
+
+// DER with low s (required for standardness) followed by the sighash type
+pub fn witness_signature(signature: &Signature, sighash_type: u32) -> Vec<u8> {
+    let mut out = signature.normalize_s().to_der();
+    out.push(sighash_type as u8);
+    out
+}
+
+impl TxOut {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.value.to_le_bytes());
+        write_var_bytes(out, &self.script_pubkey);
+    }
+}
+
+impl Transaction {
+    // Serialization without witnesses, as hashed for the txid and embedded
+    // in PSBTs
+    pub fn encode(&self) -> Vec<u8> {
+        self.encode_with_witnesses(None)
+    }
+
+    // Segwit serialization for broadcasting, one witness stack per input
+    pub fn encode_signed(&self, witnesses: &[Vec<Vec<u8>>]) -> Result<Vec<u8>, &'static str> {
+        if witnesses.len() != self.inputs.len() {
+            return Err("Witness count does not match inputs");
+        }
+        Ok(self.encode_with_witnesses(Some(witnesses)))
+    }
+
+    // Internal byte order, like OutPoint::txid
+    pub fn txid(&self) -> [u8; 32] {
+        sha256d(&self.encode())
+    }
+
+    // BIP-143 signature hash for a segwit v0 input. For P2WSH the script
+    // code is the witness script.
+    pub fn segwit_v0_sighash(
+        &self,
+        index: usize,
+        script_code: &[u8],
+        value: u64,
+        sighash_type: u32,
+    ) -> Result<[u8; 32], &'static str> {
+        let input = self.inputs.get(index).ok_or("Input index out of range")?;
+        let anyone_can_pay = sighash_type & SIGHASH_ANYONECANPAY != 0;
+        let base_type = sighash_type & 0x1f;
+
+        let hash_prevouts = if anyone_can_pay {
+            [0u8; 32]
+        } else {
+            let mut prevouts = Vec::with_capacity(36 * self.inputs.len());
+            for input in &self.inputs {
+                encode_outpoint(&mut prevouts, &input.previous_output);
+            }
+            sha256d(&prevouts)
+        };
+
+        let hash_sequence =
+            if anyone_can_pay || base_type == SIGHASH_SINGLE || base_type == SIGHASH_NONE {
+                [0u8; 32]
+            } else {
+                let sequences: Vec<u8> = self
+                    .inputs
+                    .iter()
+                    .flat_map(|input| input.sequence.to_le_bytes())
+                    .collect();
+                sha256d(&sequences)
+            };
+
+        let hash_outputs = if base_type != SIGHASH_SINGLE && base_type != SIGHASH_NONE {
+            let mut outputs = Vec::new();
+            for output in &self.outputs {
+                output.encode(&mut outputs);
+            }
+            sha256d(&outputs)
+        } else if base_type == SIGHASH_SINGLE && index < self.outputs.len() {
+            let mut output = Vec::new();
+            self.outputs[index].encode(&mut output);
+            sha256d(&output)
+        } else {
+            [0u8; 32]
+        };
+
+        let mut preimage = Vec::with_capacity(160 + script_code.len());
+        preimage.extend_from_slice(&self.version.to_le_bytes());
+        preimage.extend_from_slice(&hash_prevouts);
+        preimage.extend_from_slice(&hash_sequence);
+        encode_outpoint(&mut preimage, &input.previous_output);
+        write_var_bytes(&mut preimage, script_code);
+        preimage.extend_from_slice(&value.to_le_bytes());
+        preimage.extend_from_slice(&input.sequence.to_le_bytes());
+        preimage.extend_from_slice(&hash_outputs);
+        preimage.extend_from_slice(&self.lock_time.to_le_bytes());
+        preimage.extend_from_slice(&sighash_type.to_le_bytes());
+        Ok(sha256d(&preimage))
+    }
+
+    fn encode_with_witnesses(&self, witnesses: Option<&[Vec<Vec<u8>>]>) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&self.version.to_le_bytes());
+        if witnesses.is_some() {
+            // Segwit marker and flag
+            out.extend_from_slice(&[0x00, 0x01]);
+        }
+        write_compact_size(&mut out, self.inputs.len() as u64);
+        for input in &self.inputs {
+            encode_outpoint(&mut out, &input.previous_output);
+            // Empty scriptSig
+            write_compact_size(&mut out, 0);
+            out.extend_from_slice(&input.sequence.to_le_bytes());
+        }
+        write_compact_size(&mut out, self.outputs.len() as u64);
+        for output in &self.outputs {
+            output.encode(&mut out);
+        }
+        if let Some(witnesses) = witnesses {
+            for witness in witnesses {
+                encode_witness(&mut out, witness);
+            }
+        }
+        out.extend_from_slice(&self.lock_time.to_le_bytes());
+        out
+    }
+}
+
+pub(crate) fn encode_witness(out: &mut Vec<u8>, witness: &[Vec<u8>]) {
+    write_compact_size(out, witness.len() as u64);
+    for item in witness {
+        write_var_bytes(out, item);
+    }
+}
+
+pub(crate) fn write_compact_size(out: &mut Vec<u8>, n: u64) {
+    match n {
+        0..=0xfc => out.push(n as u8),
+        0xfd..=0xffff => {
+            out.push(0xfd);
+            out.extend_from_slice(&(n as u16).to_le_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(0xfe);
+            out.extend_from_slice(&(n as u32).to_le_bytes());
+        }
+        _ => {
+            out.push(0xff);
+            out.extend_from_slice(&n.to_le_bytes());
+        }
+    }
+}
+
+pub(crate) fn write_var_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_compact_size(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn encode_outpoint(out: &mut Vec<u8>, outpoint: &OutPoint) {
+    out.extend_from_slice(&outpoint.txid);
+    out.extend_from_slice(&outpoint.vout.to_le_bytes());
+}
+
+fn sha256d(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(Sha256::digest(data)).into()
+}
+
+// Direct push; every element pushed here is at most 32 bytes
+fn push_slice(script: &mut Vec<u8>, data: &[u8]) {
+    debug_assert!(data.len() <= 75);
+    script.push(data.len() as u8);
+    script.extend_from_slice(data);
+}
+
+// Minimal CScriptNum push of a non-negative number
+fn push_int(script: &mut Vec<u8>, n: u32) {
+    match n {
+        0 => script.push(OP_0),
+        1..=16 => script.push(OP_1 + n as u8 - 1),
+        _ => {
+            let bytes = n.to_le_bytes();
+            let len = 4 - n.leading_zeros() as usize / 8;
+            let mut number = bytes[..len].to_vec();
+            // Keep the sign bit clear
+            if number[len - 1] & 0x80 != 0 {
+                number.push(0);
+            }
+            push_slice(script, &number);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: [u8; 32] = [0x5e; 32];
+    const PREVIOUS_TXID: [u8; 32] = [0xab; 32];
+    const LOCKTIME: u32 = 800_000;
+    const HTLC_VALUE: u64 = 100_000;
+
+    // Compressed keys of the private keys 2 (recipient) and 3 (refund)
+    const RECIPIENT_KEY: &str =
+        "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5";
+    const REFUND_KEY: &str = "02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9";
+
+    const REDEEM_SCRIPT: &str = "63a8209985b2e4b9b71b28ce59ea5ce77f0b110637b625c6d7f41e982c9510940a20568876a91406afd46bcdfd22ef94ac122aa11f241244a37ecc670300350cb17576a9147dd65592d0ab2fe0d0257d571abf032cd9db93dc6888ac";
+
+    fn key(hex_key: &str) -> [u8; 33] {
+        let mut key = [0u8; 33];
+        hex::decode_to_slice(hex_key, &mut key).unwrap();
+        key
+    }
+
+    fn signature(r: &str, s: &str) -> Signature {
+        let mut signature = Signature {
+            r: [0; 32],
+            s: [0; 32],
+            recovery_id: 0,
+        };
+        hex::decode_to_slice(r, &mut signature.r).unwrap();
+        hex::decode_to_slice(s, &mut signature.s).unwrap();
+        signature
+    }
+
+    fn htlc() -> BitcoinHtlc {
+        BitcoinHtlc {
+            hashlock: Hashlock::Sha256(Sha256::digest(SECRET).into()),
+            recipient: hash160(&key(RECIPIENT_KEY)),
+            refund: hash160(&key(REFUND_KEY)),
+            locktime: LOCKTIME,
+        }
+    }
+
+    fn spend(sequence: u32, lock_time: u32, to: &str) -> Transaction {
+        Transaction {
+            version: 2,
+            inputs: vec![TxIn {
+                previous_output: OutPoint {
+                    txid: PREVIOUS_TXID,
+                    vout: 0,
+                },
+                sequence,
+            }],
+            outputs: vec![TxOut {
+                value: 99_000,
+                script_pubkey: p2wpkh_script_pubkey(&hash160(&key(to))),
+            }],
+            lock_time,
+        }
+    }
+
+    #[test]
+    fn test_redeem_script_and_address() {
+        let htlc = htlc();
+        assert_eq!(hex::encode(htlc.redeem_script()), REDEEM_SCRIPT);
+        assert_eq!(
+            hex::encode(htlc.script_pubkey()),
+            "0020782aa66e8f07ac61374a193f4223adad28b555d4af94c54831fe82ac633d3b79"
+        );
+        assert_eq!(
+            htlc.address(BitcoinNetwork::Testnet).unwrap(),
+            "tb1q0q42vm50q7kxzd62ryl5ygad455t24w5472v2jp3l6p2ccea8dus8w5vmv"
+        );
+    }
+
+    // BIP-173 P2WSH example: <generator key> OP_CHECKSIG
+    #[test]
+    fn test_p2wsh_address() {
+        let script =
+            hex::decode("210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ac")
+                .unwrap();
+        assert_eq!(
+            p2wsh_address(&script, BitcoinNetwork::Mainnet).unwrap(),
+            "bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3"
+        );
+    }
+
+    #[test]
+    fn test_script_numbers() {
+        let pushed = |n| {
+            let mut script = Vec::new();
+            push_int(&mut script, n);
+            hex::encode(script)
+        };
+        assert_eq!(pushed(0), "00");
+        assert_eq!(pushed(16), "60");
+        assert_eq!(pushed(17), "0111");
+        assert_eq!(pushed(128), "028000");
+        assert_eq!(pushed(800_000), "0300350c");
+        assert_eq!(pushed(1_700_000_000), "0400f15365");
+    }
+
+    // Native P2WPKH example from BIP-143
+    #[test]
+    fn test_bip143_sighash() {
+        let raw = hex::decode("0100000002fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f0000000000eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a0100000000ffffffff02202cb206000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac9093510d000000001976a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac11000000").unwrap();
+        let outpoint = |txid: &[u8], vout| OutPoint {
+            txid: txid.try_into().unwrap(),
+            vout,
+        };
+        let tx = Transaction {
+            version: 1,
+            inputs: vec![
+                TxIn {
+                    previous_output: outpoint(&raw[5..37], 0),
+                    sequence: 0xffff_ffee,
+                },
+                TxIn {
+                    previous_output: outpoint(&raw[46..78], 1),
+                    sequence: SEQUENCE_FINAL,
+                },
+            ],
+            outputs: vec![
+                TxOut {
+                    value: 112_340_000,
+                    script_pubkey: hex::decode(
+                        "76a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac",
+                    )
+                    .unwrap(),
+                },
+                TxOut {
+                    value: 223_450_000,
+                    script_pubkey: hex::decode(
+                        "76a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac",
+                    )
+                    .unwrap(),
+                },
+            ],
+            lock_time: 0x11,
+        };
+        assert_eq!(tx.encode(), raw);
+
+        let script_code =
+            hex::decode("76a9141d0f172a0ecb48aee1be1f2687d2963ae33f71a188ac").unwrap();
+        let sighash = tx
+            .segwit_v0_sighash(1, &script_code, 600_000_000, SIGHASH_ALL)
+            .unwrap();
+        assert_eq!(
+            hex::encode(sighash),
+            "c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670"
+        );
+    }
+
+    #[test]
+    fn test_claim_spend() {
+        let htlc = htlc();
+        let tx = spend(SEQUENCE_RBF, 0, RECIPIENT_KEY);
+        assert_eq!(
+            hex::encode(htlc.claim_sighash(&tx, 0, HTLC_VALUE).unwrap()),
+            "9c0a6e68b3d2fb53791667da79e2939907a2f3e75459ea00a0eecb33180f69f6"
+        );
+
+        let signature = signature(
+            "d47644539acec3da5e3ecf5fe8863c628a9c97e8b71e9ea9167a6f4f83c03c32",
+            "430de4544ea010d5558b1f0c85db7a86cba0e7a8f4060c453ab8b4df8be2c6b0",
+        );
+        let witness = htlc.claim_witness(&signature, &key(RECIPIENT_KEY), &SECRET);
+        assert_eq!(
+            hex::encode(tx.encode_signed(&[witness]).unwrap()),
+            format!(
+                "02000000000101{}0000000000fdffffff01b88201000000000016001406afd46bcdfd22ef94ac122aa11f241244a37ecc05483045022100d47644539acec3da5e3ecf5fe8863c628a9c97e8b71e9ea9167a6f4f83c03c320220430de4544ea010d5558b1f0c85db7a86cba0e7a8f4060c453ab8b4df8be2c6b00121{RECIPIENT_KEY}20{}01015c{REDEEM_SCRIPT}00000000",
+                "ab".repeat(32),
+                "5e".repeat(32),
+            )
+        );
+        let mut txid = tx.txid();
+        txid.reverse();
+        assert_eq!(
+            hex::encode(txid),
+            "8a6d42c3e7d0b8287ea0fc0eeaa474d5a43561c50133fcaf56994e26de9b1d19"
+        );
+    }
+
+    #[test]
+    fn test_refund_spend() {
+        let htlc = htlc();
+        let tx = spend(0xffff_fffe, LOCKTIME, REFUND_KEY);
+        assert_eq!(
+            hex::encode(htlc.refund_sighash(&tx, 0, HTLC_VALUE).unwrap()),
+            "c61c39595b50e8083774bf21ce9a1a13f69fb9642f1b094a296f5773456934ec"
+        );
+
+        let signature = signature(
+            "f30e4bd8094e53a679ddb8f55b5216b03c44623fc4279ef0791f9aa1f6930d49",
+            "37fec6970b76487fdee87ed1db0e483bfeb2b948de7b3af384ab3cf94365495e",
+        );
+        let witness = htlc.refund_witness(&signature, &key(REFUND_KEY));
+        assert_eq!(
+            hex::encode(tx.encode_signed(&[witness]).unwrap()),
+            format!(
+                "02000000000101{}0000000000feffffff01b8820100000000001600147dd65592d0ab2fe0d0257d571abf032cd9db93dc04483045022100f30e4bd8094e53a679ddb8f55b5216b03c44623fc4279ef0791f9aa1f6930d49022037fec6970b76487fdee87ed1db0e483bfeb2b948de7b3af384ab3cf94365495e0121{REFUND_KEY}005c{REDEEM_SCRIPT}00350c00",
+                "ab".repeat(32),
+            )
+        );
+    }
+
+    #[test]
+    fn test_refund_locktime_checks() {
+        let htlc = htlc();
+        assert_eq!(
+            htlc.refund_sighash(&spend(SEQUENCE_FINAL, LOCKTIME, REFUND_KEY), 0, HTLC_VALUE),
+            Err("Refund input must not be final")
+        );
+        assert_eq!(
+            htlc.refund_sighash(
+                &spend(SEQUENCE_RBF, LOCKTIME - 1, REFUND_KEY),
+                0,
+                HTLC_VALUE
+            ),
+            Err("Lock time is before the HTLC locktime")
+        );
+        assert_eq!(
+            htlc.refund_sighash(
+                &spend(SEQUENCE_RBF, 1_700_000_000, REFUND_KEY),
+                0,
+                HTLC_VALUE
+            ),
+            Err("Lock time kind does not match the HTLC")
+        );
+        assert_eq!(
+            htlc.refund_sighash(&spend(SEQUENCE_RBF, LOCKTIME, REFUND_KEY), 1, HTLC_VALUE),
+            Err("Input index out of range")
+        );
+    }
+}
@@ -0,0 +1,141 @@
+// Recoverable secp256k1 signature as returned by the MPC signer.
+
+use alloc::vec::Vec;
+
+// Curve order n and n / 2, big-endian
+const CURVE_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+const HALF_CURVE_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+// r is the x coordinate of R; recovery_id is the parity of R's y
+// coordinate, plus 2 if R's x coordinate overflowed the curve order
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Signature {
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+    pub recovery_id: u8,
+}
+
+impl Signature {
+    // r || s || recovery_id, the layout expected by ecrecover-style APIs
+    pub fn to_bytes(&self) -> [u8; 65] {
+        let mut bytes = [0u8; 65];
+        bytes[..32].copy_from_slice(&self.r);
+        bytes[32..64].copy_from_slice(&self.s);
+        bytes[64] = self.recovery_id;
+        bytes
+    }
+
+    // Ethereum rejects s above n / 2 (EIP-2). (r, n - s) is an equally valid
+    // signature whose R has the opposite y parity.
+    pub fn normalize_s(mut self) -> Self {
+        if self.s > HALF_CURVE_ORDER {
+            let mut borrow = 0u16;
+            for i in (0..32).rev() {
+                let diff = CURVE_ORDER[i] as u16 + 0x100 - self.s[i] as u16 - borrow;
+                self.s[i] = diff as u8;
+                borrow = if diff < 0x100 { 1 } else { 0 };
+            }
+            self.recovery_id ^= 1;
+        }
+        self
+    }
+
+    // Parity of R's y coordinate, as used by typed Ethereum transactions
+    pub fn y_parity(&self) -> Result<u8, &'static str> {
+        match self.recovery_id {
+            0 | 1 => Ok(self.recovery_id),
+            _ => Err("Recovery id not supported by Ethereum"),
+        }
+    }
+
+    // DER SEQUENCE of the two INTEGERs, as used in Bitcoin scripts
+    pub fn to_der(&self) -> Vec<u8> {
+        let mut body = Vec::with_capacity(70);
+        der_integer(&mut body, &self.r);
+        der_integer(&mut body, &self.s);
+
+        let mut out = Vec::from([0x30, body.len() as u8]);
+        out.extend_from_slice(&body);
+        out
+    }
+}
+
+// Minimal big-endian encoding, with a zero byte where the top bit would
+// make the value negative
+fn der_integer(out: &mut Vec<u8>, value: &[u8; 32]) {
+    let start = value.iter().position(|&b| b != 0).unwrap_or(31);
+    let bytes = &value[start..];
+    let pad = bytes[0] & 0x80 != 0;
+
+    out.push(0x02);
+    out.push((bytes.len() + pad as usize) as u8);
+    if pad {
+        out.push(0);
+    }
+    out.extend_from_slice(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_high_s() {
+        let mut s = CURVE_ORDER;
+        s[31] -= 5; // n - 5
+        let signature = Signature {
+            r: [1; 32],
+            s,
+            recovery_id: 0,
+        }
+        .normalize_s();
+
+        let mut expected = [0u8; 32];
+        expected[31] = 5;
+        assert_eq!(signature.s, expected);
+        assert_eq!(signature.recovery_id, 1);
+
+        // Low s is left alone
+        assert_eq!(signature.normalize_s(), signature);
+        let half = Signature {
+            s: HALF_CURVE_ORDER,
+            ..signature
+        };
+        assert_eq!(half.normalize_s(), half);
+    }
+
+    #[test]
+    fn test_der_encoding() {
+        let mut r = [0u8; 32];
+        r[0] = 0x80;
+        let mut s = [0u8; 32];
+        s[30] = 0x01;
+        s[31] = 0x02;
+        let signature = Signature {
+            r,
+            s,
+            recovery_id: 0,
+        };
+
+        let mut expected = Vec::from([0x30, 0x27, 0x02, 0x21, 0x00]);
+        expected.extend_from_slice(&r);
+        expected.extend_from_slice(&[0x02, 0x02, 0x01, 0x02]);
+        assert_eq!(signature.to_der(), expected);
+
+        let zero = Signature {
+            r: [0; 32],
+            s: [0; 32],
+            recovery_id: 0,
+        };
+        assert_eq!(
+            zero.to_der(),
+            [0x30, 0x06, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00]
+        );
+    }
+}
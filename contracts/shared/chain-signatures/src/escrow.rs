@@ -0,0 +1,115 @@
+// Calldata for the 1inch Fusion+ escrow on BASE (IBaseEscrow).
+//
+// Address and Timelocks are uint256 value types in the escrow contracts, so
+// the canonical signatures use uint256 for them.
+
+use alloc::vec::Vec;
+
+use crate::abi::{encode_call, Token};
+use crate::evm::Address;
+
+const WITHDRAW_SIGNATURE: &str =
+    "withdraw(bytes32,(bytes32,bytes32,uint256,uint256,uint256,uint256,uint256,uint256))";
+const CANCEL_SIGNATURE: &str =
+    "cancel((bytes32,bytes32,uint256,uint256,uint256,uint256,uint256,uint256))";
+
+// IBaseEscrow.Immutables
+#[derive(Clone, PartialEq, Debug)]
+pub struct Immutables {
+    pub order_hash: [u8; 32],
+    pub hashlock: [u8; 32],
+    pub maker: Address,
+    pub taker: Address,
+    // Zero address for the native token
+    pub token: Address,
+    pub amount: u128,
+    pub safety_deposit: u128,
+    // Packed stage offsets with the deployment time in the top 32 bits
+    pub timelocks: [u8; 32],
+}
+
+impl Immutables {
+    pub fn to_token(&self) -> Token {
+        Token::Tuple(Vec::from([
+            Token::FixedBytes32(self.order_hash),
+            Token::FixedBytes32(self.hashlock),
+            Token::Address(self.maker),
+            Token::Address(self.taker),
+            Token::Address(self.token),
+            Token::uint(self.amount),
+            Token::uint(self.safety_deposit),
+            Token::Uint(self.timelocks),
+        ]))
+    }
+}
+
+pub fn withdraw_calldata(secret: &[u8; 32], immutables: &Immutables) -> Vec<u8> {
+    encode_call(
+        WITHDRAW_SIGNATURE,
+        &[Token::FixedBytes32(*secret), immutables.to_token()],
+    )
+}
+
+pub fn cancel_calldata(immutables: &Immutables) -> Vec<u8> {
+    encode_call(CANCEL_SIGNATURE, &[immutables.to_token()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::selector;
+
+    fn immutables() -> Immutables {
+        let mut hashlock = [0u8; 32];
+        hex::decode_to_slice(
+            "852bac3b0a8c59dc25c970dd80c3fd75d4d3dc50e1a42413a414221dac7cc44e",
+            &mut hashlock,
+        )
+        .unwrap();
+        // Deployed at 1700000000 with a 3600s first stage
+        let mut timelocks = [0u8; 32];
+        timelocks[..4].copy_from_slice(&1_700_000_000u32.to_be_bytes());
+        timelocks[28..].copy_from_slice(&3_600u32.to_be_bytes());
+        Immutables {
+            order_hash: [0xaa; 32],
+            hashlock,
+            maker: [0x10; 20],
+            taker: [0x20; 20],
+            token: [0; 20],
+            amount: 1_000_000_000_000_000_000,
+            safety_deposit: 10_000_000_000_000_000,
+            timelocks,
+        }
+    }
+
+    const IMMUTABLES_ENCODED: &str = "\
+        aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\
+        852bac3b0a8c59dc25c970dd80c3fd75d4d3dc50e1a42413a414221dac7cc44e\
+        0000000000000000000000001010101010101010101010101010101010101010\
+        0000000000000000000000002020202020202020202020202020202020202020\
+        0000000000000000000000000000000000000000000000000000000000000000\
+        0000000000000000000000000000000000000000000000000de0b6b3a7640000\
+        000000000000000000000000000000000000000000000000002386f26fc10000\
+        6553f10000000000000000000000000000000000000000000000000000000e10";
+
+    #[test]
+    fn test_selectors() {
+        assert_eq!(hex::encode(selector(WITHDRAW_SIGNATURE)), "23305703");
+        assert_eq!(hex::encode(selector(CANCEL_SIGNATURE)), "90d3252f");
+    }
+
+    #[test]
+    fn test_withdraw_calldata() {
+        let calldata = withdraw_calldata(&[0x5e; 32], &immutables());
+        assert_eq!(
+            hex::encode(calldata),
+            format!("23305703{}{IMMUTABLES_ENCODED}", "5e".repeat(32))
+        );
+    }
+
+    #[test]
+    fn test_cancel_calldata() {
+        let calldata = cancel_calldata(&immutables());
+        assert_eq!(hex::encode(calldata), format!("90d3252f{IMMUTABLES_ENCODED}"));
+    }
+}
@@ -0,0 +1,287 @@
+// EVM transactions signed through Chain Signatures.
+//
+// Build a transaction, request an MPC signature over signing_hash() (it is
+// the 32-byte payload of the sign request) and assemble the raw transaction
+// with encode_signed(), ready for eth_sendRawTransaction on BASE.
+
+use alloc::vec::Vec;
+use sha3::{Digest, Keccak256};
+
+use crate::ecdsa::Signature;
+use crate::rlp::{encode_bytes, encode_list, encode_uint, encode_uint_bytes};
+
+pub const BASE_CHAIN_ID: u64 = 8453;
+pub const BASE_SEPOLIA_CHAIN_ID: u64 = 84532;
+
+const EIP1559_TX_TYPE: u8 = 0x02;
+
+pub type Address = [u8; 20];
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct AccessListItem {
+    pub address: Address,
+    pub storage_keys: Vec<[u8; 32]>,
+}
+
+// Type 2 transaction (EIP-1559)
+#[derive(Clone, PartialEq, Debug)]
+pub struct Eip1559Transaction {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub max_priority_fee_per_gas: u128,
+    pub max_fee_per_gas: u128,
+    pub gas_limit: u64,
+    // None deploys a contract
+    pub to: Option<Address>,
+    pub value: u128,
+    pub data: Vec<u8>,
+    pub access_list: Vec<AccessListItem>,
+}
+
+// Pre-typed transaction; with a chain id it is replay protected (EIP-155)
+#[derive(Clone, PartialEq, Debug)]
+pub struct LegacyTransaction {
+    pub chain_id: Option<u64>,
+    pub nonce: u64,
+    pub gas_price: u128,
+    pub gas_limit: u64,
+    pub to: Option<Address>,
+    pub value: u128,
+    pub data: Vec<u8>,
+}
+
+impl Eip1559Transaction {
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let mut out = Vec::from([EIP1559_TX_TYPE]);
+        encode_list(&mut out, &self.encode_fields());
+        out
+    }
+
+    pub fn signing_hash(&self) -> [u8; 32] {
+        Keccak256::digest(self.signing_payload()).into()
+    }
+
+    pub fn encode_signed(&self, signature: &Signature) -> Result<Vec<u8>, &'static str> {
+        let signature = signature.normalize_s();
+        let mut fields = self.encode_fields();
+        encode_uint(&mut fields, signature.y_parity()? as u128);
+        encode_uint_bytes(&mut fields, &signature.r);
+        encode_uint_bytes(&mut fields, &signature.s);
+
+        let mut out = Vec::from([EIP1559_TX_TYPE]);
+        encode_list(&mut out, &fields);
+        Ok(out)
+    }
+
+    fn encode_fields(&self) -> Vec<u8> {
+        let mut fields = Vec::new();
+        encode_uint(&mut fields, self.chain_id as u128);
+        encode_uint(&mut fields, self.nonce as u128);
+        encode_uint(&mut fields, self.max_priority_fee_per_gas);
+        encode_uint(&mut fields, self.max_fee_per_gas);
+        encode_uint(&mut fields, self.gas_limit as u128);
+        encode_to(&mut fields, &self.to);
+        encode_uint(&mut fields, self.value);
+        encode_bytes(&mut fields, &self.data);
+        encode_access_list(&mut fields, &self.access_list);
+        fields
+    }
+}
+
+impl LegacyTransaction {
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let mut fields = self.encode_fields();
+        if let Some(chain_id) = self.chain_id {
+            encode_uint(&mut fields, chain_id as u128);
+            encode_uint(&mut fields, 0);
+            encode_uint(&mut fields, 0);
+        }
+        let mut out = Vec::new();
+        encode_list(&mut out, &fields);
+        out
+    }
+
+    pub fn signing_hash(&self) -> [u8; 32] {
+        Keccak256::digest(self.signing_payload()).into()
+    }
+
+    // v is 27 + y_parity, or chain_id * 2 + 35 + y_parity under EIP-155
+    pub fn encode_signed(&self, signature: &Signature) -> Result<Vec<u8>, &'static str> {
+        let signature = signature.normalize_s();
+        let y_parity = signature.y_parity()? as u128;
+        let v = match self.chain_id {
+            Some(chain_id) => chain_id as u128 * 2 + 35 + y_parity,
+            None => 27 + y_parity,
+        };
+
+        let mut fields = self.encode_fields();
+        encode_uint(&mut fields, v);
+        encode_uint_bytes(&mut fields, &signature.r);
+        encode_uint_bytes(&mut fields, &signature.s);
+
+        let mut out = Vec::new();
+        encode_list(&mut out, &fields);
+        Ok(out)
+    }
+
+    fn encode_fields(&self) -> Vec<u8> {
+        let mut fields = Vec::new();
+        encode_uint(&mut fields, self.nonce as u128);
+        encode_uint(&mut fields, self.gas_price);
+        encode_uint(&mut fields, self.gas_limit as u128);
+        encode_to(&mut fields, &self.to);
+        encode_uint(&mut fields, self.value);
+        encode_bytes(&mut fields, &self.data);
+        fields
+    }
+}
+
+fn encode_to(out: &mut Vec<u8>, to: &Option<Address>) {
+    match to {
+        Some(address) => encode_bytes(out, address),
+        None => encode_bytes(out, &[]),
+    }
+}
+
+fn encode_access_list(out: &mut Vec<u8>, access_list: &[AccessListItem]) {
+    let mut items = Vec::new();
+    for item in access_list {
+        let mut keys = Vec::new();
+        for key in &item.storage_keys {
+            encode_bytes(&mut keys, key);
+        }
+        let mut entry = Vec::new();
+        encode_bytes(&mut entry, &item.address);
+        encode_list(&mut entry, &keys);
+        encode_list(&mut items, &entry);
+    }
+    encode_list(out, &items);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signature(r: &str, s: &str, recovery_id: u8) -> Signature {
+        let mut signature = Signature {
+            r: [0; 32],
+            s: [0; 32],
+            recovery_id,
+        };
+        hex::decode_to_slice(r, &mut signature.r).unwrap();
+        hex::decode_to_slice(s, &mut signature.s).unwrap();
+        signature
+    }
+
+    // Example from EIP-155
+    #[test]
+    fn test_eip155_transaction() {
+        let tx = LegacyTransaction {
+            chain_id: Some(1),
+            nonce: 9,
+            gas_price: 20_000_000_000,
+            gas_limit: 21_000,
+            to: Some([0x35; 20]),
+            value: 1_000_000_000_000_000_000,
+            data: Vec::new(),
+        };
+        assert_eq!(
+            hex::encode(tx.signing_payload()),
+            "ec098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080018080"
+        );
+        assert_eq!(
+            hex::encode(tx.signing_hash()),
+            "daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53"
+        );
+
+        let signature = signature(
+            "28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276",
+            "67cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83",
+            0,
+        );
+        assert_eq!(
+            hex::encode(tx.encode_signed(&signature).unwrap()),
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        );
+    }
+
+    #[test]
+    fn test_unprotected_contract_creation() {
+        let tx = LegacyTransaction {
+            chain_id: None,
+            nonce: 0,
+            gas_price: 1,
+            gas_limit: 53_000,
+            to: None,
+            value: 5,
+            data: vec![0x60, 0x00],
+        };
+        assert_eq!(
+            hex::encode(tx.signing_hash()),
+            "bbf8cfdab74359896ac2c7f6babe762794db80965dead203e21c52e848453943"
+        );
+
+        let signature = signature(
+            "d47644539acec3da5e3ecf5fe8863c628a9c97e8b71e9ea9167a6f4f83c03c32",
+            "6610ee5d95b58af4d8ea595258f591d0d0e8ce4fb297c77e179f7b44f97fc85b",
+            1,
+        );
+        assert_eq!(
+            hex::encode(tx.encode_signed(&signature).unwrap()),
+            "f84d800182cf0880058260001ca0d47644539acec3da5e3ecf5fe8863c628a9c97e8b71e9ea9167a6f4f83c03c32a06610ee5d95b58af4d8ea595258f591d0d0e8ce4fb297c77e179f7b44f97fc85b"
+        );
+    }
+
+    fn base_sepolia_tx() -> Eip1559Transaction {
+        let mut data = vec![0xde, 0xad, 0xbe, 0xef];
+        data.extend(0u8..32);
+        let mut storage_key = [0u8; 32];
+        storage_key[31] = 1;
+        Eip1559Transaction {
+            chain_id: BASE_SEPOLIA_CHAIN_ID,
+            nonce: 7,
+            max_priority_fee_per_gas: 1_000_000,
+            max_fee_per_gas: 1_500_000_000,
+            gas_limit: 120_000,
+            to: Some([0x11; 20]),
+            value: 0,
+            data,
+            access_list: vec![AccessListItem {
+                address: [0x22; 20],
+                storage_keys: vec![storage_key, [0; 32]],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_eip1559_transaction() {
+        let tx = base_sepolia_tx();
+        assert_eq!(
+            hex::encode(tx.signing_hash()),
+            "51d8648d80a16ef59c78fc3dac2832fbcbd45efdf2fa528c8b4368e6e2bcb2d8"
+        );
+
+        let signature = signature(
+            "08b2a8c29506cdf27fe61b47f6f0852e0ad0abc1fcb50ebce19d2fd9eed93ed7",
+            "32a6d94b368afa2ab2079ce0acc59de847ceeec59aca748c16d0b5ba1462227b",
+            0,
+        );
+        assert_eq!(
+            hex::encode(tx.encode_signed(&signature).unwrap()),
+            "02f8ed83014a3407830f42408459682f008301d4c094111111111111111111111111111111111111111180a4deadbeef000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1ff85bf859942222222222222222222222222222222222222222f842a00000000000000000000000000000000000000000000000000000000000000001a0000000000000000000000000000000000000000000000000000000000000000080a008b2a8c29506cdf27fe61b47f6f0852e0ad0abc1fcb50ebce19d2fd9eed93ed7a032a6d94b368afa2ab2079ce0acc59de847ceeec59aca748c16d0b5ba1462227b"
+        );
+    }
+
+    #[test]
+    fn test_overflowed_recovery_id_rejected() {
+        let signature = Signature {
+            r: [1; 32],
+            s: [1; 32],
+            recovery_id: 2,
+        };
+        assert_eq!(
+            base_sepolia_tx().encode_signed(&signature),
+            Err("Recovery id not supported by Ethereum")
+        );
+    }
+}
@@ -0,0 +1,154 @@
+// Chain Signatures key derivation.
+//
+// Every (predecessor, path) pair maps to its own secp256k1 key:
+// epsilon = sha3_256("near-mpc-recovery v0.1.0 epsilon derivation:{predecessor},{path}")
+// reduced mod n, and derived = root + epsilon * G, where root is the public
+// key of the MPC signer. This mirrors the derivation done by the signer nodes.
+
+use k256::elliptic_curve::ops::Reduce;
+use k256::elliptic_curve::sec1::{FromEncodedPoint, ToEncodedPoint};
+use k256::{AffinePoint, EncodedPoint, ProjectivePoint, Scalar, U256};
+use near_sdk::{AccountId, CurveType, PublicKey};
+use sha3::{Digest, Sha3_256};
+
+const EPSILON_DERIVATION_PREFIX: &str = "near-mpc-recovery v0.1.0 epsilon derivation:";
+
+pub fn derive_epsilon(predecessor: &AccountId, path: &str) -> [u8; 32] {
+    let hash = Sha3_256::digest(format!("{EPSILON_DERIVATION_PREFIX}{predecessor},{path}"));
+    <Scalar as Reduce<U256>>::reduce_bytes(&hash).to_bytes().into()
+}
+
+// Both keys are NEAR secp256k1 public keys (64-byte x || y)
+pub fn derive_key(root: &PublicKey, epsilon: &[u8; 32]) -> Result<PublicKey, &'static str> {
+    let root = to_affine(root)?;
+    let epsilon = <Scalar as Reduce<U256>>::reduce_bytes(epsilon.into());
+    let derived = (ProjectivePoint::GENERATOR * epsilon + root).to_affine();
+    from_affine(&derived)
+}
+
+// 33-byte SEC1 compressed form, as used in Bitcoin scripts
+pub fn compressed_key(key: &PublicKey) -> Result<[u8; 33], &'static str> {
+    let point = to_affine(key)?.to_encoded_point(true);
+    point.as_bytes().try_into().map_err(|_| "Invalid secp256k1 key")
+}
+
+pub(crate) fn to_affine(key: &PublicKey) -> Result<AffinePoint, &'static str> {
+    if key.curve_type() != CurveType::SECP256K1 {
+        return Err("Key is not a secp256k1 key");
+    }
+    let point = EncodedPoint::from_untagged_bytes(key.as_bytes()[1..].into());
+    Option::from(AffinePoint::from_encoded_point(&point)).ok_or("Key is not on the secp256k1 curve")
+}
+
+fn from_affine(point: &AffinePoint) -> Result<PublicKey, &'static str> {
+    let encoded = point.to_encoded_point(false);
+    // Uncompressed points are tagged with 0x04; the identity has no coordinates
+    let coordinates = encoded.as_bytes().get(1..).ok_or("Derived key is the identity")?;
+    PublicKey::from_parts(CurveType::SECP256K1, coordinates.to_vec())
+        .map_err(|_| "Derived key is the identity")
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // secp256k1 multiples k * G from Chuck Batson's published test vectors,
+    // https://chuckbatson.wordpress.com/2014/11/26/secp256k1-test-vectors/
+    pub(crate) const G: (&str, &str) = (
+        "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        "483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8",
+    );
+    pub(crate) const G2: (&str, &str) = (
+        "c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+        "1ae168fea63dc339a3c58419466ceaeef7f632653266d0e1236431a950cfe52a",
+    );
+    const G3: (&str, &str) = (
+        "f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9",
+        "388f7b0f632de8140fe337e62a37f3566500a99934c2231b6cb9fd7584b8e672",
+    );
+    // Group order n from SEC 2, section 2.4.1
+    const ORDER: &str = "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141";
+
+    // MPC root key of v1.signer-prod.testnet. The epsilons, derived keys and
+    // addresses for it below come from an independent reference (Python
+    // hashlib.sha3_256, textbook secp256k1 arithmetic, keccak and bech32),
+    // not from this crate.
+    pub(crate) const TESTNET_ROOT_KEY: &str = "secp256k1:4NfTiv3UsGahebgTaHyD9vF8KYKMBnfd6kh94mK6xv8fGBiJB8TBtFMP5WWXz6B89Ac1fbpzPwAvoyQebemHFwx3";
+
+    pub(crate) fn point_key((x, y): (&str, &str)) -> PublicKey {
+        let mut bytes = hex::decode(x).unwrap();
+        bytes.extend(hex::decode(y).unwrap());
+        PublicKey::from_parts(CurveType::SECP256K1, bytes).unwrap()
+    }
+
+    pub(crate) fn scalar(value: u8) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        bytes[31] = value;
+        bytes
+    }
+
+    #[test]
+    fn test_sha3_256() {
+        // FIPS 202 example "abc", from the NIST SHA3-256 example values,
+        // https://csrc.nist.gov/projects/cryptographic-standards-and-guidelines/example-values
+        assert_eq!(
+            hex::encode(Sha3_256::digest(b"abc")),
+            "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532"
+        );
+    }
+
+    #[test]
+    fn test_derive_epsilon() {
+        let cases = [
+            ("alice.testnet", "ethereum-1", "482da62ee42db2d6083294265c1f027684ce13938c2ed712f78d2e4640008fe3"),
+            ("alice.testnet", "bitcoin-1", "cc259791f8768505eb871b1f36e3cdfd665a839d1ff12fac43a73b0260426176"),
+            ("fusion-htlc.testnet", "base-1", "9ef7fac2466dba85f9784232b92873a133fe4cee44cb2f754c6ec38176ec0a22"),
+        ];
+        for (predecessor, path, expected) in cases {
+            let epsilon = derive_epsilon(&predecessor.parse().unwrap(), path);
+            assert_eq!(hex::encode(epsilon), expected);
+        }
+    }
+
+    #[test]
+    fn test_derive_testnet_key() {
+        let root: PublicKey = TESTNET_ROOT_KEY.parse().unwrap();
+        let epsilon = derive_epsilon(&"alice.testnet".parse().unwrap(), "ethereum-1");
+        assert_eq!(
+            derive_key(&root, &epsilon),
+            Ok("secp256k1:3hBMdabo82DyxjTr4LThx1MDpdx6GRaCaq6LgUPeeqQkpfywZ4xmKXf9ja68iSut7doEjTELYGbiCgJw5ms2HwFU".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_derive_key() {
+        // G + 1 * G = 2G and G + 2 * G = 3G
+        let root = point_key(G);
+        assert_eq!(derive_key(&root, &scalar(1)), Ok(point_key(G2)));
+        assert_eq!(derive_key(&root, &scalar(2)), Ok(point_key(G3)));
+        // Epsilons are reduced mod n: n + 1 tweaks like 1
+        let mut order_plus_one: [u8; 32] = hex::decode(ORDER).unwrap().try_into().unwrap();
+        order_plus_one[31] += 1;
+        assert_eq!(derive_key(&root, &order_plus_one), Ok(point_key(G2)));
+    }
+
+    #[test]
+    fn test_derive_identity() {
+        // G + (n - 1) * G = n * G, the point at infinity
+        let mut order_minus_one: [u8; 32] = hex::decode(ORDER).unwrap().try_into().unwrap();
+        order_minus_one[31] -= 1;
+        assert_eq!(
+            derive_key(&point_key(G), &order_minus_one),
+            Err("Derived key is the identity")
+        );
+    }
+
+    #[test]
+    fn test_reject_non_secp256k1_keys() {
+        let ed25519: PublicKey = "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp".parse().unwrap();
+        assert_eq!(derive_key(&ed25519, &[1; 32]), Err("Key is not a secp256k1 key"));
+
+        let off_curve = PublicKey::from_parts(CurveType::SECP256K1, vec![1; 64]).unwrap();
+        assert_eq!(compressed_key(&off_curve), Err("Key is not on the secp256k1 curve"));
+    }
+}
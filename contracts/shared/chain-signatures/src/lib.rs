@@ -1,3 +1,41 @@
-pub mod signatures {
-    // Chain signature utilities will be implemented here
-}
\ No newline at end of file
+// Chain Signatures helpers shared by the NEAR contracts.
+//
+// The rlp, ecdsa, evm, abi and escrow modules only need core and alloc and build without
+// the default "near" feature, e.g. for no_std targets; everything that talks
+// to the NEAR runtime or the MPC signer sits behind that feature.
+
+#![cfg_attr(not(any(feature = "near", test)), no_std)]
+
+extern crate alloc;
+
+pub mod abi;
+pub mod ecdsa;
+pub mod escrow;
+pub mod evm;
+pub mod rlp;
+
+#[cfg(feature = "near")]
+pub mod address;
+#[cfg(feature = "near")]
+pub mod bitcoin;
+#[cfg(feature = "near")]
+pub mod kdf;
+#[cfg(feature = "near")]
+pub mod psbt;
+#[cfg(feature = "near")]
+pub mod signatures;
+
+pub use ecdsa::Signature;
+pub use escrow::Immutables;
+pub use evm::{Eip1559Transaction, LegacyTransaction};
+
+#[cfg(feature = "near")]
+pub use address::{bitcoin_p2wpkh_address, ethereum_address, BitcoinNetwork};
+#[cfg(feature = "near")]
+pub use bitcoin::{BitcoinHtlc, Hashlock};
+#[cfg(feature = "near")]
+pub use kdf::{derive_epsilon, derive_key};
+#[cfg(feature = "near")]
+pub use psbt::Psbt;
+#[cfg(feature = "near")]
+pub use signatures::{SignRequest, SignResult};
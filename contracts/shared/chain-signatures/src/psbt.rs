@@ -0,0 +1,166 @@
+// Minimal BIP-174 PSBT (version 0) serialization.
+//
+// Covers what is needed to hand an HTLC spend to a Bitcoin wallet or node:
+// the unsigned transaction, the spent segwit outputs with their witness
+// scripts and, once signed through the MPC signer, the final witnesses.
+// Nodes can then extract and broadcast it (finalizepsbt).
+
+use crate::bitcoin::{encode_witness, write_compact_size, write_var_bytes, Transaction, TxOut};
+
+const MAGIC: &[u8] = b"psbt\xff";
+
+const PSBT_GLOBAL_UNSIGNED_TX: u8 = 0x00;
+const PSBT_IN_WITNESS_UTXO: u8 = 0x01;
+const PSBT_IN_WITNESS_SCRIPT: u8 = 0x05;
+const PSBT_IN_FINAL_SCRIPTWITNESS: u8 = 0x08;
+
+const SEPARATOR: u8 = 0x00;
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct PsbtInput {
+    // Output being spent, needed by signers to compute the segwit sighash
+    pub witness_utxo: Option<TxOut>,
+    pub witness_script: Option<Vec<u8>>,
+    pub final_script_witness: Option<Vec<Vec<u8>>>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Psbt {
+    pub unsigned_tx: Transaction,
+    // One per transaction input
+    pub inputs: Vec<PsbtInput>,
+}
+
+impl Psbt {
+    pub fn new(unsigned_tx: Transaction) -> Self {
+        let inputs = vec![PsbtInput::default(); unsigned_tx.inputs.len()];
+        Self {
+            unsigned_tx,
+            inputs,
+        }
+    }
+
+    pub fn serialize(&self) -> Result<Vec<u8>, &'static str> {
+        if self.inputs.len() != self.unsigned_tx.inputs.len() {
+            return Err("PSBT input count does not match the transaction");
+        }
+
+        let mut out = Vec::from(MAGIC);
+        write_pair(
+            &mut out,
+            PSBT_GLOBAL_UNSIGNED_TX,
+            &self.unsigned_tx.encode(),
+        );
+        out.push(SEPARATOR);
+
+        for input in &self.inputs {
+            if let Some(utxo) = &input.witness_utxo {
+                let mut value = Vec::new();
+                value.extend_from_slice(&utxo.value.to_le_bytes());
+                write_var_bytes(&mut value, &utxo.script_pubkey);
+                write_pair(&mut out, PSBT_IN_WITNESS_UTXO, &value);
+            }
+            if let Some(script) = &input.witness_script {
+                write_pair(&mut out, PSBT_IN_WITNESS_SCRIPT, script);
+            }
+            if let Some(witness) = &input.final_script_witness {
+                let mut value = Vec::new();
+                encode_witness(&mut value, witness);
+                write_pair(&mut out, PSBT_IN_FINAL_SCRIPTWITNESS, &value);
+            }
+            out.push(SEPARATOR);
+        }
+
+        // Output maps carry nothing for these spends
+        out.resize(out.len() + self.unsigned_tx.outputs.len(), SEPARATOR);
+        Ok(out)
+    }
+}
+
+// Key-value pair whose key is just the type byte
+fn write_pair(out: &mut Vec<u8>, key_type: u8, value: &[u8]) {
+    write_compact_size(out, 1);
+    out.push(key_type);
+    write_var_bytes(out, value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin::{p2wpkh_script_pubkey, OutPoint, TxIn, SEQUENCE_RBF};
+
+    // Claim of a P2WSH HTLC output of 100000 sat into a P2WPKH output
+    const WITNESS_SCRIPT: &str = "63a8209985b2e4b9b71b28ce59ea5ce77f0b110637b625c6d7f41e982c9510940a20568876a91406afd46bcdfd22ef94ac122aa11f241244a37ecc670300350cb17576a9147dd65592d0ab2fe0d0257d571abf032cd9db93dc6888ac";
+    const PSBT_BASE64: &str = "cHNidP8BAFICAAAAAaurq6urq6urq6urq6urq6urq6urq6urq6urq6urq6urAAAAAAD9////AbiCAQAAAAAAFgAUBq/Ua839Iu+UrBIqoR8kEkSjfswAAAAAAAEBK6CGAQAAAAAAIgAgeCqmbo8HrGE3Shk/QiOtrSi1VdSvlMVIMf6CrGM9O3kBBVxjqCCZhbLkubcbKM5Z6lznfwsRBje2JcbX9B6YLJUQlAogVoh2qRQGr9Rrzf0i75SsEiqhHyQSRKN+zGcDADUMsXV2qRR91lWS0Ksv4NAlfVcavwMs2duT3GiIrAAA";
+
+    fn claim_psbt() -> Psbt {
+        let mut recipient = [0u8; 20];
+        hex::decode_to_slice("06afd46bcdfd22ef94ac122aa11f241244a37ecc", &mut recipient).unwrap();
+        let tx = Transaction {
+            version: 2,
+            inputs: vec![TxIn {
+                previous_output: OutPoint {
+                    txid: [0xab; 32],
+                    vout: 0,
+                },
+                sequence: SEQUENCE_RBF,
+            }],
+            outputs: vec![TxOut {
+                value: 99_000,
+                script_pubkey: p2wpkh_script_pubkey(&recipient),
+            }],
+            lock_time: 0,
+        };
+
+        let mut psbt = Psbt::new(tx);
+        psbt.inputs[0] = PsbtInput {
+            witness_utxo: Some(TxOut {
+                value: 100_000,
+                script_pubkey: hex::decode(
+                    "0020782aa66e8f07ac61374a193f4223adad28b555d4af94c54831fe82ac633d3b79",
+                )
+                .unwrap(),
+            }),
+            witness_script: Some(hex::decode(WITNESS_SCRIPT).unwrap()),
+            final_script_witness: None,
+        };
+        psbt
+    }
+
+    #[test]
+    fn test_serialize_unsigned() {
+        use near_sdk::base64::Engine;
+
+        let serialized = claim_psbt().serialize().unwrap();
+        assert_eq!(
+            near_sdk::base64::engine::general_purpose::STANDARD.encode(serialized),
+            PSBT_BASE64
+        );
+    }
+
+    #[test]
+    fn test_serialize_final_witness() {
+        let mut psbt = claim_psbt();
+        let unsigned = psbt.serialize().unwrap();
+        let witness = vec![vec![0x30; 3], vec![], vec![0x01]];
+        psbt.inputs[0].final_script_witness = Some(witness);
+
+        let signed = psbt.serialize().unwrap();
+        // Final witness pair goes right before the input map's separator
+        let pair = hex::decode("0108080303303030000101").unwrap();
+        let split = unsigned.len() - 2;
+        assert_eq!(signed[..split], unsigned[..split]);
+        assert_eq!(signed[split..split + pair.len()], pair);
+        assert_eq!(signed[split + pair.len()..], [0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_input_count_mismatch() {
+        let mut psbt = claim_psbt();
+        psbt.inputs.push(PsbtInput::default());
+        assert_eq!(
+            psbt.serialize(),
+            Err("PSBT input count does not match the transaction")
+        );
+    }
+}
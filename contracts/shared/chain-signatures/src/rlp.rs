@@ -0,0 +1,114 @@
+// Recursive Length Prefix encoding, as used by Ethereum transactions.
+//
+// Items are appended to an output buffer; a list is encoded by encoding its
+// items into a separate buffer and wrapping it with encode_list.
+
+use alloc::vec::Vec;
+
+pub fn encode_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    // A single byte below 0x80 is its own encoding
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        out.push(bytes[0]);
+        return;
+    }
+    encode_header(out, 0x80, bytes.len());
+    out.extend_from_slice(bytes);
+}
+
+// Big-endian without leading zeros; zero is the empty string
+pub fn encode_uint(out: &mut Vec<u8>, value: u128) {
+    encode_uint_bytes(out, &value.to_be_bytes());
+}
+
+// Same as encode_uint for a big-endian integer of any width, e.g. a uint256
+pub fn encode_uint_bytes(out: &mut Vec<u8>, be_bytes: &[u8]) {
+    let start = be_bytes.iter().position(|b| *b != 0).unwrap_or(be_bytes.len());
+    encode_bytes(out, &be_bytes[start..]);
+}
+
+pub fn encode_list(out: &mut Vec<u8>, payload: &[u8]) {
+    encode_header(out, 0xc0, payload.len());
+    out.extend_from_slice(payload);
+}
+
+fn encode_header(out: &mut Vec<u8>, offset: u8, len: usize) {
+    if len < 56 {
+        out.push(offset + len as u8);
+        return;
+    }
+    let len_bytes = (len as u64).to_be_bytes();
+    let start = len_bytes.iter().position(|b| *b != 0).unwrap_or(len_bytes.len());
+    out.push(offset + 55 + (len_bytes.len() - start) as u8);
+    out.extend_from_slice(&len_bytes[start..]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(input: &[u8]) -> String {
+        let mut out = Vec::new();
+        encode_bytes(&mut out, input);
+        hex::encode(out)
+    }
+
+    fn uint(value: u128) -> String {
+        let mut out = Vec::new();
+        encode_uint(&mut out, value);
+        hex::encode(out)
+    }
+
+    // Vectors from the Ethereum wiki RLP page
+    #[test]
+    fn test_encode_strings() {
+        assert_eq!(bytes(b"dog"), "83646f67");
+        assert_eq!(bytes(b""), "80");
+        assert_eq!(bytes(&[0x00]), "00");
+        assert_eq!(bytes(&[0x0f]), "0f");
+        assert_eq!(bytes(&[0x80]), "8180");
+        let lorem = b"Lorem ipsum dolor sit amet, consectetur adipisicing elit";
+        assert_eq!(bytes(lorem), format!("b838{}", hex::encode(lorem)));
+    }
+
+    #[test]
+    fn test_encode_uints() {
+        assert_eq!(uint(0), "80");
+        assert_eq!(uint(15), "0f");
+        assert_eq!(uint(1024), "820400");
+        assert_eq!(uint(u128::MAX), format!("90{}", "ff".repeat(16)));
+
+        let mut out = Vec::new();
+        encode_uint_bytes(&mut out, &[0, 0, 4, 0]);
+        assert_eq!(hex::encode(out), "820400");
+    }
+
+    #[test]
+    fn test_encode_lists() {
+        let mut payload = Vec::new();
+        encode_bytes(&mut payload, b"cat");
+        encode_bytes(&mut payload, b"dog");
+        let mut out = Vec::new();
+        encode_list(&mut out, &payload);
+        assert_eq!(hex::encode(&out), "c88363617483646f67");
+
+        let mut empty = Vec::new();
+        encode_list(&mut empty, &[]);
+        assert_eq!(hex::encode(empty), "c0");
+
+        // [ [], [[]], [ [], [[]] ] ]
+        let mut set = Vec::new();
+        let mut inner = Vec::new();
+        encode_list(&mut inner, &[]);
+        let mut nested = Vec::new();
+        encode_list(&mut nested, &inner);
+        let mut third = inner.clone();
+        third.extend_from_slice(&nested);
+        let mut third_list = Vec::new();
+        encode_list(&mut third_list, &third);
+        let mut all = inner.clone();
+        all.extend_from_slice(&nested);
+        all.extend_from_slice(&third_list);
+        encode_list(&mut set, &all);
+        assert_eq!(hex::encode(set), "c7c0c1c0c3c0c1c0");
+    }
+}
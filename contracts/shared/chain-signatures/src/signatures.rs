@@ -0,0 +1,167 @@
+// Requests to the NEAR MPC signer contract (v1.signer) and decoding of its
+// responses.
+//
+// The signer signs a 32-byte payload with a key derived from the calling
+// contract's account id and a path. The response carries R as a compressed
+// secp256k1 point, s as a scalar and the recovery id, all hex encoded.
+
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{ext_contract, AccountId, Gas, NearSchema, NearToken, Promise, PromiseError};
+
+use crate::ecdsa::Signature;
+
+pub const MPC_SIGNER_MAINNET: &str = "v1.signer";
+pub const MPC_SIGNER_TESTNET: &str = "v1.signer-prod.testnet";
+
+// The signer charges a dynamic fee and refunds whatever is left, so callers
+// can attach more when the network is busy
+pub const SIGN_DEPOSIT: NearToken = NearToken::from_yoctonear(1);
+pub const GAS_FOR_SIGN: Gas = Gas::from_tgas(50);
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct SignRequest {
+    pub payload: [u8; 32],
+    pub path: String,
+    pub key_version: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct AffinePoint {
+    pub affine_point: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct Scalar {
+    pub scalar: String,
+}
+
+// Response of the signer's sign method
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct SignResult {
+    pub big_r: AffinePoint,
+    pub s: Scalar,
+    pub recovery_id: u8,
+}
+
+#[allow(dead_code)]
+#[ext_contract(ext_signer)]
+trait MpcSigner {
+    fn sign(&mut self, request: SignRequest) -> SignResult;
+}
+
+impl SignRequest {
+    pub fn new(payload: [u8; 32], path: impl Into<String>, key_version: u32) -> Self {
+        Self {
+            payload,
+            path: path.into(),
+            key_version,
+        }
+    }
+
+    // Sign call to the signer contract; chain a callback that passes its
+    // result to parse_sign_result
+    pub fn send(self, signer: AccountId, deposit: NearToken, gas: Gas) -> Promise {
+        ext_signer::ext(signer)
+            .with_attached_deposit(deposit)
+            .with_static_gas(gas)
+            .sign(self)
+    }
+}
+
+impl SignResult {
+    pub fn decode(&self) -> Result<Signature, &'static str> {
+        let big_r = hex::decode(&self.big_r.affine_point).map_err(|_| "Invalid big_r hex")?;
+        if big_r.len() != 33 || !matches!(big_r[0], 0x02 | 0x03) {
+            return Err("big_r must be a compressed secp256k1 point");
+        }
+        let s = hex::decode(&self.s.scalar).map_err(|_| "Invalid s hex")?;
+        if s.len() != 32 {
+            return Err("s must be 32 bytes");
+        }
+        if self.recovery_id > 3 {
+            return Err("Invalid recovery id");
+        }
+
+        let mut signature = Signature {
+            r: [0; 32],
+            s: [0; 32],
+            recovery_id: self.recovery_id,
+        };
+        signature.r.copy_from_slice(&big_r[1..]);
+        signature.s.copy_from_slice(&s);
+        Ok(signature)
+    }
+}
+
+// For use in the callback of a sign request
+pub fn parse_sign_result(result: Result<SignResult, PromiseError>) -> Result<Signature, &'static str> {
+    result.map_err(|_| "Signature request failed")?.decode()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESPONSE: &str = r#"{
+        "big_r": {"affine_point": "0349F1D6E0BE9F39A9C0E9F9D0A1A0F1A6A8F4F7D8BDBB9A3F8A1B2C3D4E5F6071"},
+        "s": {"scalar": "1B5C7E6A3F9D8E2B4C6A8F0E1D3C5B7A9E8F6D4C2B0A1928374655647382910A"},
+        "recovery_id": 1
+    }"#;
+
+    fn response() -> SignResult {
+        near_sdk::serde_json::from_str(RESPONSE).unwrap()
+    }
+
+    #[test]
+    fn test_decode_sign_result() {
+        let signature = response().decode().unwrap();
+        assert_eq!(
+            hex::encode(signature.r),
+            "49f1d6e0be9f39a9c0e9f9d0a1a0f1a6a8f4f7d8bdbb9a3f8a1b2c3d4e5f6071"
+        );
+        assert_eq!(signature.s[0], 0x1b);
+        assert_eq!(signature.recovery_id, 1);
+
+        let bytes = signature.to_bytes();
+        assert_eq!(bytes[..32], signature.r);
+        assert_eq!(bytes[32..64], signature.s);
+        assert_eq!(bytes[64], 1);
+    }
+
+    #[test]
+    fn test_reject_malformed_results() {
+        let mut result = response();
+        result.big_r.affine_point = format!("04{}", &result.big_r.affine_point[2..]);
+        assert_eq!(result.decode(), Err("big_r must be a compressed secp256k1 point"));
+
+        let mut result = response();
+        result.s.scalar.truncate(62);
+        assert_eq!(result.decode(), Err("s must be 32 bytes"));
+
+        let mut result = response();
+        result.recovery_id = 4;
+        assert_eq!(result.decode(), Err("Invalid recovery id"));
+
+        assert_eq!(
+            parse_sign_result(Err(PromiseError::Failed)),
+            Err("Signature request failed")
+        );
+    }
+
+    #[test]
+    fn test_sign_request_json() {
+        let request = SignRequest::new([7u8; 32], "base-1", 0);
+        let json = near_sdk::serde_json::to_value(&request).unwrap();
+        assert_eq!(json["path"], "base-1");
+        assert_eq!(json["key_version"], 0);
+        assert_eq!(json["payload"].as_array().unwrap().len(), 32);
+    }
+}
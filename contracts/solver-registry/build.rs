@@ -0,0 +1,29 @@
+// contract_source_metadata (NEP-330) is generated by near-sdk from these
+// variables at compile time; see scripts/nep330-env.sh. Rebuild when they
+// change, fill in this contract's path for the build info and append the
+// ABI hash to the link (NEP-330 has no field for it).
+
+const NEP330_VARS: [&str; 7] = [
+    "NEP330_VERSION",
+    "NEP330_LINK",
+    "NEP330_BUILD_INFO_BUILD_ENVIRONMENT",
+    "NEP330_BUILD_INFO_BUILD_COMMAND",
+    "NEP330_BUILD_INFO_SOURCE_CODE_SNAPSHOT",
+    "NEP330_BUILD_INFO_CONTRACT_PATH",
+    ABI_SHA256_VAR,
+];
+
+const ABI_SHA256_VAR: &str = "NEP330_ABI_SHA256_SOLVER_REGISTRY";
+
+fn main() {
+    for var in NEP330_VARS {
+        println!("cargo:rerun-if-env-changed={var}");
+    }
+    if std::env::var_os("NEP330_BUILD_INFO_CONTRACT_PATH").is_none() {
+        println!("cargo:rustc-env=NEP330_BUILD_INFO_CONTRACT_PATH=contracts/solver-registry");
+    }
+    let link = std::env::var("NEP330_LINK").ok().filter(|link| !link.is_empty());
+    if let (Some(link), Ok(abi_sha256)) = (link, std::env::var(ABI_SHA256_VAR)) {
+        println!("cargo:rustc-env=NEP330_LINK={link}#abi-sha256={abi_sha256}");
+    }
+}
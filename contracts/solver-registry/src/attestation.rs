@@ -0,0 +1,167 @@
+// Parsing of Intel DCAP quotes (SGX v3/v4, TDX v4) as produced by Phala's dstack.
+//
+// Only the quote structure, the report data binding and the measurements are
+// checked on-chain. The ECDSA signature chain (QE report, PCK certificates)
+// is checked off-chain by the owner-configured attestation verifier, which
+// signs off on the sha256 of each valid quote with verify_quote before the
+// quote can be used to register a worker.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, NearSchema, PublicKey};
+
+const HEADER_LEN: usize = 48;
+const SGX_REPORT_LEN: usize = 384;
+const TDX_REPORT_LEN: usize = 584;
+
+const TEE_TYPE_SGX: u32 = 0x0000_0000;
+const TEE_TYPE_TDX: u32 = 0x0000_0081;
+
+// Offsets inside the report body
+const SGX_MR_ENCLAVE: usize = 64;
+const SGX_REPORT_DATA: usize = 320;
+const TDX_MR_TD: usize = 136;
+const TDX_RTMR0: usize = 328;
+const TDX_REPORT_DATA: usize = 520;
+const MEASUREMENT_LEN: usize = 48;
+
+#[derive(
+    BorshDeserialize,
+    BorshSerialize,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Debug,
+    NearSchema,
+)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub enum TeeType {
+    Sgx,
+    Tdx,
+}
+
+pub struct Quote {
+    pub tee_type: TeeType,
+    pub report_data: [u8; 64],
+    // MRENCLAVE for SGX, MRTD followed by RTMR0-3 for TDX
+    pub measurements: Vec<u8>,
+}
+
+impl Quote {
+    pub fn parse(raw: &[u8]) -> Result<Self, &'static str> {
+        if raw.len() < HEADER_LEN {
+            return Err("Quote too short");
+        }
+        let version = u16::from_le_bytes([raw[0], raw[1]]);
+        let tee_type = u32::from_le_bytes([raw[4], raw[5], raw[6], raw[7]]);
+
+        let (tee_type, body_len) = match (version, tee_type) {
+            (3, TEE_TYPE_SGX) | (4, TEE_TYPE_SGX) => (TeeType::Sgx, SGX_REPORT_LEN),
+            (4, TEE_TYPE_TDX) => (TeeType::Tdx, TDX_REPORT_LEN),
+            _ => return Err("Unsupported quote version or TEE type"),
+        };
+
+        // Header, report body, then a length-prefixed signature section
+        let sig_offset = HEADER_LEN + body_len;
+        if raw.len() < sig_offset + 4 {
+            return Err("Quote too short");
+        }
+        let sig_len = u32::from_le_bytes([
+            raw[sig_offset],
+            raw[sig_offset + 1],
+            raw[sig_offset + 2],
+            raw[sig_offset + 3],
+        ]) as usize;
+        if raw.len() != sig_offset + 4 + sig_len {
+            return Err("Quote signature length mismatch");
+        }
+
+        let body = &raw[HEADER_LEN..sig_offset];
+        let (report_data_offset, measurements) = match tee_type {
+            TeeType::Sgx => (
+                SGX_REPORT_DATA,
+                body[SGX_MR_ENCLAVE..SGX_MR_ENCLAVE + 32].to_vec(),
+            ),
+            TeeType::Tdx => {
+                let mut measurements = body[TDX_MR_TD..TDX_MR_TD + MEASUREMENT_LEN].to_vec();
+                measurements.extend_from_slice(&body[TDX_RTMR0..TDX_RTMR0 + 4 * MEASUREMENT_LEN]);
+                (TDX_REPORT_DATA, measurements)
+            }
+        };
+
+        let mut report_data = [0u8; 64];
+        report_data.copy_from_slice(&body[report_data_offset..report_data_offset + 64]);
+
+        Ok(Self {
+            tee_type,
+            report_data,
+            measurements,
+        })
+    }
+
+    // Workers put sha256 of their NEAR public key in the first half of the
+    // report data; the second half is left to the application.
+    pub fn binds_public_key(&self, public_key: &PublicKey) -> bool {
+        env::sha256(public_key.as_bytes()) == self.report_data[..32]
+    }
+
+    // Hex code hash compared against the owner-managed allowlist: MRENCLAVE
+    // as-is for SGX, sha256(MRTD || RTMR0..3) for TDX
+    pub fn codehash(&self) -> String {
+        match self.tee_type {
+            TeeType::Sgx => hex::encode(&self.measurements),
+            TeeType::Tdx => hex::encode(env::sha256(&self.measurements)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TDX_QUOTE: &str = include_str!("../fixtures/tdx_quote.hex");
+    const SGX_QUOTE: &str = include_str!("../fixtures/sgx_quote.hex");
+    const WORKER_KEY: &str = "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp";
+
+    fn fixture(hex_quote: &str) -> Vec<u8> {
+        hex::decode(hex_quote.trim()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_tdx_quote() {
+        let quote = Quote::parse(&fixture(TDX_QUOTE)).unwrap();
+        assert_eq!(quote.tee_type, TeeType::Tdx);
+        assert_eq!(quote.measurements.len(), 5 * MEASUREMENT_LEN);
+        assert!(quote.binds_public_key(&WORKER_KEY.parse().unwrap()));
+        assert_eq!(quote.codehash(), include_str!("../fixtures/tdx_codehash.txt").trim());
+    }
+
+    #[test]
+    fn test_parse_sgx_quote() {
+        let quote = Quote::parse(&fixture(SGX_QUOTE)).unwrap();
+        assert_eq!(quote.tee_type, TeeType::Sgx);
+        assert!(quote.binds_public_key(&WORKER_KEY.parse().unwrap()));
+        assert_eq!(quote.codehash(), include_str!("../fixtures/sgx_codehash.txt").trim());
+    }
+
+    #[test]
+    fn test_reject_malformed_quotes() {
+        let mut raw = fixture(TDX_QUOTE);
+        raw.pop();
+        assert_eq!(Quote::parse(&raw).err(), Some("Quote signature length mismatch"));
+
+        raw[0] = 5;
+        assert_eq!(Quote::parse(&raw).err(), Some("Unsupported quote version or TEE type"));
+        assert_eq!(Quote::parse(&raw[..10]).err(), Some("Quote too short"));
+    }
+
+    #[test]
+    fn test_other_key_not_bound() {
+        let quote = Quote::parse(&fixture(TDX_QUOTE)).unwrap();
+        let other: PublicKey = "ed25519:DcA2MzgpJbrUATQLLceocVckhhAqrkingax4oJ9kZ847".parse().unwrap();
+        assert!(!quote.binds_public_key(&other));
+    }
+}
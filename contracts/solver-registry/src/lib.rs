@@ -1,20 +1,1124 @@
-// TODO: Implement solver registry
-// This contract will manage solver registrations for the TEE-based solver
-
-// use near_sdk::{near, AccountId};
-// 
-// #[near(contract_state)]
-// pub struct SolverRegistry {
-//     owner: AccountId,
-// }
-// 
-// #[near]
-// impl SolverRegistry {
-//     #[init]
-//     pub fn new(owner: AccountId) -> Self {
-//         Self { owner }
-//     }
-// }
-
-// Placeholder to allow compilation
-pub struct SolverRegistry;
\ No newline at end of file
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, LookupSet, UnorderedMap, UnorderedSet, Vector};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{
+    env, near_bindgen, AccountId, BorshStorageKey, NearSchema, NearToken, PanicOnDefault, Promise,
+    PublicKey,
+};
+
+mod attestation;
+mod reputation;
+mod slashing;
+mod workers;
+
+pub use attestation::TeeType;
+pub use reputation::{SolverStatsView, TokenVolume};
+use reputation::SolverStats;
+pub use slashing::{SlashCaseView, SlashConfigView, SlashDestination, SlashStatus};
+use slashing::{SlashCase, SlashConfig};
+pub use workers::WorkerView;
+use workers::Worker;
+
+type Balance = u128;
+type Timestamp = u64;
+
+const MAX_NAME_LEN: usize = 64;
+const MAX_LIST_LEN: usize = 16;
+const MAX_ENTRY_LEN: usize = 256;
+const DEFAULT_HEARTBEAT_TIMEOUT: Timestamp = 600_000_000_000; // 10 minutes
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct SolverRegistry {
+    owner: AccountId,
+    solvers: UnorderedMap<AccountId, Solver>,
+    active_solvers: UnorderedSet<AccountId>,
+    min_stake: Balance,
+    unbonding_period: Timestamp,
+    workers: UnorderedMap<PublicKey, Worker>,
+    codehashes: UnorderedMap<String, CodehashEntry>,
+    codehash_workers: LookupMap<String, Vec<PublicKey>>,
+    governance: Option<AccountId>,
+    htlc_contract: Option<AccountId>,
+    slash_config: SlashConfig,
+    slash_cases: Vector<SlashCase>,
+    solver_slash_cases: LookupMap<AccountId, Vec<u64>>,
+    solver_stats: LookupMap<AccountId, SolverStats>,
+    heartbeat_timeout: Timestamp,
+    attestation_verifier: Option<AccountId>,
+    // sha256 of quotes signed off by the attestation verifier, not yet used
+    verified_quotes: LookupSet<Vec<u8>>,
+    // Per token ("near" for NEAR), see reputation.rs
+    min_swap_amounts: LookupMap<String, Balance>,
+    // Per NEP-141 token, see slashing.rs
+    slash_rates: LookupMap<String, Balance>,
+    // (solver, maker) pairs with a counted completed swap, see reputation.rs
+    solver_counterparties: LookupSet<(AccountId, AccountId)>,
+}
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug, NearSchema,
+)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub enum SolverStatus {
+    Active,
+    Unbonding,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct SolverMetadata {
+    pub name: String,
+    pub endpoints: Vec<String>,
+    pub supported_chains: Vec<String>,
+    pub supported_tokens: Vec<String>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct Solver {
+    pub metadata: SolverMetadata,
+    pub stake: Balance,
+    pub status: SolverStatus,
+    pub registered_at: Timestamp,
+    pub unbonding_started_at: Option<Timestamp>,
+    pub worker_keys: Vec<PublicKey>,
+    pub open_slash_cases: u32,
+}
+
+// Approved solver image. A revoked image stays usable until expires_at so
+// workers can roll over to the next version.
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct CodehashEntry {
+    pub version: String,
+    pub approved_at: Timestamp,
+    pub expires_at: Option<Timestamp>,
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey {
+    Solvers,
+    ActiveSolvers,
+    Workers,
+    Codehashes,
+    CodehashWorkers,
+    SlashCases,
+    SolverSlashCases,
+    SolverStats,
+    VerifiedQuotes,
+    MinSwapAmounts,
+    SlashRates,
+    SolverCounterparties,
+}
+
+#[derive(Serialize, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct SolverView {
+    pub account_id: AccountId,
+    pub metadata: SolverMetadata,
+    pub stake: U128,
+    pub status: SolverStatus,
+    pub registered_at: U128,
+    pub unbonding_ends_at: Option<U128>,
+    pub worker_keys: Vec<PublicKey>,
+}
+
+#[derive(Serialize, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct CodehashView {
+    pub codehash: String,
+    pub version: String,
+    pub approved_at: U128,
+    pub expires_at: Option<U128>,
+}
+
+#[derive(Serialize, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct RegistryConfig {
+    pub owner: AccountId,
+    pub min_stake: U128,
+    pub unbonding_period: U128,
+    pub heartbeat_timeout: U128,
+    pub attestation_verifier: Option<AccountId>,
+}
+
+#[derive(Serialize, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct EventLog {
+    pub event_type: String,
+    pub solver: Option<AccountId>,
+    pub stake: Option<U128>,
+    pub worker_key: Option<PublicKey>,
+    pub codehash: Option<String>,
+    pub version: Option<String>,
+    pub expires_at: Option<U128>,
+    pub case_id: Option<u64>,
+    pub amount: Option<U128>,
+    pub timestamp: U128,
+}
+
+impl EventLog {
+    fn new(event_type: &str) -> Self {
+        Self {
+            event_type: event_type.to_string(),
+            solver: None,
+            stake: None,
+            worker_key: None,
+            codehash: None,
+            version: None,
+            expires_at: None,
+            case_id: None,
+            amount: None,
+            timestamp: U128(env::block_timestamp() as u128),
+        }
+    }
+}
+
+#[near_bindgen]
+impl SolverRegistry {
+    #[init]
+    pub fn new(owner: AccountId, min_stake: U128, unbonding_period: U128) -> Self {
+        assert!(!env::state_exists(), "Already initialized");
+        Self {
+            owner,
+            solvers: UnorderedMap::new(StorageKey::Solvers),
+            active_solvers: UnorderedSet::new(StorageKey::ActiveSolvers),
+            min_stake: min_stake.0,
+            unbonding_period: unbonding_period.0 as u64,
+            workers: UnorderedMap::new(StorageKey::Workers),
+            codehashes: UnorderedMap::new(StorageKey::Codehashes),
+            codehash_workers: LookupMap::new(StorageKey::CodehashWorkers),
+            governance: None,
+            htlc_contract: None,
+            slash_config: SlashConfig::default(),
+            slash_cases: Vector::new(StorageKey::SlashCases),
+            solver_slash_cases: LookupMap::new(StorageKey::SolverSlashCases),
+            solver_stats: LookupMap::new(StorageKey::SolverStats),
+            heartbeat_timeout: DEFAULT_HEARTBEAT_TIMEOUT,
+            attestation_verifier: None,
+            verified_quotes: LookupSet::new(StorageKey::VerifiedQuotes),
+            min_swap_amounts: LookupMap::new(StorageKey::MinSwapAmounts),
+            slash_rates: LookupMap::new(StorageKey::SlashRates),
+            solver_counterparties: LookupSet::new(StorageKey::SolverCounterparties),
+        }
+    }
+
+    // Register the caller as a solver, staking the attached deposit
+    #[payable]
+    pub fn register(&mut self, metadata: SolverMetadata) {
+        let account_id = env::predecessor_account_id();
+        let stake = env::attached_deposit().as_yoctonear();
+
+        assert!(self.solvers.get(&account_id).is_none(), "Solver already registered");
+        assert!(stake >= self.min_stake, "Attached deposit below minimum stake");
+        assert_valid_metadata(&metadata);
+
+        let solver = Solver {
+            metadata,
+            stake,
+            status: SolverStatus::Active,
+            registered_at: env::block_timestamp(),
+            unbonding_started_at: None,
+            worker_keys: Vec::new(),
+            open_slash_cases: 0,
+        };
+        self.solvers.insert(&account_id, &solver);
+        self.active_solvers.insert(&account_id);
+
+        self.emit_event(EventLog {
+            solver: Some(account_id),
+            stake: Some(U128(stake)),
+            ..EventLog::new("solver_registered")
+        });
+    }
+
+    // Top up the stake of an active solver
+    #[payable]
+    pub fn add_stake(&mut self) {
+        let account_id = env::predecessor_account_id();
+        let mut solver = self.expect_active_solver(&account_id);
+        let amount = env::attached_deposit().as_yoctonear();
+        assert!(amount > 0, "Attached deposit must be positive");
+
+        solver.stake += amount;
+        self.solvers.insert(&account_id, &solver);
+        // Solvers slashed below the minimum rejoin once topped up
+        if solver.stake >= self.min_stake {
+            self.active_solvers.insert(&account_id);
+        }
+
+        self.emit_event(EventLog {
+            solver: Some(account_id),
+            stake: Some(U128(solver.stake)),
+            ..EventLog::new("stake_added")
+        });
+    }
+
+    pub fn update_metadata(&mut self, metadata: SolverMetadata) {
+        let account_id = env::predecessor_account_id();
+        let mut solver = self.expect_active_solver(&account_id);
+        assert_valid_metadata(&metadata);
+
+        solver.metadata = metadata;
+        self.solvers.insert(&account_id, &solver);
+
+        self.emit_event(EventLog {
+            solver: Some(account_id),
+            ..EventLog::new("solver_updated")
+        });
+    }
+
+    // Leave the active set; the stake unlocks after the unbonding period
+    pub fn deregister(&mut self) {
+        let account_id = env::predecessor_account_id();
+        let mut solver = self.expect_active_solver(&account_id);
+
+        solver.status = SolverStatus::Unbonding;
+        solver.unbonding_started_at = Some(env::block_timestamp());
+        self.solvers.insert(&account_id, &solver);
+        self.active_solvers.remove(&account_id);
+
+        self.emit_event(EventLog {
+            solver: Some(account_id),
+            stake: Some(U128(solver.stake)),
+            ..EventLog::new("solver_deregistered")
+        });
+    }
+
+    pub fn withdraw_stake(&mut self) -> Promise {
+        let account_id = env::predecessor_account_id();
+        let solver = self.solvers.get(&account_id).expect("Solver not registered");
+        let started_at = match solver.status {
+            SolverStatus::Unbonding => solver.unbonding_started_at.unwrap(),
+            SolverStatus::Active => env::panic_str("Solver must deregister first"),
+        };
+        assert!(
+            env::block_timestamp() >= started_at + self.unbonding_period,
+            "Unbonding period not over"
+        );
+        assert!(solver.open_slash_cases == 0, "Solver has open slash cases");
+
+        // Swap stats and slash history are kept for a later re-registration
+        self.solvers.remove(&account_id);
+        for worker_key in &solver.worker_keys {
+            self.internal_remove_worker(worker_key);
+        }
+
+        self.emit_event(EventLog {
+            solver: Some(account_id.clone()),
+            stake: Some(U128(solver.stake)),
+            ..EventLog::new("stake_withdrawn")
+        });
+
+        Promise::new(account_id).transfer(NearToken::from_yoctonear(solver.stake))
+    }
+
+    // Owner configuration
+    pub fn set_min_stake(&mut self, min_stake: U128) {
+        self.assert_owner();
+        self.min_stake = min_stake.0;
+    }
+
+    pub fn set_unbonding_period(&mut self, unbonding_period: U128) {
+        self.assert_owner();
+        self.unbonding_period = unbonding_period.0 as u64;
+    }
+
+    pub fn set_governance(&mut self, governance: Option<AccountId>) {
+        self.assert_owner();
+        self.governance = governance;
+    }
+
+    // Account that checks quote signatures off-chain; without one no worker
+    // can be registered
+    pub fn set_attestation_verifier(&mut self, verifier: Option<AccountId>) {
+        self.assert_owner();
+        self.attestation_verifier = verifier;
+    }
+
+    // Codehash governance (owner or DAO)
+    pub fn approve_codehash(&mut self, codehash: String, version: String) {
+        self.assert_governance();
+        assert!(
+            codehash.len() == 64 && hex::decode(&codehash).is_ok(),
+            "Codehash must be 32 bytes hex"
+        );
+
+        // Re-approving an image cancels a pending revocation
+        let entry = CodehashEntry {
+            version: version.clone(),
+            approved_at: env::block_timestamp(),
+            expires_at: None,
+        };
+        self.codehashes.insert(&codehash, &entry);
+
+        self.emit_event(EventLog {
+            codehash: Some(codehash),
+            version: Some(version),
+            ..EventLog::new("codehash_approved")
+        });
+    }
+
+    // Phase out an image. Without expires_at the revocation is immediate and
+    // its workers are deactivated right away; otherwise anyone can finalize
+    // it with expire_codehash once the expiry has passed.
+    pub fn revoke_codehash(&mut self, codehash: String, expires_at: Option<U128>) {
+        self.assert_governance();
+        let mut entry = self.codehashes.get(&codehash).expect("Codehash not approved");
+
+        match expires_at.map(|t| t.0 as u64) {
+            Some(expires_at) if expires_at > env::block_timestamp() => {
+                entry.expires_at = Some(expires_at);
+                self.codehashes.insert(&codehash, &entry);
+
+                self.emit_event(EventLog {
+                    codehash: Some(codehash),
+                    version: Some(entry.version),
+                    expires_at: Some(U128(expires_at as u128)),
+                    ..EventLog::new("codehash_revocation_scheduled")
+                });
+            }
+            _ => self.internal_revoke_codehash(codehash, entry),
+        }
+    }
+
+    pub fn expire_codehash(&mut self, codehash: String) {
+        let entry = self.codehashes.get(&codehash).expect("Codehash not approved");
+        let expires_at = entry.expires_at.expect("Codehash revocation not scheduled");
+        assert!(env::block_timestamp() >= expires_at, "Codehash not expired yet");
+
+        self.internal_revoke_codehash(codehash, entry);
+    }
+
+    // View methods
+    pub fn get_solver(&self, account_id: AccountId) -> Option<SolverView> {
+        self.solvers
+            .get(&account_id)
+            .map(|solver| self.solver_view(account_id, solver))
+    }
+
+    // Staked solvers with at least one live worker; pagination applies to
+    // this filtered list
+    pub fn get_active_solvers(&self, from_index: u64, limit: u64) -> Vec<SolverView> {
+        self.active_solvers
+            .iter()
+            .filter(|account_id| self.has_live_worker(account_id))
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .filter_map(|account_id| self.get_solver(account_id))
+            .collect()
+    }
+
+    // Number of staked solvers, whether or not their workers are live
+    pub fn get_num_active_solvers(&self) -> u64 {
+        self.active_solvers.len()
+    }
+
+    pub fn is_active_solver(&self, account_id: AccountId) -> bool {
+        self.active_solvers.contains(&account_id) && self.has_live_worker(&account_id)
+    }
+
+    pub fn get_codehashes(&self) -> Vec<CodehashView> {
+        self.codehashes
+            .iter()
+            .map(|(codehash, entry)| CodehashView {
+                codehash,
+                version: entry.version,
+                approved_at: U128(entry.approved_at as u128),
+                expires_at: entry.expires_at.map(|t| U128(t as u128)),
+            })
+            .collect()
+    }
+
+    // Approved and, if a revocation is scheduled, not yet past its expiry
+    pub fn is_codehash_approved(&self, codehash: String) -> bool {
+        self.codehashes.get(&codehash).is_some_and(|entry| {
+            entry
+                .expires_at
+                .is_none_or(|expires_at| env::block_timestamp() < expires_at)
+        })
+    }
+
+    pub fn get_governance(&self) -> Option<AccountId> {
+        self.governance.clone()
+    }
+
+    pub fn get_owner(&self) -> AccountId {
+        self.owner.clone()
+    }
+
+    pub fn get_config(&self) -> RegistryConfig {
+        RegistryConfig {
+            owner: self.owner.clone(),
+            min_stake: U128(self.min_stake),
+            unbonding_period: U128(self.unbonding_period as u128),
+            heartbeat_timeout: U128(self.heartbeat_timeout as u128),
+            attestation_verifier: self.attestation_verifier.clone(),
+        }
+    }
+
+    // Internal helpers
+    fn expect_active_solver(&self, account_id: &AccountId) -> Solver {
+        let solver = self.solvers.get(account_id).expect("Solver not registered");
+        assert!(solver.status == SolverStatus::Active, "Solver is not active");
+        solver
+    }
+
+    fn solver_view(&self, account_id: AccountId, solver: Solver) -> SolverView {
+        SolverView {
+            account_id,
+            stake: U128(solver.stake),
+            status: solver.status,
+            registered_at: U128(solver.registered_at as u128),
+            unbonding_ends_at: solver
+                .unbonding_started_at
+                .map(|t| U128((t + self.unbonding_period) as u128)),
+            worker_keys: solver.worker_keys,
+            metadata: solver.metadata,
+        }
+    }
+
+    fn internal_revoke_codehash(&mut self, codehash: String, entry: CodehashEntry) {
+        self.codehashes.remove(&codehash);
+
+        self.emit_event(EventLog {
+            codehash: Some(codehash.clone()),
+            version: Some(entry.version),
+            ..EventLog::new("codehash_revoked")
+        });
+
+        for worker_key in self.codehash_workers.remove(&codehash).unwrap_or_default() {
+            let Some(mut worker) = self.workers.get(&worker_key) else {
+                continue;
+            };
+            worker.active = false;
+            self.workers.insert(&worker_key, &worker);
+
+            self.emit_event(EventLog {
+                solver: Some(worker.solver),
+                worker_key: Some(worker_key),
+                codehash: Some(codehash.clone()),
+                ..EventLog::new("worker_deactivated")
+            });
+        }
+    }
+
+    fn assert_governance(&self) {
+        let caller = env::predecessor_account_id();
+        assert!(
+            caller == self.owner || Some(&caller) == self.governance.as_ref(),
+            "Only owner or governance can call this method"
+        );
+    }
+
+    fn assert_owner(&self) {
+        assert!(
+            env::predecessor_account_id() == self.owner,
+            "Only owner can call this method"
+        );
+    }
+
+    fn emit_event(&self, event: EventLog) {
+        env::log_str(&format!(
+            "EVENT_JSON:{}",
+            near_sdk::serde_json::to_string(&event).unwrap()
+        ));
+    }
+}
+
+// Bounds metadata so a registration cannot bloat storage beyond its stake
+fn assert_valid_metadata(metadata: &SolverMetadata) {
+    assert!(
+        !metadata.name.is_empty() && metadata.name.len() <= MAX_NAME_LEN,
+        "Solver name must be 1-64 bytes"
+    );
+    for list in [
+        &metadata.endpoints,
+        &metadata.supported_chains,
+        &metadata.supported_tokens,
+    ] {
+        assert!(list.len() <= MAX_LIST_LEN, "Too many metadata entries");
+        assert!(
+            list.iter().all(|entry| entry.len() <= MAX_ENTRY_LEN),
+            "Metadata entry too long"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::json_types::Base64VecU8;
+    use near_sdk::mock::MockAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, VMContext};
+
+    const MIN_STAKE: Balance = 10_000_000_000_000_000_000_000_000; // 10 NEAR
+    const UNBONDING_PERIOD: Timestamp = 86_400_000_000_000; // 1 day
+
+    fn get_context(predecessor: AccountId, deposit: Balance, timestamp: Timestamp) -> VMContext {
+        VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .signer_account_id(predecessor.clone())
+            .predecessor_account_id(predecessor)
+            .attached_deposit(NearToken::from_yoctonear(deposit))
+            .block_timestamp(timestamp)
+            .build()
+    }
+
+    fn metadata(name: &str) -> SolverMetadata {
+        SolverMetadata {
+            name: name.to_string(),
+            endpoints: vec!["https://solver.example.com".to_string()],
+            supported_chains: vec!["near".to_string(), "base".to_string()],
+            supported_tokens: vec!["usdc.near".to_string()],
+        }
+    }
+
+    fn setup() -> SolverRegistry {
+        testing_env!(get_context(accounts(0), 0, 0));
+        SolverRegistry::new(accounts(0), U128(MIN_STAKE), U128(UNBONDING_PERIOD as u128))
+    }
+
+    #[test]
+    fn test_contract_source_metadata() {
+        let metadata: serde_json::Value = serde_json::from_str(CONTRACT_SOURCE_METADATA).unwrap();
+        assert_eq!(metadata["version"], env!("CARGO_PKG_VERSION"));
+        assert!(metadata["standards"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|standard| standard["standard"] == "nep330"));
+        if let Some(abi_sha256) = option_env!("NEP330_ABI_SHA256_SOLVER_REGISTRY") {
+            let link = metadata["link"].as_str().unwrap();
+            assert!(link.ends_with(&format!("#abi-sha256={abi_sha256}")));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Attached deposit below minimum stake")]
+    fn test_register_below_min_stake() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(1), MIN_STAKE - 1, 0));
+        contract.register(metadata("alpha"));
+    }
+
+    #[test]
+    fn test_deregister_and_withdraw_after_unbonding() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(1), MIN_STAKE, 0));
+        contract.register(metadata("alpha"));
+
+        testing_env!(get_context(accounts(1), 0, 100));
+        contract.deregister();
+        assert!(!contract.is_active_solver(accounts(1)));
+        let solver = contract.get_solver(accounts(1)).unwrap();
+        assert_eq!(solver.status, SolverStatus::Unbonding);
+        assert_eq!(solver.unbonding_ends_at, Some(U128(100 + UNBONDING_PERIOD as u128)));
+
+        testing_env!(get_context(accounts(1), 0, 100 + UNBONDING_PERIOD));
+        contract.withdraw_stake();
+        assert!(contract.get_solver(accounts(1)).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Unbonding period not over")]
+    fn test_withdraw_before_unbonding_ends() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(1), MIN_STAKE, 0));
+        contract.register(metadata("alpha"));
+
+        testing_env!(get_context(accounts(1), 0, 100));
+        contract.deregister();
+        testing_env!(get_context(accounts(1), 0, UNBONDING_PERIOD));
+        contract.withdraw_stake();
+    }
+
+    const TDX_QUOTE: &str = include_str!("../fixtures/tdx_quote.hex");
+    const TDX_CODEHASH: &str = include_str!("../fixtures/tdx_codehash.txt");
+    const TDX_QUOTE_ROTATED: &str = include_str!("../fixtures/tdx_quote_rotated.hex");
+    const WORKER_KEY: &str = "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp";
+    const ROTATED_KEY: &str = "ed25519:DcA2MzgpJbrUATQLLceocVckhhAqrkingax4oJ9kZ847";
+    const HEARTBEAT_TIMEOUT: Timestamp = 600_000_000_000;
+
+    fn tdx_quote() -> Base64VecU8 {
+        Base64VecU8(hex::decode(TDX_QUOTE.trim()).unwrap())
+    }
+
+    fn rotated_quote() -> Base64VecU8 {
+        Base64VecU8(hex::decode(TDX_QUOTE_ROTATED.trim()).unwrap())
+    }
+
+    // Approves the fixtures' image and has the attestation verifier sign off
+    // on both quotes
+    fn approve_image(contract: &mut SolverRegistry) {
+        testing_env!(get_context(accounts(0), 0, 0));
+        contract.approve_codehash(TDX_CODEHASH.trim().to_string(), "v1".to_string());
+        contract.set_attestation_verifier(Some(accounts(5)));
+        testing_env!(get_context(accounts(5), 0, 0));
+        for quote in [tdx_quote(), rotated_quote()] {
+            contract.verify_quote(Base64VecU8(env::sha256(&quote.0)));
+        }
+    }
+
+    // Call made with the worker key on the solver account
+    fn worker_context(solver: AccountId, worker_key: &str, timestamp: Timestamp) -> VMContext {
+        VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .signer_account_id(solver.clone())
+            .signer_account_pk(worker_key.parse().unwrap())
+            .predecessor_account_id(solver)
+            .block_timestamp(timestamp)
+            .build()
+    }
+
+    #[test]
+    fn test_register_and_list() {
+        let mut contract = setup();
+        approve_image(&mut contract);
+
+        testing_env!(get_context(accounts(1), MIN_STAKE, 0));
+        contract.register(metadata("alpha"));
+        testing_env!(get_context(accounts(2), MIN_STAKE, 0));
+        contract.register(metadata("beta"));
+
+        // Staked, but not listed until a worker is attested
+        assert_eq!(contract.get_num_active_solvers(), 2);
+        assert!(!contract.is_active_solver(accounts(1)));
+        assert!(contract.get_active_solvers(0, 10).is_empty());
+
+        testing_env!(get_context(accounts(1), 0, 10));
+        contract.register_worker(WORKER_KEY.parse().unwrap(), tdx_quote());
+        testing_env!(get_context(accounts(2), 0, 10));
+        contract.register_worker(ROTATED_KEY.parse().unwrap(), rotated_quote());
+
+        assert!(contract.is_active_solver(accounts(1)));
+        let page = contract.get_active_solvers(1, 10);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].account_id, accounts(2));
+        assert_eq!(page[0].stake, U128(MIN_STAKE));
+    }
+
+    fn registered() -> SolverRegistry {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(1), MIN_STAKE, 0));
+        contract.register(metadata("alpha"));
+        contract
+    }
+
+    #[test]
+    fn test_register_attested_worker() {
+        let mut contract = registered();
+        approve_image(&mut contract);
+
+        testing_env!(get_context(accounts(1), 0, 10));
+        let key: PublicKey = WORKER_KEY.parse().unwrap();
+        contract.register_worker(key.clone(), tdx_quote());
+
+        let worker = contract.get_worker(key.clone()).unwrap();
+        assert_eq!(worker.solver, accounts(1));
+        assert_eq!(worker.tee_type, TeeType::Tdx);
+        assert_eq!(contract.get_solver(accounts(1)).unwrap().worker_keys, vec![key]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Worker codehash not approved")]
+    fn test_register_worker_unapproved_codehash() {
+        let mut contract = registered();
+        testing_env!(get_context(accounts(0), 0, 0));
+        contract.set_attestation_verifier(Some(accounts(5)));
+        testing_env!(get_context(accounts(5), 0, 0));
+        contract.verify_quote(Base64VecU8(env::sha256(&tdx_quote().0)));
+
+        testing_env!(get_context(accounts(1), 0, 10));
+        contract.register_worker(WORKER_KEY.parse().unwrap(), tdx_quote());
+    }
+
+    #[test]
+    #[should_panic(expected = "Quote report data does not bind worker key")]
+    fn test_register_worker_wrong_key() {
+        let mut contract = registered();
+        approve_image(&mut contract);
+
+        testing_env!(get_context(accounts(1), 0, 10));
+        contract.register_worker(ROTATED_KEY.parse().unwrap(), tdx_quote());
+    }
+
+    #[test]
+    #[should_panic(expected = "Quote not verified by the attestation verifier")]
+    fn test_register_worker_tampered_signature() {
+        let mut contract = registered();
+        approve_image(&mut contract);
+
+        // Same report body, but a signature byte flipped after the sign-off
+        let mut quote = tdx_quote();
+        *quote.0.last_mut().unwrap() ^= 1;
+        testing_env!(get_context(accounts(1), 0, 10));
+        contract.register_worker(WORKER_KEY.parse().unwrap(), quote);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the attestation verifier can verify quotes")]
+    fn test_verify_quote_from_other_account() {
+        let mut contract = registered();
+        testing_env!(get_context(accounts(0), 0, 0));
+        contract.set_attestation_verifier(Some(accounts(5)));
+        testing_env!(get_context(accounts(1), 0, 0));
+        contract.verify_quote(Base64VecU8(env::sha256(&tdx_quote().0)));
+    }
+
+    fn attested() -> SolverRegistry {
+        let mut contract = registered();
+        approve_image(&mut contract);
+        testing_env!(get_context(accounts(1), 0, 10));
+        contract.register_worker(WORKER_KEY.parse().unwrap(), tdx_quote());
+        contract
+    }
+
+    #[test]
+    fn test_heartbeat_timeout() {
+        let mut contract = attested();
+        assert!(contract.is_active_solver(accounts(1)));
+
+        testing_env!(worker_context(accounts(1), WORKER_KEY, HEARTBEAT_TIMEOUT));
+        contract.heartbeat();
+        assert!(contract.is_active_solver(accounts(1)));
+
+        // Missed heartbeats take the worker, and with it the solver, offline
+        testing_env!(get_context(accounts(2), 0, 2 * HEARTBEAT_TIMEOUT));
+        assert!(!contract.get_worker(WORKER_KEY.parse().unwrap()).unwrap().active);
+        assert!(!contract.is_active_solver(accounts(1)));
+        assert!(contract.get_active_solvers(0, 10).is_empty());
+
+        testing_env!(worker_context(accounts(1), WORKER_KEY, 2 * HEARTBEAT_TIMEOUT));
+        contract.heartbeat();
+        assert!(contract.is_active_solver(accounts(1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Worker not registered to solver")]
+    fn test_heartbeat_from_other_account() {
+        let mut contract = attested();
+        testing_env!(worker_context(accounts(2), WORKER_KEY, 100));
+        contract.heartbeat();
+    }
+
+    #[test]
+    fn test_rotate_worker_keeps_stake() {
+        let mut contract = attested();
+
+        testing_env!(get_context(accounts(1), 0, 100));
+        contract.rotate_worker(
+            WORKER_KEY.parse().unwrap(),
+            ROTATED_KEY.parse().unwrap(),
+            rotated_quote(),
+        );
+
+        assert!(contract.get_worker(WORKER_KEY.parse().unwrap()).is_none());
+        let solver = contract.get_solver(accounts(1)).unwrap();
+        assert_eq!(solver.stake, U128(MIN_STAKE));
+        assert_eq!(solver.worker_keys, vec![ROTATED_KEY.parse::<PublicKey>().unwrap()]);
+        assert_eq!(contract.get_solver_workers(accounts(1))[0].attested_at, U128(100));
+        assert!(contract.is_active_solver(accounts(1)));
+    }
+
+    #[test]
+    fn test_multiple_workers() {
+        let mut contract = attested();
+        testing_env!(get_context(accounts(1), 0, 100));
+        contract.register_worker(ROTATED_KEY.parse().unwrap(), rotated_quote());
+        assert_eq!(contract.get_solver_workers(accounts(1)).len(), 2);
+
+        // A stale worker does not matter while another one is live
+        testing_env!(worker_context(accounts(1), ROTATED_KEY, HEARTBEAT_TIMEOUT + 50));
+        contract.heartbeat();
+        assert!(!contract.get_worker(WORKER_KEY.parse().unwrap()).unwrap().active);
+        assert!(contract.is_active_solver(accounts(1)));
+
+        testing_env!(get_context(accounts(1), 0, HEARTBEAT_TIMEOUT + 50));
+        contract.remove_worker(ROTATED_KEY.parse().unwrap());
+        assert!(!contract.is_active_solver(accounts(1)));
+    }
+
+    #[test]
+    fn test_scheduled_codehash_revocation() {
+        let mut contract = attested();
+        let codehash = TDX_CODEHASH.trim().to_string();
+
+        testing_env!(get_context(accounts(0), 0, 100));
+        contract.set_governance(Some(accounts(3)));
+        testing_env!(get_context(accounts(3), 0, 100));
+        contract.revoke_codehash(codehash.clone(), Some(U128(1_000)));
+
+        // Still usable until expiry
+        assert!(contract.is_codehash_approved(codehash.clone()));
+        assert!(contract.get_worker(WORKER_KEY.parse().unwrap()).unwrap().active);
+
+        testing_env!(get_context(accounts(2), 0, 1_000));
+        assert!(!contract.get_worker(WORKER_KEY.parse().unwrap()).unwrap().active);
+        contract.expire_codehash(codehash.clone());
+        assert!(contract.get_codehashes().is_empty());
+        assert!(get_logs().iter().any(|log| log.contains("worker_deactivated")));
+    }
+
+    #[test]
+    fn test_immediate_revocation_deactivates_workers() {
+        let mut contract = attested();
+        testing_env!(get_context(accounts(0), 0, 100));
+        contract.revoke_codehash(TDX_CODEHASH.trim().to_string(), None);
+
+        let worker = contract.get_worker(WORKER_KEY.parse().unwrap()).unwrap();
+        assert!(!worker.active);
+        assert!(get_logs().iter().any(|log| log.contains("codehash_revoked")));
+    }
+
+    #[test]
+    #[should_panic(expected = "Codehash not expired yet")]
+    fn test_expire_codehash_before_expiry() {
+        let mut contract = attested();
+        testing_env!(get_context(accounts(0), 0, 100));
+        contract.revoke_codehash(TDX_CODEHASH.trim().to_string(), Some(U128(1_000)));
+        contract.expire_codehash(TDX_CODEHASH.trim().to_string());
+    }
+
+    const REPORT_BOND: Balance = 1_000_000_000_000_000_000_000_000; // 1 NEAR
+
+    fn reported_htlc(htlc_amount: Balance) -> (SolverRegistry, u64) {
+        let mut contract = registered();
+        testing_env!(get_context(accounts(0), 0, 0));
+        contract.set_htlc_contract(Some(accounts(4)));
+
+        testing_env!(get_context(accounts(4), REPORT_BOND, 100));
+        let case_id = contract.report_misbehavior(
+            accounts(1),
+            "htlc_1".to_string(),
+            accounts(2),
+            None,
+            U128(htlc_amount),
+            "base:0xescrow".to_string(),
+        );
+        (contract, case_id)
+    }
+
+    fn reported() -> (SolverRegistry, u64) {
+        reported_htlc(MIN_STAKE)
+    }
+
+    // Receivers of the transfers created so far
+    fn transfers() -> Vec<(AccountId, Balance)> {
+        get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| {
+                receipt.actions.into_iter().filter_map(move |action| match action {
+                    MockAction::Transfer { deposit, .. } => {
+                        Some((receipt.receiver_id.clone(), deposit.as_yoctonear()))
+                    }
+                    _ => None,
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_slash_after_dispute_window() {
+        let (mut contract, case_id) = reported();
+
+        testing_env!(get_context(accounts(3), 0, 100 + 86_400_000_000_000));
+        contract.execute_slash(case_id);
+
+        let case = contract.get_slash_case(case_id).unwrap();
+        assert_eq!(case.status, SlashStatus::Executed);
+        assert_eq!(case.slashed_amount, U128(MIN_STAKE / 10));
+        assert_eq!(case.paid_to, Some(accounts(2)));
+        assert_eq!(contract.get_solver(accounts(1)).unwrap().stake, U128(MIN_STAKE - MIN_STAKE / 10));
+        // Below the minimum stake the solver drops out of the active set
+        assert!(!contract.is_active_solver(accounts(1)));
+        assert_eq!(contract.get_slash_history(accounts(1), 0, 10).len(), 1);
+        // Slash and returned bond both go to the maker
+        assert_eq!(
+            transfers(),
+            vec![(accounts(2), MIN_STAKE / 10), (accounts(2), REPORT_BOND)]
+        );
+    }
+
+    #[test]
+    fn test_slash_capped_by_htlc_amount() {
+        let (mut contract, case_id) = reported_htlc(1_000);
+
+        testing_env!(get_context(accounts(3), 0, 100 + 86_400_000_000_000));
+        contract.execute_slash(case_id);
+
+        let case = contract.get_slash_case(case_id).unwrap();
+        assert_eq!(case.slashed_amount, U128(1_000));
+        assert_eq!(contract.get_solver(accounts(1)).unwrap().stake, U128(MIN_STAKE - 1_000));
+    }
+
+    #[test]
+    fn test_ft_slash_capped_by_converted_amount() {
+        let mut contract = registered();
+        testing_env!(get_context(accounts(0), 0, 0));
+        contract.set_htlc_contract(Some(accounts(4)));
+        contract.set_slash_rate("usdc.near".parse().unwrap(), Some(U128(1_000)));
+
+        // 7 token units at 1000 yoctoNEAR each
+        testing_env!(get_context(accounts(4), REPORT_BOND, 100));
+        let case_id = contract.report_misbehavior(
+            accounts(1),
+            "htlc_1".to_string(),
+            accounts(2),
+            Some("usdc.near".parse().unwrap()),
+            U128(7),
+            "base:0xescrow".to_string(),
+        );
+        // Rate changes do not affect open cases
+        testing_env!(get_context(accounts(0), 0, 100));
+        contract.set_slash_rate("usdc.near".parse().unwrap(), None);
+
+        testing_env!(get_context(accounts(3), 0, 100 + 86_400_000_000_000));
+        contract.execute_slash(case_id);
+
+        let case = contract.get_slash_case(case_id).unwrap();
+        assert_eq!(case.htlc_amount, U128(7));
+        assert_eq!(case.max_slash, U128(7_000));
+        assert_eq!(case.slashed_amount, U128(7_000));
+        assert_eq!(contract.get_solver(accounts(1)).unwrap().stake, U128(MIN_STAKE - 7_000));
+    }
+
+    #[test]
+    #[should_panic(expected = "No slash rate for token")]
+    fn test_ft_report_without_slash_rate() {
+        let mut contract = registered();
+        testing_env!(get_context(accounts(0), 0, 0));
+        contract.set_htlc_contract(Some(accounts(4)));
+
+        testing_env!(get_context(accounts(4), REPORT_BOND, 100));
+        contract.report_misbehavior(
+            accounts(1),
+            "htlc_1".to_string(),
+            accounts(2),
+            Some("usdc.near".parse().unwrap()),
+            U128(7),
+            String::new(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Attached deposit below report bond")]
+    fn test_report_without_bond() {
+        let mut contract = registered();
+        testing_env!(get_context(accounts(0), 0, 0));
+        contract.set_htlc_contract(Some(accounts(4)));
+
+        testing_env!(get_context(accounts(4), REPORT_BOND - 1, 100));
+        contract.report_misbehavior(
+            accounts(1),
+            "htlc_1".to_string(),
+            accounts(2),
+            None,
+            U128(1_000),
+            String::new(),
+        );
+    }
+
+    #[test]
+    fn test_disputed_slash_dismissed() {
+        let (mut contract, case_id) = reported();
+
+        testing_env!(get_context(accounts(1), 0, 200));
+        contract.dispute_slash(case_id, "counterparty leg never funded".to_string());
+
+        testing_env!(get_context(accounts(0), 0, 300));
+        contract.resolve_dispute(case_id, false);
+
+        let case = contract.get_slash_case(case_id).unwrap();
+        assert_eq!(case.status, SlashStatus::Dismissed);
+        assert_eq!(contract.get_solver(accounts(1)).unwrap().stake, U128(MIN_STAKE));
+        // The maker's bond is forfeited to the solver
+        assert_eq!(transfers(), vec![(accounts(1), REPORT_BOND)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Dispute window still open")]
+    fn test_execute_slash_during_dispute_window() {
+        let (mut contract, case_id) = reported();
+        testing_env!(get_context(accounts(3), 0, 200));
+        contract.execute_slash(case_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the HTLC contract can report misbehavior")]
+    fn test_report_from_unknown_account() {
+        let mut contract = registered();
+        contract.report_misbehavior(
+            accounts(1),
+            "htlc_1".to_string(),
+            accounts(2),
+            None,
+            U128(1_000),
+            String::new(),
+        );
+    }
+
+    #[test]
+    fn test_record_swap_outcomes() {
+        let mut contract = registered();
+        testing_env!(get_context(accounts(0), 0, 0));
+        contract.set_htlc_contract(Some(accounts(4)));
+
+        contract.set_min_swap_amount(None, Some(U128(100)));
+        contract.set_min_swap_amount(Some("usdc.near".parse().unwrap()), Some(U128(5)));
+
+        testing_env!(get_context(accounts(4), 0, 100));
+        contract.record_swap_outcome(accounts(1), accounts(2), None, U128(1_000), true, U128(60));
+        contract.record_swap_outcome(accounts(1), accounts(3), None, U128(500), true, U128(120));
+        contract.record_swap_outcome(accounts(1), accounts(2), None, U128(2_000), true, U128(30));
+        contract.record_swap_outcome(accounts(1), accounts(2), Some("usdc.near".parse().unwrap()), U128(7), false, U128(0));
+        // Dust swaps, self-swaps and tokens without a minimum only add to the volume
+        contract.record_swap_outcome(accounts(1), accounts(2), None, U128(99), true, U128(1));
+        contract.record_swap_outcome(accounts(1), accounts(1), None, U128(5_000), true, U128(1));
+        contract.record_swap_outcome(accounts(1), accounts(2), Some("dai.near".parse().unwrap()), U128(10), false, U128(0));
+        // Unknown accounts are ignored
+        contract.record_swap_outcome(accounts(2), accounts(3), None, U128(1), true, U128(1));
+
+        let stats = contract.get_solver_stats(accounts(1)).unwrap();
+        assert_eq!(stats.completed_swaps, 3);
+        assert_eq!(stats.refunded_swaps, 1);
+        assert_eq!(stats.counterparties, 2);
+        assert_eq!(stats.unscored_swaps, 1);
+        assert_eq!(stats.average_settlement_time, U128(70));
+        assert_eq!(stats.volume.len(), 1);
+        assert_eq!(stats.volume[0].token, "near");
+        assert_eq!(stats.volume[0].amount, U128(8_599));
+        // 75% success, ramped by 2 of 20 counterparties
+        assert_eq!(stats.score, 750);
+        assert!(contract.get_solver_stats(accounts(2)).is_none());
+    }
+
+    #[test]
+    fn test_stats_survive_reregistration() {
+        let (mut contract, case_id) = reported();
+        testing_env!(get_context(accounts(0), 0, 100));
+        contract.set_min_swap_amount(None, Some(U128(1)));
+        testing_env!(get_context(accounts(4), 0, 100));
+        contract.record_swap_outcome(accounts(1), accounts(2), None, U128(1_000), true, U128(60));
+        testing_env!(get_context(accounts(3), 0, 100 + 86_400_000_000_000));
+        contract.execute_slash(case_id);
+
+        testing_env!(get_context(accounts(1), 0, 200_000_000_000_000));
+        contract.deregister();
+        testing_env!(get_context(accounts(1), 0, 300_000_000_000_000));
+        contract.withdraw_stake();
+        testing_env!(get_context(accounts(1), MIN_STAKE, 300_000_000_000_000));
+        contract.register(metadata("alpha"));
+
+        let stats = contract.get_solver_stats(accounts(1)).unwrap();
+        assert_eq!(stats.completed_swaps, 1);
+        assert_eq!(stats.executed_slashes, 1);
+    }
+}
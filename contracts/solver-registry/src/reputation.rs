@@ -0,0 +1,218 @@
+// Per-solver swap statistics reported by the HTLC contract on every withdraw
+// and refund, and the reputation score derived from them.
+//
+// score = success_bps * min(counterparties, 20) / 20 - 1000 * executed_slashes,
+// clamped to [0, 10000]. success_bps is completed / (completed + refunded) in
+// basis points; counterparties is the number of distinct makers the solver
+// completed a counted swap with. The ramp keeps new solvers from outranking
+// established ones after a handful of fills, and keeps a solver from raising
+// its score by recycling capital through swaps with a few accounts of its own.
+// Swaps with itself never count.
+//
+// Only swaps of at least the owner-set minimum amount for their token count
+// towards the score, so it cannot be farmed with dust swaps. Tokens without a
+// minimum are not scored at all: their outcomes only add to the volume and to
+// unscored_swaps, so the owner must opt every token in explicitly. Stats
+// outlive deregistration, so re-registering does not reset a solver's record.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, AccountId, NearSchema};
+
+use crate::{Balance, SolverRegistry, SolverRegistryExt};
+
+const MAX_SCORE: u64 = 10_000;
+const CONFIDENCE_SWAPS: u64 = 20;
+const SLASH_PENALTY: u64 = 1_000;
+const NATIVE_TOKEN: &str = "near";
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Default)]
+pub struct SolverStats {
+    pub completed_swaps: u64,
+    pub refunded_swaps: u64,
+    pub executed_slashes: u64,
+    pub total_settlement_time: u128,
+    pub volume: Vec<(String, Balance)>,
+    pub counterparties: u64,
+    pub unscored_swaps: u64,
+}
+
+#[derive(Serialize, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct TokenVolume {
+    pub token: String,
+    pub amount: U128,
+}
+
+#[derive(Serialize, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct SolverStatsView {
+    pub account_id: AccountId,
+    pub completed_swaps: u64,
+    pub refunded_swaps: u64,
+    pub executed_slashes: u64,
+    pub counterparties: u64,
+    pub unscored_swaps: u64,
+    pub average_settlement_time: U128,
+    pub volume: Vec<TokenVolume>,
+    pub score: u64,
+}
+
+impl SolverStats {
+    pub fn score(&self) -> u64 {
+        let swaps = self.completed_swaps + self.refunded_swaps;
+        if swaps == 0 {
+            return 0;
+        }
+        let success_bps = self.completed_swaps * MAX_SCORE / swaps;
+        let weighted = success_bps * self.counterparties.min(CONFIDENCE_SWAPS) / CONFIDENCE_SWAPS;
+        weighted.saturating_sub(self.executed_slashes * SLASH_PENALTY)
+    }
+
+    fn add_volume(&mut self, token: String, amount: Balance) {
+        match self.volume.iter_mut().find(|(t, _)| *t == token) {
+            Some((_, total)) => *total += amount,
+            None => self.volume.push((token, amount)),
+        }
+    }
+}
+
+#[near_bindgen]
+impl SolverRegistry {
+    // Called by the HTLC contract when an HTLC is withdrawn (completed) or
+    // refunded; counterparty is the HTLC receiver. Outcomes for accounts that
+    // are not solvers are ignored.
+    pub fn record_swap_outcome(
+        &mut self,
+        solver: AccountId,
+        counterparty: AccountId,
+        token: Option<AccountId>,
+        amount: U128,
+        completed: bool,
+        settlement_time: U128,
+    ) {
+        assert!(
+            Some(env::predecessor_account_id()) == self.htlc_contract,
+            "Only the HTLC contract can record outcomes"
+        );
+        if self.solvers.get(&solver).is_none() {
+            return;
+        }
+
+        let token = token_key(token);
+        let min_amount = self.min_swap_amounts.get(&token);
+        let counted = counterparty != solver && min_amount.is_some_and(|min| amount.0 >= min);
+
+        let mut stats = self.solver_stats.get(&solver).unwrap_or_default();
+        if completed {
+            stats.add_volume(token, amount.0);
+        }
+        if min_amount.is_none() {
+            stats.unscored_swaps += 1;
+        }
+        if counted && completed {
+            stats.completed_swaps += 1;
+            stats.total_settlement_time += settlement_time.0;
+            if self.solver_counterparties.insert(&(solver.clone(), counterparty)) {
+                stats.counterparties += 1;
+            }
+        } else if counted {
+            stats.refunded_swaps += 1;
+        }
+        self.solver_stats.insert(&solver, &stats);
+    }
+
+    // Smallest swap in `token` (None for NEAR) that counts towards
+    // reputation; None stops scoring the token
+    pub fn set_min_swap_amount(&mut self, token: Option<AccountId>, min_amount: Option<U128>) {
+        self.assert_owner();
+        match min_amount {
+            Some(min_amount) => self.min_swap_amounts.insert(&token_key(token), &min_amount.0),
+            None => self.min_swap_amounts.remove(&token_key(token)),
+        };
+    }
+
+    pub fn get_min_swap_amount(&self, token: Option<AccountId>) -> Option<U128> {
+        self.min_swap_amounts.get(&token_key(token)).map(U128)
+    }
+
+    pub fn get_solver_stats(&self, account_id: AccountId) -> Option<SolverStatsView> {
+        self.solvers.get(&account_id)?;
+        let stats = self.solver_stats.get(&account_id).unwrap_or_default();
+
+        Some(SolverStatsView {
+            account_id,
+            completed_swaps: stats.completed_swaps,
+            refunded_swaps: stats.refunded_swaps,
+            executed_slashes: stats.executed_slashes,
+            counterparties: stats.counterparties,
+            unscored_swaps: stats.unscored_swaps,
+            average_settlement_time: U128(
+                stats
+                    .total_settlement_time
+                    .checked_div(stats.completed_swaps as u128)
+                    .unwrap_or(0),
+            ),
+            volume: stats
+                .volume
+                .iter()
+                .map(|(token, amount)| TokenVolume {
+                    token: token.clone(),
+                    amount: U128(*amount),
+                })
+                .collect(),
+            score: stats.score(),
+        })
+    }
+}
+
+pub(crate) fn token_key(token: Option<AccountId>) -> String {
+    token.map_or_else(|| NATIVE_TOKEN.to_string(), String::from)
+}
+
+impl SolverRegistry {
+    pub(crate) fn record_executed_slash(&mut self, solver: &AccountId) {
+        let mut stats = self.solver_stats.get(solver).unwrap_or_default();
+        stats.executed_slashes += 1;
+        self.solver_stats.insert(solver, &stats);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(completed: u64, refunded: u64, slashes: u64) -> SolverStats {
+        SolverStats {
+            completed_swaps: completed,
+            refunded_swaps: refunded,
+            executed_slashes: slashes,
+            counterparties: completed,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_score() {
+        assert_eq!(stats(0, 0, 0).score(), 0);
+        // Ramp: 5 perfect swaps count for a quarter of the full score
+        assert_eq!(stats(5, 0, 0).score(), 2_500);
+        assert_eq!(stats(40, 0, 0).score(), 10_000);
+        assert_eq!(stats(36, 4, 0).score(), 9_000);
+        assert_eq!(stats(36, 4, 2).score(), 7_000);
+        assert_eq!(stats(1, 1, 2).score(), 0);
+    }
+
+    #[test]
+    fn test_score_ramps_with_counterparties() {
+        // 40 perfect swaps with only 2 makers count like 2 swaps
+        let recycled = SolverStats {
+            counterparties: 2,
+            ..stats(40, 0, 0)
+        };
+        assert_eq!(recycled.score(), 1_000);
+    }
+}
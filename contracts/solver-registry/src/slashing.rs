@@ -0,0 +1,408 @@
+// Slashing of solver stake for failed or griefed swaps.
+//
+// The HTLC contract reports an HTLC that a solver refunded after the maker had
+// funded the counterparty leg. The counterparty leg lives on another chain and
+// cannot be checked here, so every report opens a case the solver can dispute
+// within the dispute window; disputes are settled by governance.
+//
+// The maker posts a bond with the report. It is returned when the slash is
+// executed and paid to the solver when the case is dismissed. A slash takes
+// slash_bps of the stake, but never more than max_htlc_multiple times the
+// reported HTLC amount, so small swaps cannot drain large stakes.
+//
+// Stake is in yoctoNEAR while NEP-141 HTLC amounts are in token units, so FT
+// amounts are converted with an owner-set slash rate (yoctoNEAR per smallest
+// token unit) before the multiple is applied. Reports for tokens without a
+// rate are rejected. The cap is fixed when the case is opened, so later rate
+// changes do not affect open cases.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, NearSchema, NearToken, Promise};
+
+use crate::reputation::token_key;
+use crate::{Balance, EventLog, SolverRegistry, SolverRegistryExt, Timestamp};
+
+const MAX_BPS: u16 = 10_000;
+const MAX_EVIDENCE_LEN: usize = 1_024;
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug, NearSchema,
+)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub enum SlashDestination {
+    InsurancePool,
+    Maker,
+}
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug, NearSchema,
+)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub enum SlashStatus {
+    Pending,
+    Disputed,
+    Executed,
+    Dismissed,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct SlashConfig {
+    pub slash_bps: u16,
+    pub max_htlc_multiple: u16,
+    pub report_bond: Balance,
+    pub dispute_window: Timestamp,
+    pub destination: SlashDestination,
+    pub insurance_pool: Option<AccountId>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct SlashCase {
+    pub solver: AccountId,
+    pub htlc_id: String,
+    pub maker: AccountId,
+    pub token: Option<AccountId>,
+    pub htlc_amount: Balance,
+    pub max_slash: Balance,
+    pub evidence: String,
+    pub bond: Balance,
+    pub reported_at: Timestamp,
+    pub status: SlashStatus,
+    pub dispute_reason: Option<String>,
+    pub slashed_amount: Balance,
+    pub paid_to: Option<AccountId>,
+}
+
+#[derive(Serialize, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct SlashCaseView {
+    pub case_id: u64,
+    pub solver: AccountId,
+    pub htlc_id: String,
+    pub maker: AccountId,
+    pub token: Option<AccountId>,
+    pub htlc_amount: U128,
+    pub max_slash: U128,
+    pub evidence: String,
+    pub bond: U128,
+    pub reported_at: U128,
+    pub dispute_ends_at: U128,
+    pub status: SlashStatus,
+    pub dispute_reason: Option<String>,
+    pub slashed_amount: U128,
+    pub paid_to: Option<AccountId>,
+}
+
+#[derive(Serialize, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct SlashConfigView {
+    pub htlc_contract: Option<AccountId>,
+    pub slash_bps: u16,
+    pub max_htlc_multiple: u16,
+    pub report_bond: U128,
+    pub dispute_window: U128,
+    pub destination: SlashDestination,
+    pub insurance_pool: Option<AccountId>,
+}
+
+impl Default for SlashConfig {
+    fn default() -> Self {
+        Self {
+            slash_bps: 1_000, // 10% of stake per case
+            max_htlc_multiple: 1,
+            report_bond: 1_000_000_000_000_000_000_000_000, // 1 NEAR
+            dispute_window: 86_400_000_000_000, // 1 day
+            destination: SlashDestination::Maker,
+            insurance_pool: None,
+        }
+    }
+}
+
+#[near_bindgen]
+impl SolverRegistry {
+    // Called by the HTLC contract when a maker reports a griefed swap, with
+    // the maker's bond attached
+    #[payable]
+    pub fn report_misbehavior(
+        &mut self,
+        solver: AccountId,
+        htlc_id: String,
+        maker: AccountId,
+        token: Option<AccountId>,
+        amount: U128,
+        evidence: String,
+    ) -> u64 {
+        assert!(
+            Some(env::predecessor_account_id()) == self.htlc_contract,
+            "Only the HTLC contract can report misbehavior"
+        );
+        assert!(evidence.len() <= MAX_EVIDENCE_LEN, "Evidence too long");
+        let bond = env::attached_deposit().as_yoctonear();
+        assert!(bond >= self.slash_config.report_bond, "Attached deposit below report bond");
+        let mut account = self.solvers.get(&solver).expect("Solver not registered");
+        let htlc_value = match &token {
+            None => amount.0,
+            Some(_) => {
+                let rate = self
+                    .slash_rates
+                    .get(&token_key(token.clone()))
+                    .expect("No slash rate for token");
+                amount.0.saturating_mul(rate)
+            }
+        };
+
+        let case_id = self.slash_cases.len();
+        let case = SlashCase {
+            solver: solver.clone(),
+            htlc_id,
+            maker,
+            token,
+            htlc_amount: amount.0,
+            max_slash: htlc_value.saturating_mul(self.slash_config.max_htlc_multiple as u128),
+            evidence,
+            bond,
+            reported_at: env::block_timestamp(),
+            status: SlashStatus::Pending,
+            dispute_reason: None,
+            slashed_amount: 0,
+            paid_to: None,
+        };
+        self.slash_cases.push(&case);
+
+        let mut history = self.solver_slash_cases.get(&solver).unwrap_or_default();
+        history.push(case_id);
+        self.solver_slash_cases.insert(&solver, &history);
+
+        // Stake stays locked while a case is open
+        account.open_slash_cases += 1;
+        self.solvers.insert(&solver, &account);
+
+        self.emit_event(EventLog {
+            solver: Some(solver),
+            case_id: Some(case_id),
+            amount: Some(amount),
+            ..EventLog::new("slash_reported")
+        });
+
+        case_id
+    }
+
+    pub fn dispute_slash(&mut self, case_id: u64, reason: String) {
+        let mut case = self.expect_slash_case(case_id);
+        assert!(
+            env::predecessor_account_id() == case.solver,
+            "Only the reported solver can dispute"
+        );
+        assert!(case.status == SlashStatus::Pending, "Slash case is not pending");
+        assert!(
+            env::block_timestamp() < case.reported_at + self.slash_config.dispute_window,
+            "Dispute window closed"
+        );
+        assert!(reason.len() <= MAX_EVIDENCE_LEN, "Dispute reason too long");
+
+        case.status = SlashStatus::Disputed;
+        case.dispute_reason = Some(reason);
+        self.slash_cases.replace(case_id, &case);
+
+        self.emit_event(EventLog {
+            solver: Some(case.solver),
+            case_id: Some(case_id),
+            ..EventLog::new("slash_disputed")
+        });
+    }
+
+    // Governance ruling on a disputed case
+    pub fn resolve_dispute(&mut self, case_id: u64, uphold: bool) {
+        self.assert_governance();
+        let case = self.expect_slash_case(case_id);
+        assert!(case.status == SlashStatus::Disputed, "Slash case is not disputed");
+
+        if uphold {
+            self.internal_execute_slash(case_id, case);
+        } else {
+            self.close_slash_case(case_id, case, SlashStatus::Dismissed);
+        }
+    }
+
+    // Anyone can execute an undisputed case once the dispute window is over
+    pub fn execute_slash(&mut self, case_id: u64) {
+        let case = self.expect_slash_case(case_id);
+        assert!(case.status == SlashStatus::Pending, "Slash case is not pending");
+        assert!(
+            env::block_timestamp() >= case.reported_at + self.slash_config.dispute_window,
+            "Dispute window still open"
+        );
+
+        self.internal_execute_slash(case_id, case);
+    }
+
+    // Owner configuration
+    pub fn set_htlc_contract(&mut self, htlc_contract: Option<AccountId>) {
+        self.assert_owner();
+        self.htlc_contract = htlc_contract;
+    }
+
+    pub fn set_slash_config(
+        &mut self,
+        slash_bps: u16,
+        max_htlc_multiple: u16,
+        report_bond: U128,
+        dispute_window: U128,
+        destination: SlashDestination,
+        insurance_pool: Option<AccountId>,
+    ) {
+        self.assert_owner();
+        assert!(slash_bps <= MAX_BPS, "Slash bps above 10000");
+        assert!(max_htlc_multiple > 0, "HTLC multiple must be positive");
+        assert!(
+            destination != SlashDestination::InsurancePool || insurance_pool.is_some(),
+            "Insurance pool account required"
+        );
+        self.slash_config = SlashConfig {
+            slash_bps,
+            max_htlc_multiple,
+            report_bond: report_bond.0,
+            dispute_window: dispute_window.0 as u64,
+            destination,
+            insurance_pool,
+        };
+    }
+
+    // yoctoNEAR per smallest unit of `token`, used to cap slashes for FT
+    // HTLCs; None stops accepting reports for the token
+    pub fn set_slash_rate(&mut self, token: AccountId, rate: Option<U128>) {
+        self.assert_owner();
+        match rate {
+            Some(rate) => self.slash_rates.insert(&token_key(Some(token)), &rate.0),
+            None => self.slash_rates.remove(&token_key(Some(token))),
+        };
+    }
+
+    // View methods
+    pub fn get_slash_rate(&self, token: AccountId) -> Option<U128> {
+        self.slash_rates.get(&token_key(Some(token))).map(U128)
+    }
+
+    pub fn get_slash_case(&self, case_id: u64) -> Option<SlashCaseView> {
+        self.slash_cases
+            .get(case_id)
+            .map(|case| self.slash_case_view(case_id, case))
+    }
+
+    pub fn get_slash_history(
+        &self,
+        account_id: AccountId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<SlashCaseView> {
+        self.solver_slash_cases
+            .get(&account_id)
+            .unwrap_or_default()
+            .into_iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .filter_map(|case_id| self.get_slash_case(case_id))
+            .collect()
+    }
+
+    pub fn get_slash_config(&self) -> SlashConfigView {
+        SlashConfigView {
+            htlc_contract: self.htlc_contract.clone(),
+            slash_bps: self.slash_config.slash_bps,
+            max_htlc_multiple: self.slash_config.max_htlc_multiple,
+            report_bond: U128(self.slash_config.report_bond),
+            dispute_window: U128(self.slash_config.dispute_window as u128),
+            destination: self.slash_config.destination.clone(),
+            insurance_pool: self.slash_config.insurance_pool.clone(),
+        }
+    }
+}
+
+impl SolverRegistry {
+    fn expect_slash_case(&self, case_id: u64) -> SlashCase {
+        self.slash_cases.get(case_id).expect("Slash case does not exist")
+    }
+
+    fn internal_execute_slash(&mut self, case_id: u64, mut case: SlashCase) {
+        let mut solver = self.solvers.get(&case.solver).expect("Solver not registered");
+        let slashed = (solver.stake * self.slash_config.slash_bps as u128 / MAX_BPS as u128)
+            .min(case.max_slash);
+
+        solver.stake -= slashed;
+        // Drop out of the active set until the stake is topped up again
+        if solver.stake < self.min_stake {
+            self.active_solvers.remove(&case.solver);
+        }
+        self.solvers.insert(&case.solver, &solver);
+
+        let paid_to = match self.slash_config.destination {
+            SlashDestination::Maker => case.maker.clone(),
+            SlashDestination::InsurancePool => self
+                .slash_config
+                .insurance_pool
+                .clone()
+                .expect("Insurance pool account required"),
+        };
+        if slashed > 0 {
+            Promise::new(paid_to.clone()).transfer(NearToken::from_yoctonear(slashed));
+        }
+
+        self.record_executed_slash(&case.solver);
+
+        case.slashed_amount = slashed;
+        case.paid_to = Some(paid_to);
+        self.close_slash_case(case_id, case, SlashStatus::Executed);
+    }
+
+    fn close_slash_case(&mut self, case_id: u64, mut case: SlashCase, status: SlashStatus) {
+        if let Some(mut solver) = self.solvers.get(&case.solver) {
+            solver.open_slash_cases -= 1;
+            self.solvers.insert(&case.solver, &solver);
+        }
+
+        // The bond goes back to the maker if the report held up and to the
+        // solver otherwise
+        let (event_type, bond_to) = match status {
+            SlashStatus::Executed => ("slash_executed", &case.maker),
+            _ => ("slash_dismissed", &case.solver),
+        };
+        if case.bond > 0 {
+            Promise::new(bond_to.clone()).transfer(NearToken::from_yoctonear(case.bond));
+        }
+        case.status = status;
+        self.slash_cases.replace(case_id, &case);
+
+        self.emit_event(EventLog {
+            solver: Some(case.solver),
+            case_id: Some(case_id),
+            amount: Some(U128(case.slashed_amount)),
+            ..EventLog::new(event_type)
+        });
+    }
+
+    fn slash_case_view(&self, case_id: u64, case: SlashCase) -> SlashCaseView {
+        SlashCaseView {
+            case_id,
+            dispute_ends_at: U128((case.reported_at + self.slash_config.dispute_window) as u128),
+            solver: case.solver,
+            htlc_id: case.htlc_id,
+            maker: case.maker,
+            token: case.token,
+            htlc_amount: U128(case.htlc_amount),
+            max_slash: U128(case.max_slash),
+            evidence: case.evidence,
+            bond: U128(case.bond),
+            reported_at: U128(case.reported_at as u128),
+            status: case.status,
+            dispute_reason: case.dispute_reason,
+            slashed_amount: U128(case.slashed_amount),
+            paid_to: case.paid_to,
+        }
+    }
+
+}
@@ -0,0 +1,259 @@
+// TEE workers acting on behalf of solvers.
+//
+// A solver can bind several workers, each admitted with its own attestation
+// quote, and rotate their keys without touching its stake. Workers must call
+// heartbeat periodically; a worker is live while its codehash is approved and
+// its last heartbeat is within the heartbeat timeout. Solvers without a live
+// worker are left out of the active-solver views used by the HTLC contract.
+//
+// The registry does not add access keys for workers. A contract can only
+// manage keys on its own account, so calls made with them would arrive from
+// the registry rather than the solver and could not attach the deposits
+// create_htlc needs. Solvers add the worker's function-call key to their own
+// account once it is attested.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base64VecU8, U128};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, AccountId, NearSchema, PublicKey};
+
+use crate::attestation::{Quote, TeeType};
+use crate::{EventLog, Solver, SolverRegistry, SolverRegistryExt, Timestamp};
+
+const MAX_WORKERS_PER_SOLVER: usize = 8;
+
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct Worker {
+    pub solver: AccountId,
+    pub tee_type: TeeType,
+    pub codehash: String,
+    pub attested_at: Timestamp,
+    pub last_heartbeat: Timestamp,
+    // Cleared when the worker's codehash is revoked
+    pub active: bool,
+}
+
+#[derive(Serialize, NearSchema)]
+#[serde(crate = "near_sdk::serde")]
+#[abi(json)]
+pub struct WorkerView {
+    pub public_key: PublicKey,
+    pub solver: AccountId,
+    pub tee_type: TeeType,
+    pub codehash: String,
+    pub attested_at: U128,
+    pub last_heartbeat: U128,
+    pub active: bool,
+}
+
+#[near_bindgen]
+impl SolverRegistry {
+    // Bind a TEE worker key to the calling solver. The quote must commit to
+    // the key in its report data and carry an approved measurement.
+    pub fn register_worker(&mut self, public_key: PublicKey, quote: Base64VecU8) {
+        let account_id = env::predecessor_account_id();
+        let mut solver = self.expect_active_solver(&account_id);
+        assert!(
+            solver.worker_keys.len() < MAX_WORKERS_PER_SOLVER,
+            "Too many workers for solver"
+        );
+
+        let codehash =
+            self.internal_add_worker(&account_id, &mut solver, public_key.clone(), quote);
+
+        self.emit_event(EventLog {
+            solver: Some(account_id),
+            worker_key: Some(public_key),
+            codehash: Some(codehash),
+            ..EventLog::new("worker_registered")
+        });
+    }
+
+    // Replace a worker key with a freshly attested one, e.g. after a restart
+    pub fn rotate_worker(&mut self, old_key: PublicKey, new_key: PublicKey, quote: Base64VecU8) {
+        let account_id = env::predecessor_account_id();
+        let mut solver = self.expect_active_solver(&account_id);
+        assert!(
+            solver.worker_keys.contains(&old_key),
+            "Worker not registered to solver"
+        );
+
+        solver.worker_keys.retain(|key| *key != old_key);
+        self.internal_remove_worker(&old_key);
+        let codehash = self.internal_add_worker(&account_id, &mut solver, new_key.clone(), quote);
+
+        self.emit_event(EventLog {
+            solver: Some(account_id),
+            worker_key: Some(new_key),
+            codehash: Some(codehash),
+            ..EventLog::new("worker_rotated")
+        });
+    }
+
+    pub fn remove_worker(&mut self, public_key: PublicKey) {
+        let account_id = env::predecessor_account_id();
+        let mut solver = self
+            .solvers
+            .get(&account_id)
+            .expect("Solver not registered");
+        assert!(
+            solver.worker_keys.contains(&public_key),
+            "Worker not registered to solver"
+        );
+
+        solver.worker_keys.retain(|key| *key != public_key);
+        self.solvers.insert(&account_id, &solver);
+        self.internal_remove_worker(&public_key);
+
+        self.emit_event(EventLog {
+            solver: Some(account_id),
+            worker_key: Some(public_key),
+            ..EventLog::new("worker_removed")
+        });
+    }
+
+    // Signed by the worker key through an access key on the solver account
+    pub fn heartbeat(&mut self) {
+        let public_key = env::signer_account_pk();
+        let mut worker = self
+            .workers
+            .get(&public_key)
+            .expect("Worker not registered");
+        assert!(
+            env::predecessor_account_id() == worker.solver,
+            "Worker not registered to solver"
+        );
+        assert!(worker.active, "Worker is not active");
+
+        worker.last_heartbeat = env::block_timestamp();
+        self.workers.insert(&public_key, &worker);
+    }
+
+    // Sign-off by the attestation verifier on a quote whose signature and PCK
+    // chain it checked against Intel's collateral. Each sign-off admits one
+    // worker registration with exactly that quote.
+    pub fn verify_quote(&mut self, quote_hash: Base64VecU8) {
+        assert!(
+            Some(env::predecessor_account_id()) == self.attestation_verifier,
+            "Only the attestation verifier can verify quotes"
+        );
+        assert!(quote_hash.0.len() == 32, "Quote hash must be 32 bytes");
+        self.verified_quotes.insert(&quote_hash.0);
+    }
+
+    pub fn is_quote_verified(&self, quote_hash: Base64VecU8) -> bool {
+        self.verified_quotes.contains(&quote_hash.0)
+    }
+
+    pub fn set_heartbeat_timeout(&mut self, heartbeat_timeout: U128) {
+        self.assert_owner();
+        assert!(
+            heartbeat_timeout.0 > 0,
+            "Heartbeat timeout must be positive"
+        );
+        self.heartbeat_timeout = heartbeat_timeout.0 as u64;
+    }
+
+    pub fn get_worker(&self, public_key: PublicKey) -> Option<WorkerView> {
+        self.workers
+            .get(&public_key)
+            .map(|worker| self.worker_view(public_key, worker))
+    }
+
+    pub fn get_solver_workers(&self, account_id: AccountId) -> Vec<WorkerView> {
+        self.solvers
+            .get(&account_id)
+            .map(|solver| solver.worker_keys)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|public_key| self.get_worker(public_key))
+            .collect()
+    }
+}
+
+impl SolverRegistry {
+    // Attest a worker and bind it to the solver; returns its codehash
+    fn internal_add_worker(
+        &mut self,
+        account_id: &AccountId,
+        solver: &mut Solver,
+        public_key: PublicKey,
+        quote: Base64VecU8,
+    ) -> String {
+        assert!(
+            self.workers.get(&public_key).is_none(),
+            "Worker key already registered"
+        );
+
+        assert!(
+            self.verified_quotes.remove(&env::sha256(&quote.0)),
+            "Quote not verified by the attestation verifier"
+        );
+        let quote = Quote::parse(&quote.0).unwrap_or_else(|err| env::panic_str(err));
+        assert!(
+            quote.binds_public_key(&public_key),
+            "Quote report data does not bind worker key"
+        );
+        let codehash = quote.codehash();
+        assert!(
+            self.is_codehash_approved(codehash.clone()),
+            "Worker codehash not approved"
+        );
+
+        let now = env::block_timestamp();
+        let worker = Worker {
+            solver: account_id.clone(),
+            tee_type: quote.tee_type,
+            codehash: codehash.clone(),
+            attested_at: now,
+            last_heartbeat: now,
+            active: true,
+        };
+        self.workers.insert(&public_key, &worker);
+        let mut keys = self.codehash_workers.get(&codehash).unwrap_or_default();
+        keys.push(public_key.clone());
+        self.codehash_workers.insert(&codehash, &keys);
+
+        solver.worker_keys.push(public_key);
+        self.solvers.insert(account_id, solver);
+
+        codehash
+    }
+
+    pub(crate) fn internal_remove_worker(&mut self, public_key: &PublicKey) {
+        if let Some(worker) = self.workers.remove(public_key) {
+            if let Some(mut keys) = self.codehash_workers.get(&worker.codehash) {
+                keys.retain(|key| key != public_key);
+                self.codehash_workers.insert(&worker.codehash, &keys);
+            }
+        }
+    }
+
+    fn is_worker_live(&self, worker: &Worker) -> bool {
+        worker.active
+            && self.is_codehash_approved(worker.codehash.clone())
+            && env::block_timestamp() < worker.last_heartbeat + self.heartbeat_timeout
+    }
+
+    pub(crate) fn has_live_worker(&self, account_id: &AccountId) -> bool {
+        self.solvers.get(account_id).is_some_and(|solver| {
+            solver
+                .worker_keys
+                .iter()
+                .filter_map(|key| self.workers.get(key))
+                .any(|worker| self.is_worker_live(&worker))
+        })
+    }
+
+    fn worker_view(&self, public_key: PublicKey, worker: Worker) -> WorkerView {
+        WorkerView {
+            public_key,
+            active: self.is_worker_live(&worker),
+            solver: worker.solver,
+            tee_type: worker.tee_type,
+            codehash: worker.codehash,
+            attested_at: U128(worker.attested_at as u128),
+            last_heartbeat: U128(worker.last_heartbeat as u128),
+        }
+    }
+}
@@ -0,0 +1,164 @@
+// Minimal NEP-141 token for the sandbox tests. Anyone can mint and storage
+// is registered for free; transfers and ft_transfer_call follow the
+// standard, including refunds of unused amounts in ft_resolve_transfer.
+
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::json_types::U128;
+use near_sdk::{
+    env, ext_contract, near_bindgen, require, AccountId, BorshStorageKey, Gas, NearToken,
+    PanicOnDefault, PromiseOrValue, PromiseResult,
+};
+
+const GAS_FOR_FT_ON_TRANSFER: Gas = Gas::from_tgas(30);
+const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas::from_tgas(10);
+
+#[ext_contract(ext_ft_receiver)]
+pub trait FungibleTokenReceiver {
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128>;
+}
+
+#[ext_contract(ext_self)]
+pub trait FungibleTokenResolver {
+    fn ft_resolve_transfer(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> U128;
+}
+
+#[derive(BorshStorageKey, BorshSerialize)]
+#[borsh(crate = "near_sdk::borsh")]
+enum StorageKey {
+    Balances,
+}
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+#[borsh(crate = "near_sdk::borsh")]
+pub struct MockFungibleToken {
+    balances: LookupMap<AccountId, u128>,
+    total_supply: u128,
+}
+
+#[near_bindgen]
+impl MockFungibleToken {
+    #[init]
+    pub fn new() -> Self {
+        Self {
+            balances: LookupMap::new(StorageKey::Balances),
+            total_supply: 0,
+        }
+    }
+
+    pub fn mint(&mut self, account_id: AccountId, amount: U128) {
+        let balance = self.balances.get(&account_id).unwrap_or(0);
+        self.balances.insert(&account_id, &(balance + amount.0));
+        self.total_supply += amount.0;
+    }
+
+    // Free stand-in for storage_deposit
+    pub fn register(&mut self, account_id: AccountId) {
+        if self.balances.get(&account_id).is_none() {
+            self.balances.insert(&account_id, &0);
+        }
+    }
+
+    #[payable]
+    pub fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>) {
+        require!(
+            env::attached_deposit() == NearToken::from_yoctonear(1),
+            "Requires attached deposit of exactly 1 yoctoNEAR"
+        );
+        let _ = memo;
+        self.internal_transfer(&env::predecessor_account_id(), &receiver_id, amount.0);
+    }
+
+    #[payable]
+    pub fn ft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        require!(
+            env::attached_deposit() == NearToken::from_yoctonear(1),
+            "Requires attached deposit of exactly 1 yoctoNEAR"
+        );
+        let _ = memo;
+        let sender_id = env::predecessor_account_id();
+        self.internal_transfer(&sender_id, &receiver_id, amount.0);
+
+        ext_ft_receiver::ext(receiver_id.clone())
+            .with_static_gas(GAS_FOR_FT_ON_TRANSFER)
+            .ft_on_transfer(sender_id.clone(), amount, msg)
+            .then(
+                ext_self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_TRANSFER)
+                    .ft_resolve_transfer(sender_id, receiver_id, amount),
+            )
+            .into()
+    }
+
+    // Returns the amount the receiver kept
+    #[private]
+    pub fn ft_resolve_transfer(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> U128 {
+        // A failed ft_on_transfer refunds everything
+        let unused = match env::promise_result(0) {
+            PromiseResult::Successful(value) => near_sdk::serde_json::from_slice::<U128>(&value)
+                .map(|unused| unused.0.min(amount.0))
+                .unwrap_or(amount.0),
+            PromiseResult::Failed => amount.0,
+        };
+
+        let refund = unused.min(self.balances.get(&receiver_id).unwrap_or(0));
+        if refund > 0 {
+            self.internal_transfer(&receiver_id, &sender_id, refund);
+        }
+        U128(amount.0 - refund)
+    }
+
+    pub fn ft_total_supply(&self) -> U128 {
+        U128(self.total_supply)
+    }
+
+    pub fn ft_balance_of(&self, account_id: AccountId) -> U128 {
+        U128(self.balances.get(&account_id).unwrap_or(0))
+    }
+
+    fn internal_transfer(&mut self, sender_id: &AccountId, receiver_id: &AccountId, amount: u128) {
+        require!(
+            sender_id != receiver_id,
+            "Sender and receiver should be different"
+        );
+        require!(amount > 0, "The amount should be a positive number");
+        let sender_balance = self
+            .balances
+            .get(sender_id)
+            .expect("Sender is not registered");
+        let receiver_balance = self
+            .balances
+            .get(receiver_id)
+            .expect("Receiver is not registered");
+        require!(
+            sender_balance >= amount,
+            "The account doesn't have enough balance"
+        );
+
+        self.balances.insert(sender_id, &(sender_balance - amount));
+        self.balances
+            .insert(receiver_id, &(receiver_balance + amount));
+    }
+}
@@ -0,0 +1,35 @@
+// HTLC receiver for the sandbox tests that withdraws and deletes itself from
+// the same receipt. Both run in the next block and the payout in the one
+// after, so the payout always reaches a deleted account.
+
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::{env, ext_contract, near_bindgen, AccountId, Gas, Promise};
+
+const GAS_FOR_WITHDRAW: Gas = Gas::from_tgas(50);
+
+#[ext_contract(ext_htlc)]
+pub trait FusionPlusHTLC {
+    fn withdraw(&mut self, htlc_id: String, secret: Base64VecU8);
+}
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+#[borsh(crate = "near_sdk::borsh")]
+pub struct MockReceiver {}
+
+#[near_bindgen]
+impl MockReceiver {
+    pub fn withdraw_and_delete(
+        &self,
+        htlc: AccountId,
+        htlc_id: String,
+        secret: Base64VecU8,
+        beneficiary: AccountId,
+    ) {
+        ext_htlc::ext(htlc)
+            .with_static_gas(GAS_FOR_WITHDRAW)
+            .withdraw(htlc_id, secret);
+        Promise::new(env::current_account_id()).delete_account(beneficiary);
+    }
+}
@@ -0,0 +1,549 @@
+// Typed async bindings for the FusionPlusHTLC contract.
+//
+// Requests and responses reuse the contract's own serde types, so a change
+// to the contract interface shows up here as a compile error rather than as a
+// JSON mismatch at runtime. The chain is reached through a Transport: the
+// near-workspaces one (default "workspaces" feature) covers both a local
+// sandbox and JSON-RPC endpoints, and tests can plug in their own.
+
+use near_sdk::json_types::{Base64VecU8, U128};
+use near_sdk::serde::de::DeserializeOwned;
+use near_sdk::{AccountId, Gas, NearToken};
+use serde_json::{json, Value};
+
+mod transport;
+
+pub use fusion_plus_htlc::{
+    BaseEscrow, ContractConfig, ContractInfo, ContractStats, CreateHTLCArgs, EventLog, HTLCView,
+    TokenAmount,
+};
+pub use transport::{CallOutcome, Transport};
+
+// Enough for the resolver check on create and the settlement signature on
+// withdraw
+pub const DEFAULT_GAS: Gas = Gas::from_tgas(300);
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("transport error: {0}")]
+    Transport(String),
+    #[error("transaction failed: {0}")]
+    Execution(String),
+    #[error("unexpected response: {0}")]
+    Decode(#[from] serde_json::Error),
+    // create_htlc's resolver check refunded the deposit instead of creating
+    #[error("HTLC creation rejected: {}", .0.join("; "))]
+    Rejected(Vec<String>),
+}
+
+pub struct HtlcClient<T> {
+    transport: T,
+    contract_id: AccountId,
+    gas: Gas,
+}
+
+impl<T: Transport> HtlcClient<T> {
+    pub fn new(transport: T, contract_id: AccountId) -> Self {
+        Self {
+            transport,
+            contract_id,
+            gas: DEFAULT_GAS,
+        }
+    }
+
+    pub fn with_gas(mut self, gas: Gas) -> Self {
+        self.gas = gas;
+        self
+    }
+
+    pub fn contract_id(&self) -> &AccountId {
+        &self.contract_id
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    // Attaches the amount for native NEAR HTLCs, plus the settlement sign
+    // deposit if the HTLC settles on BASE. NEP-141 HTLCs are created by
+    // locking the tokens with ft_transfer_call instead. Returns the new HTLC
+    // id.
+    pub async fn create_htlc(&self, args: &CreateHTLCArgs) -> Result<String, ClientError> {
+        if let Some(token) = &args.token {
+            return self.create_ft_htlc(token, args).await;
+        }
+        let deposit = match args.base_escrow {
+            Some(_) => NearToken::from_yoctonear(args.amount.0 + self.settlement_deposit().await?),
+            None => NearToken::from_yoctonear(args.amount.0),
+        };
+        let outcome = self
+            .call("create_htlc", json!({ "args": args }), deposit)
+            .await?;
+        // In resolver-exclusive mode the id comes from the registry callback,
+        // which returns null after refunding a rejected creation
+        match outcome.json::<Option<String>>()? {
+            Some(htlc_id) => Ok(htlc_id),
+            None => Err(ClientError::Rejected(outcome.logs)),
+        }
+    }
+
+    pub async fn withdraw(&self, htlc_id: &str, secret: &[u8]) -> Result<CallOutcome, ClientError> {
+        let args = json!({
+            "htlc_id": htlc_id,
+            "secret": Base64VecU8(secret.to_vec()),
+        });
+        self.call("withdraw", args, NearToken::from_yoctonear(0))
+            .await
+    }
+
+    pub async fn refund(&self, htlc_id: &str) -> Result<CallOutcome, ClientError> {
+        self.call(
+            "refund",
+            json!({ "htlc_id": htlc_id }),
+            NearToken::from_yoctonear(0),
+        )
+        .await
+    }
+
+    // Pays out the signer's failed payouts of `token` (None for NEAR) again
+    pub async fn claim(&self, token: Option<&AccountId>) -> Result<CallOutcome, ClientError> {
+        self.call(
+            "claim",
+            json!({ "token": token }),
+            NearToken::from_yoctonear(0),
+        )
+        .await
+    }
+
+    pub async fn get_htlc(&self, htlc_id: &str) -> Result<Option<HTLCView>, ClientError> {
+        self.view("get_htlc", json!({ "htlc_id": htlc_id })).await
+    }
+
+    pub async fn get_active_htlcs(
+        &self,
+        from_index: u64,
+        limit: u64,
+    ) -> Result<Vec<HTLCView>, ClientError> {
+        self.view(
+            "get_active_htlcs",
+            json!({ "from_index": from_index, "limit": limit }),
+        )
+        .await
+    }
+
+    pub async fn get_claimable(
+        &self,
+        account_id: &AccountId,
+        token: Option<&AccountId>,
+    ) -> Result<U128, ClientError> {
+        self.view(
+            "get_claimable",
+            json!({ "account_id": account_id, "token": token }),
+        )
+        .await
+    }
+
+    pub async fn get_info(&self) -> Result<ContractInfo, ClientError> {
+        self.view("get_info", json!({})).await
+    }
+
+    pub async fn get_stats(&self) -> Result<ContractStats, ClientError> {
+        self.view("get_stats", json!({})).await
+    }
+
+    // ft_transfer_call returns the amount the token kept, so the HTLC id is
+    // taken from the htlc_created event. Without it the tokens were refunded.
+    async fn create_ft_htlc(
+        &self,
+        token: &AccountId,
+        args: &CreateHTLCArgs,
+    ) -> Result<String, ClientError> {
+        let transfer = json!({
+            "receiver_id": self.contract_id,
+            "amount": args.amount,
+            "msg": serde_json::to_string(args)?,
+        });
+        let outcome = self
+            .transport
+            .call(
+                token,
+                "ft_transfer_call",
+                serde_json::to_vec(&transfer)?,
+                NearToken::from_yoctonear(1),
+                self.gas,
+            )
+            .await?;
+        outcome
+            .events()
+            .into_iter()
+            .find(|event| event.event_type == "htlc_created")
+            .map(|event| event.htlc_id)
+            .ok_or(ClientError::Rejected(outcome.logs))
+    }
+
+    // Sign deposit the contract takes with an HTLC that settles on BASE,
+    // zero while settlement is not configured
+    async fn settlement_deposit(&self) -> Result<u128, ClientError> {
+        let info = self.get_info().await?;
+        Ok(info
+            .config
+            .settlement_config
+            .map_or(0, |config| config.sign_deposit.0))
+    }
+
+    async fn view<R: DeserializeOwned>(&self, method: &str, args: Value) -> Result<R, ClientError> {
+        let result = self
+            .transport
+            .view(&self.contract_id, method, serde_json::to_vec(&args)?)
+            .await?;
+        Ok(serde_json::from_slice(&result)?)
+    }
+
+    async fn call(
+        &self,
+        method: &str,
+        args: Value,
+        deposit: NearToken,
+    ) -> Result<CallOutcome, ClientError> {
+        self.transport
+            .call(
+                &self.contract_id,
+                method,
+                serde_json::to_vec(&args)?,
+                deposit,
+                self.gas,
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    // Canned responses per method; records every call
+    #[derive(Default)]
+    struct MockTransport {
+        views: HashMap<&'static str, Value>,
+        calls: HashMap<&'static str, Result<CallOutcome, String>>,
+        sent: Mutex<Vec<(String, Value, NearToken)>>,
+    }
+
+    #[async_trait]
+    impl Transport for MockTransport {
+        async fn view(
+            &self,
+            _contract_id: &AccountId,
+            method: &str,
+            args: Vec<u8>,
+        ) -> Result<Vec<u8>, ClientError> {
+            let args = serde_json::from_slice(&args).unwrap();
+            self.sent.lock().unwrap().push((
+                method.to_string(),
+                args,
+                NearToken::from_yoctonear(0),
+            ));
+            let value = self.views.get(method).expect("unexpected view");
+            Ok(serde_json::to_vec(value).unwrap())
+        }
+
+        async fn call(
+            &self,
+            _contract_id: &AccountId,
+            method: &str,
+            args: Vec<u8>,
+            deposit: NearToken,
+            gas: Gas,
+        ) -> Result<CallOutcome, ClientError> {
+            assert_eq!(gas, DEFAULT_GAS);
+            let args = serde_json::from_slice(&args).unwrap();
+            self.sent
+                .lock()
+                .unwrap()
+                .push((method.to_string(), args, deposit));
+            self.calls
+                .get(method)
+                .expect("unexpected call")
+                .clone()
+                .map_err(ClientError::Execution)
+        }
+    }
+
+    fn client(transport: MockTransport) -> HtlcClient<MockTransport> {
+        HtlcClient::new(transport, "htlc.test.near".parse().unwrap())
+    }
+
+    fn create_args() -> CreateHTLCArgs {
+        CreateHTLCArgs {
+            receiver: "bob.near".parse().unwrap(),
+            token: None,
+            amount: U128(1_000),
+            hashlock: Base64VecU8(vec![7u8; 32]),
+            timelock: U128(2_000),
+            order_hash: Base64VecU8(vec![1u8; 32]),
+            base_escrow: None,
+        }
+    }
+
+    fn outcome(value: Value, logs: &[&str]) -> Result<CallOutcome, String> {
+        Ok(CallOutcome {
+            value: serde_json::to_vec(&value).unwrap(),
+            logs: logs.iter().map(|log| log.to_string()).collect(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_create_htlc() {
+        let mut transport = MockTransport::default();
+        transport
+            .calls
+            .insert("create_htlc", outcome(json!("htlc_1"), &[]));
+        let client = client(transport);
+
+        assert_eq!(client.create_htlc(&create_args()).await.unwrap(), "htlc_1");
+        let sent = client.transport().sent.lock().unwrap();
+        let (method, args, deposit) = &sent[0];
+        assert_eq!(method, "create_htlc");
+        assert_eq!(*deposit, NearToken::from_yoctonear(1_000));
+        assert_eq!(args["args"]["amount"], "1000");
+        assert_eq!(args["args"]["receiver"], "bob.near");
+        assert_eq!(
+            args["args"]["hashlock"],
+            "BwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwc="
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_ft_htlc() {
+        let mut transport = MockTransport::default();
+        transport.calls.insert(
+            "ft_transfer_call",
+            outcome(
+                json!("1000"),
+                &[
+                    "Transfer 1000 from alice.near to htlc.test.near",
+                    r#"EVENT_JSON:{"event_type":"htlc_created","htlc_id":"htlc_4","sender":"alice.near","receiver":"bob.near","secret":null,"amount":"1000","hashlock":null,"timelock":null,"order_hash":null,"signed_tx":null,"timestamp":"5"}"#,
+                ],
+            ),
+        );
+        let client = client(transport);
+
+        let mut args = create_args();
+        args.token = Some("usdc.near".parse().unwrap());
+        assert_eq!(client.create_htlc(&args).await.unwrap(), "htlc_4");
+        let sent = client.transport().sent.lock().unwrap();
+        let (method, transfer, deposit) = &sent[0];
+        assert_eq!(method, "ft_transfer_call");
+        assert_eq!(*deposit, NearToken::from_yoctonear(1));
+        assert_eq!(transfer["receiver_id"], "htlc.test.near");
+        assert_eq!(transfer["amount"], "1000");
+        let msg: CreateHTLCArgs = serde_json::from_str(transfer["msg"].as_str().unwrap()).unwrap();
+        assert_eq!(msg.token.unwrap().as_str(), "usdc.near");
+    }
+
+    #[tokio::test]
+    async fn test_create_ft_htlc_refunded() {
+        let mut transport = MockTransport::default();
+        transport.calls.insert(
+            "ft_transfer_call",
+            outcome(json!("0"), &["Resolver is not an active registered solver"]),
+        );
+        let mut args = create_args();
+        args.token = Some("usdc.near".parse().unwrap());
+
+        match client(transport).create_htlc(&args).await {
+            Err(ClientError::Rejected(logs)) => {
+                assert_eq!(logs, vec!["Resolver is not an active registered solver"])
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_htlc_prepays_settlement() {
+        let mut transport = MockTransport::default();
+        transport.views.insert(
+            "get_info",
+            json!({
+                "owner": "owner.near",
+                "version": "2.0.0",
+                "total_htlcs": 0,
+                "active_htlcs": 0,
+                "config": {
+                    "solver_registry": null,
+                    "resolver_exclusive": false,
+                    "settlement_config": {
+                        "mpc_signer": "v1.signer-prod.testnet",
+                        "path": "base-1",
+                        "key_version": 0,
+                        "chain_id": 84532,
+                        "gas_limit": 150000,
+                        "max_fee_per_gas": "1500000000",
+                        "max_priority_fee_per_gas": "1000000",
+                        "sign_deposit": "250",
+                        "retry_interval": 60000000000u64,
+                    },
+                },
+            }),
+        );
+        transport
+            .calls
+            .insert("create_htlc", outcome(json!("htlc_1"), &[]));
+        let client = client(transport);
+
+        let mut args = create_args();
+        args.base_escrow = Some(BaseEscrow {
+            escrow: format!("0x{}", "11".repeat(20)),
+            maker: format!("0x{}", "10".repeat(20)),
+            taker: format!("0x{}", "20".repeat(20)),
+            token: format!("0x{}", "00".repeat(20)),
+            amount: U128(1),
+            safety_deposit: U128(0),
+            timelocks: "00".repeat(32),
+        });
+        assert_eq!(client.create_htlc(&args).await.unwrap(), "htlc_1");
+        let sent = client.transport().sent.lock().unwrap();
+        assert_eq!(sent[1].0, "create_htlc");
+        assert_eq!(sent[1].2, NearToken::from_yoctonear(1_250));
+    }
+
+    #[tokio::test]
+    async fn test_create_htlc_rejected_by_registry() {
+        let mut transport = MockTransport::default();
+        transport.calls.insert(
+            "create_htlc",
+            outcome(
+                Value::Null,
+                &["Resolver is not an active registered solver"],
+            ),
+        );
+
+        match client(transport).create_htlc(&create_args()).await {
+            Err(ClientError::Rejected(logs)) => {
+                assert_eq!(logs, vec!["Resolver is not an active registered solver"])
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_withdraw_events() {
+        let mut transport = MockTransport::default();
+        transport.calls.insert(
+            "withdraw",
+            outcome(
+                Value::Null,
+                &[
+                    r#"EVENT_JSON:{"event_type":"secret_revealed","htlc_id":"htlc_1","sender":null,"receiver":null,"secret":"AQID","amount":null,"hashlock":null,"timelock":null,"order_hash":null,"signed_tx":null,"timestamp":"5"}"#,
+                    "unrelated log",
+                ],
+            ),
+        );
+        let client = client(transport);
+
+        let outcome = client.withdraw("htlc_1", &[1, 2, 3]).await.unwrap();
+        let events = outcome.events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, "secret_revealed");
+        assert_eq!(events[0].secret.as_ref().unwrap().0, vec![1, 2, 3]);
+
+        let sent = client.transport().sent.lock().unwrap();
+        assert_eq!(sent[0].1, json!({ "htlc_id": "htlc_1", "secret": "AQID" }));
+        assert_eq!(sent[0].2, NearToken::from_yoctonear(0));
+    }
+
+    #[tokio::test]
+    async fn test_failed_call() {
+        let mut transport = MockTransport::default();
+        transport
+            .calls
+            .insert("refund", Err("Timelock not expired".to_string()));
+
+        match client(transport).refund("htlc_1").await {
+            Err(ClientError::Execution(message)) => assert_eq!(message, "Timelock not expired"),
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_views() {
+        let htlc = json!({
+            "htlc_id": "htlc_1",
+            "sender": "alice.near",
+            "receiver": "bob.near",
+            "token": null,
+            "amount": "1000",
+            "hashlock": "AQID",
+            "timelock": "2000",
+            "order_hash": "AQID",
+            "withdrawn": false,
+            "refunded": false,
+            "created_at": "1000",
+            "base_escrow": null,
+        });
+        let mut transport = MockTransport::default();
+        transport.views.insert("get_htlc", Value::Null);
+        transport
+            .views
+            .insert("get_active_htlcs", json!([htlc.clone()]));
+        let client = client(transport);
+
+        assert!(client.get_htlc("htlc_9").await.unwrap().is_none());
+        let active = client.get_active_htlcs(0, 10).await.unwrap();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].htlc_id, "htlc_1");
+        assert_eq!(active[0].amount, U128(1_000));
+        assert_eq!(
+            client.transport().sent.lock().unwrap()[1].1,
+            json!({ "from_index": 0, "limit": 10 })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_info_and_stats() {
+        let mut transport = MockTransport::default();
+        transport.views.insert(
+            "get_info",
+            json!({
+                "owner": "owner.near",
+                "version": "2.0.0",
+                "total_htlcs": 3,
+                "active_htlcs": 1,
+                "config": {
+                    "solver_registry": "registry.near",
+                    "resolver_exclusive": true,
+                    "settlement_config": null,
+                },
+            }),
+        );
+        transport.views.insert(
+            "get_stats",
+            json!({
+                "version": "2.0.0",
+                "total_htlcs": 3,
+                "active_htlcs": 1,
+                "withdrawn_htlcs": 1,
+                "refunded_htlcs": 1,
+                "locked": [{ "token": null, "amount": "1000" }],
+            }),
+        );
+        let client = client(transport);
+
+        let info = client.get_info().await.unwrap();
+        assert_eq!(info.owner.as_str(), "owner.near");
+        assert_eq!(info.version, "2.0.0");
+        assert!(info.config.resolver_exclusive);
+        assert_eq!(
+            info.config.solver_registry.unwrap().as_str(),
+            "registry.near"
+        );
+
+        let stats = client.get_stats().await.unwrap();
+        assert_eq!((stats.withdrawn_htlcs, stats.refunded_htlcs), (1, 1));
+        assert_eq!(stats.locked[0].token, None);
+        assert_eq!(stats.locked[0].amount, U128(1_000));
+    }
+}
@@ -0,0 +1,97 @@
+// How the client reaches the chain. Arguments and results are raw JSON bytes,
+// so a transport only has to move them; the typed layer lives in HtlcClient.
+
+use async_trait::async_trait;
+use near_sdk::serde::de::DeserializeOwned;
+use near_sdk::{AccountId, Gas, NearToken};
+
+use crate::{ClientError, EventLog};
+
+const EVENT_PREFIX: &str = "EVENT_JSON:";
+
+// Result of a function call transaction once all its receipts have executed
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CallOutcome {
+    // Return value of the last receipt in the call chain
+    pub value: Vec<u8>,
+    pub logs: Vec<String>,
+}
+
+impl CallOutcome {
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, ClientError> {
+        Ok(serde_json::from_slice(&self.value)?)
+    }
+
+    // Events emitted by the HTLC contract, in log order
+    pub fn events(&self) -> Vec<EventLog> {
+        self.logs
+            .iter()
+            .filter_map(|log| log.strip_prefix(EVENT_PREFIX))
+            .filter_map(|json| serde_json::from_str(json).ok())
+            .collect()
+    }
+}
+
+#[async_trait]
+pub trait Transport: Send + Sync {
+    async fn view(
+        &self,
+        contract_id: &AccountId,
+        method: &str,
+        args: Vec<u8>,
+    ) -> Result<Vec<u8>, ClientError>;
+
+    // Signs and submits a call, waiting for the final outcome. A failed
+    // receipt in the call chain is returned as ClientError::Execution.
+    async fn call(
+        &self,
+        contract_id: &AccountId,
+        method: &str,
+        args: Vec<u8>,
+        deposit: NearToken,
+        gas: Gas,
+    ) -> Result<CallOutcome, ClientError>;
+}
+
+// Calls are signed by the account, which may live on a sandbox worker or on
+// a network reached through near_workspaces::custom(rpc_url)
+#[cfg(feature = "workspaces")]
+#[async_trait]
+impl Transport for near_workspaces::Account {
+    async fn view(
+        &self,
+        contract_id: &AccountId,
+        method: &str,
+        args: Vec<u8>,
+    ) -> Result<Vec<u8>, ClientError> {
+        let result = near_workspaces::Account::view(self, contract_id, method)
+            .args(args)
+            .await
+            .map_err(|err| ClientError::Transport(err.to_string()))?;
+        Ok(result.result)
+    }
+
+    async fn call(
+        &self,
+        contract_id: &AccountId,
+        method: &str,
+        args: Vec<u8>,
+        deposit: NearToken,
+        gas: Gas,
+    ) -> Result<CallOutcome, ClientError> {
+        let result = near_workspaces::Account::call(self, contract_id, method)
+            .args(args)
+            .deposit(deposit)
+            .gas(gas)
+            .transact()
+            .await
+            .map_err(|err| ClientError::Transport(err.to_string()))?;
+        let logs = result.logs().into_iter().map(String::from).collect();
+        let value = result
+            .into_result()
+            .map_err(|err| ClientError::Execution(err.to_string()))?
+            .raw_bytes()
+            .map_err(|err| ClientError::Transport(err.to_string()))?;
+        Ok(CallOutcome { value, logs })
+    }
+}
@@ -0,0 +1,146 @@
+// Helpers behind htlc-cli: secrets, hashlocks and event decoding for working
+// with FusionPlusHTLC by hand.
+
+use fusion_plus_htlc::EventLog;
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+
+pub mod merkle;
+
+pub use merkle::MerkleTree;
+
+const EVENT_PREFIX: &str = "EVENT_JSON:";
+
+// Hashes a swap leg may lock with. The NEAR contract and the EVM escrows
+// use Keccak-256; Bitcoin scripts use OP_SHA256 or OP_HASH160 (see
+// chain_signatures::Hashlock).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    Keccak256,
+    Sha256,
+    Hash160,
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 3] = [Self::Keccak256, Self::Sha256, Self::Hash160];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Keccak256 => "keccak256",
+            Self::Sha256 => "sha256",
+            Self::Hash160 => "hash160",
+        }
+    }
+
+    pub fn hashlock(&self, secret: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Keccak256 => Keccak256::digest(secret).to_vec(),
+            Self::Sha256 => Sha256::digest(secret).to_vec(),
+            Self::Hash160 => chain_signatures::address::hash160(secret).to_vec(),
+        }
+    }
+}
+
+impl std::str::FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.name() == name)
+            .ok_or_else(|| format!("unknown algorithm {name} (keccak256, sha256 or hash160)"))
+    }
+}
+
+// 32 random bytes from the OS
+pub fn generate_secret() -> Result<[u8; 32], String> {
+    let mut secret = [0u8; 32];
+    getrandom::getrandom(&mut secret).map_err(|err| format!("no randomness: {err}"))?;
+    Ok(secret)
+}
+
+// Parses one log line. None for logs that are not HTLC events.
+pub fn decode_event(log: &str) -> Option<EventLog> {
+    let json = log.trim().strip_prefix(EVENT_PREFIX)?;
+    serde_json::from_str(json).ok()
+}
+
+// Event fields one per line, binary ones in hex. Unset fields are left out.
+pub fn format_event(event: &EventLog) -> String {
+    let mut fields = vec![("htlc_id", event.htlc_id.clone())];
+    let mut push = |name, value: Option<String>| {
+        if let Some(value) = value {
+            fields.push((name, value));
+        }
+    };
+    push("sender", event.sender.as_ref().map(|id| id.to_string()));
+    push("receiver", event.receiver.as_ref().map(|id| id.to_string()));
+    push("amount", event.amount.map(|amount| amount.0.to_string()));
+    push("token", event.token.as_ref().map(|id| id.to_string()));
+    push("secret", event.secret.as_ref().map(|s| hex_bytes(&s.0)));
+    push("hashlock", event.hashlock.as_ref().map(|h| hex_bytes(&h.0)));
+    push("timelock", event.timelock.map(|t| t.0.to_string()));
+    push(
+        "order_hash",
+        event.order_hash.as_ref().map(|h| hex_bytes(&h.0)),
+    );
+    push("signed_tx", event.signed_tx.clone());
+    push("timestamp", Some(event.timestamp.0.to_string()));
+
+    let mut out = event.event_type.clone();
+    for (name, value) in fields {
+        out.push_str(&format!("\n  {name:<11}{value}"));
+    }
+    out
+}
+
+pub fn hex_bytes(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+// Accepts hex with or without 0x
+pub fn parse_hex(value: &str) -> Result<Vec<u8>, String> {
+    hex::decode(value.trim_start_matches("0x")).map_err(|err| format!("invalid hex {value}: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hashlocks() {
+        // Known digests of "abc"
+        assert_eq!(
+            hex::encode(Algorithm::Keccak256.hashlock(b"abc")),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+        assert_eq!(
+            hex::encode(Algorithm::Sha256.hashlock(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hex::encode(Algorithm::Hash160.hashlock(b"abc")),
+            "bb1be98c142444d7a56aa3981c3942a978e4dc33"
+        );
+        assert_eq!("sha256".parse::<Algorithm>(), Ok(Algorithm::Sha256));
+        assert!("md5".parse::<Algorithm>().is_err());
+    }
+
+    #[test]
+    fn test_generate_secret() {
+        assert_ne!(generate_secret().unwrap(), generate_secret().unwrap());
+    }
+
+    #[test]
+    fn test_decode_event() {
+        let log = r#"EVENT_JSON:{"event_type":"secret_revealed","htlc_id":"htlc_1","sender":null,"receiver":null,"secret":"AQID","amount":null,"hashlock":null,"timelock":null,"order_hash":null,"signed_tx":null,"timestamp":"5"}"#;
+        let event = decode_event(log).unwrap();
+        assert_eq!(
+            format_event(&event),
+            "secret_revealed\n  htlc_id    htlc_1\n  secret     0x010203\n  timestamp  5"
+        );
+
+        assert!(decode_event("Transferred 1 NEAR").is_none());
+        assert!(decode_event("EVENT_JSON:{\"standard\":\"nep141\"}").is_none());
+    }
+}
@@ -0,0 +1,291 @@
+// htlc-cli: generate secrets and hashlocks, build create_htlc arguments and
+// read HTLC events.
+//
+//   htlc-cli secret [--parts <n>]
+//   htlc-cli hashlock <secret hex> [--algorithm <keccak256|sha256|hash160>]
+//   htlc-cli create-args --receiver <account> --amount <units> --hashlock <hex>
+//            --order-hash <hex> (--timelock <ns> | --expires-in <secs>)
+//            [--token <account> --contract <htlc account>]
+//   htlc-cli decode-event [<log>...]
+//
+// secret --parts prints a Merkle set of parts + 1 secrets with their proofs;
+// its `hashlock` (the root tagged with the secret count) locks the order.
+// Binary values are printed both as 0x hex (EVM, Bitcoin tooling) and base64
+// (the NEAR contract's Base64VecU8 arguments). With --token, create-args
+// prints ft_transfer_call arguments for the token contract, since NEP-141
+// HTLCs are created by locking the tokens. decode-event reads log lines from
+// stdin when none are given.
+
+use std::io::BufRead;
+use std::process::ExitCode;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use fusion_plus_htlc::CreateHTLCArgs;
+use htlc_cli::{
+    decode_event, format_event, generate_secret, hex_bytes, parse_hex, Algorithm, MerkleTree,
+};
+use near_sdk::json_types::{Base64VecU8, U128};
+use serde_json::{json, Value};
+
+const USAGE: &str = "usage: htlc-cli <secret | hashlock | create-args | decode-event> [options]";
+
+fn main() -> ExitCode {
+    match run(std::env::args().skip(1).collect()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: Vec<String>) -> Result<(), String> {
+    let (command, args) = args.split_first().ok_or(USAGE)?;
+    let mut options = Options::parse(args)?;
+    match command.as_str() {
+        "secret" => secret(&mut options),
+        "hashlock" => hashlock(&mut options),
+        "create-args" => create_args(&mut options),
+        "decode-event" => decode(&mut options),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn secret(options: &mut Options) -> Result<(), String> {
+    let parts = options.take("--parts")?;
+    options.finish(0)?;
+
+    let output = match parts {
+        None => {
+            let secret = generate_secret()?;
+            json!({
+                "secret": encoded(&secret),
+                "hashlocks": hashlocks(&secret, &Algorithm::ALL),
+            })
+        }
+        Some(parts) => {
+            // The SDK takes a Merkle set only for three or more secrets
+            let parts: usize = parts
+                .parse()
+                .ok()
+                .filter(|parts| *parts > 1)
+                .ok_or("--parts must be at least 2; a single fill uses one secret")?;
+            let secrets = (0..=parts)
+                .map(|_| generate_secret())
+                .collect::<Result<Vec<_>, _>>()?;
+            let tree = MerkleTree::from_secrets(&secrets);
+            let entries: Vec<Value> = secrets
+                .iter()
+                .enumerate()
+                .map(|(index, secret)| {
+                    json!({
+                        "index": index,
+                        "secret": encoded(secret),
+                        "hashlock": encoded(&Algorithm::Keccak256.hashlock(secret)),
+                        "leaf": hex_bytes(&tree.leaf(index)),
+                        "proof": tree
+                            .proof(index)
+                            .iter()
+                            .map(|node| hex_bytes(node))
+                            .collect::<Vec<_>>(),
+                    })
+                })
+                .collect();
+            json!({
+                "parts": parts,
+                "root": encoded(&tree.root()),
+                "hashlock": encoded(&tree.hashlock()),
+                "secrets": entries,
+            })
+        }
+    };
+    print_json(&output)
+}
+
+fn hashlock(options: &mut Options) -> Result<(), String> {
+    let algorithms = match options.take("--algorithm")? {
+        Some(name) => vec![name.parse()?],
+        None => Algorithm::ALL.to_vec(),
+    };
+    let secret = parse_hex(&options.finish(1)?[0])?;
+    print_json(&hashlocks(&secret, &algorithms))
+}
+
+fn create_args(options: &mut Options) -> Result<(), String> {
+    let receiver = options.require("--receiver")?;
+    let amount = options.require("--amount")?;
+    let hashlock = parse_hex(&options.require("--hashlock")?)?;
+    let order_hash = parse_hex(&options.require("--order-hash")?)?;
+    let token = options.take("--token")?;
+    let contract = options.take("--contract")?;
+    let timelock = match (options.take("--timelock")?, options.take("--expires-in")?) {
+        (Some(timelock), None) => timelock
+            .parse::<u128>()
+            .map_err(|err| format!("invalid --timelock: {err}"))?,
+        (None, Some(secs)) => {
+            let secs = secs
+                .parse()
+                .map_err(|err| format!("invalid --expires-in: {err}"))?;
+            let expiry = SystemTime::now() + Duration::from_secs(secs);
+            expiry
+                .duration_since(UNIX_EPOCH)
+                .expect("clock before 1970")
+                .as_nanos()
+        }
+        _ => return Err("give exactly one of --timelock and --expires-in".to_string()),
+    };
+    options.finish(0)?;
+
+    // The contract rejects anything else
+    if hashlock.len() != 32 {
+        return Err("hashlock must be 32 bytes".to_string());
+    }
+    if order_hash.len() != 32 {
+        return Err("order hash must be 32 bytes".to_string());
+    }
+
+    let args = CreateHTLCArgs {
+        receiver: receiver
+            .parse()
+            .map_err(|err| format!("invalid --receiver: {err}"))?,
+        token: token
+            .map(|token| token.parse())
+            .transpose()
+            .map_err(|err| format!("invalid --token: {err}"))?,
+        amount: U128(
+            amount
+                .parse()
+                .map_err(|err| format!("invalid --amount: {err}"))?,
+        ),
+        hashlock: Base64VecU8(hashlock),
+        timelock: U128(timelock),
+        order_hash: Base64VecU8(order_hash),
+        base_escrow: None,
+    };
+    let Some(token) = &args.token else {
+        if contract.is_some() {
+            return Err("--contract is only used with --token".to_string());
+        }
+        eprintln!("attach {} yoctoNEAR as deposit", args.amount.0);
+        println!("{}", json!({ "args": args }));
+        return Ok(());
+    };
+    let contract = contract.ok_or("--token needs --contract, the HTLC contract account")?;
+    eprintln!("call ft_transfer_call on {token} attaching 1 yoctoNEAR");
+    println!(
+        "{}",
+        json!({
+            "receiver_id": contract,
+            "amount": args.amount,
+            "msg": serde_json::to_string(&args).map_err(|err| err.to_string())?,
+        })
+    );
+    Ok(())
+}
+
+fn decode(options: &mut Options) -> Result<(), String> {
+    let mut logs = options.finish_any()?;
+    if logs.is_empty() {
+        logs = std::io::stdin()
+            .lock()
+            .lines()
+            .collect::<Result<_, _>>()
+            .map_err(|err| err.to_string())?;
+    }
+
+    let events: Vec<String> = logs
+        .iter()
+        .filter_map(|log| decode_event(log))
+        .map(|event| format_event(&event))
+        .collect();
+    if events.is_empty() {
+        return Err("no HTLC events found".to_string());
+    }
+    println!("{}", events.join("\n\n"));
+    Ok(())
+}
+
+fn encoded(bytes: &[u8]) -> Value {
+    json!({
+        "hex": hex_bytes(bytes),
+        "base64": Base64VecU8(bytes.to_vec()),
+    })
+}
+
+fn hashlocks(secret: &[u8], algorithms: &[Algorithm]) -> Value {
+    algorithms
+        .iter()
+        .map(|algorithm| {
+            (
+                algorithm.name().to_string(),
+                encoded(&algorithm.hashlock(secret)),
+            )
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+fn print_json(value: &Value) -> Result<(), String> {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).map_err(|err| err.to_string())?
+    );
+    Ok(())
+}
+
+// --flag value pairs plus positional arguments
+struct Options {
+    flags: Vec<(String, String)>,
+    positional: Vec<String>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut flags = Vec::new();
+        let mut positional = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg.starts_with("--") {
+                let value = args.next().ok_or_else(|| format!("{arg} needs a value"))?;
+                flags.push((arg.clone(), value.clone()));
+            } else {
+                positional.push(arg.clone());
+            }
+        }
+        Ok(Self { flags, positional })
+    }
+
+    fn take(&mut self, flag: &str) -> Result<Option<String>, String> {
+        let mut values = Vec::new();
+        self.flags.retain(|(name, value)| {
+            let matches = name == flag;
+            if matches {
+                values.push(value.clone());
+            }
+            !matches
+        });
+        if values.len() > 1 {
+            return Err(format!("{flag} given more than once"));
+        }
+        Ok(values.pop())
+    }
+
+    fn require(&mut self, flag: &str) -> Result<String, String> {
+        self.take(flag)?.ok_or_else(|| format!("missing {flag}"))
+    }
+
+    // Rejects leftover flags and expects exactly `count` positional arguments
+    fn finish(&mut self, count: usize) -> Result<Vec<String>, String> {
+        if self.positional.len() != count {
+            return Err(USAGE.to_string());
+        }
+        self.finish_any()
+    }
+
+    fn finish_any(&mut self) -> Result<Vec<String>, String> {
+        match self.flags.first() {
+            Some((flag, _)) => Err(format!("unknown option {flag}")),
+            None => Ok(std::mem::take(&mut self.positional)),
+        }
+    }
+}
@@ -0,0 +1,176 @@
+// Secret sets for partially fillable orders, as in 1inch Fusion+.
+//
+// An order split into N parts gets N + 1 secrets: fill i reveals secret i,
+// and the extra one is used for the fill that completes the order. Leaf i is
+// keccak256(uint64 i big endian ++ keccak256(secret i)). The tree is
+// OpenZeppelin's SimpleMerkleTree, as built by the 1inch cross-chain SDK:
+// leaves sorted, inner nodes hashing their children in sorted order, so
+// proofs verify with OpenZeppelin's MerkleProof. The order's hashlock is the
+// root with its top 16 bits replaced by the number of secrets minus one.
+
+use sha3::{Digest, Keccak256};
+
+use crate::Algorithm;
+
+pub struct MerkleTree {
+    // tree[0] is the root and the children of node i are 2i + 1 and 2i + 2;
+    // the sorted leaves fill the end of the array in reverse
+    tree: Vec<[u8; 32]>,
+    // Position in `tree` of each secret's leaf
+    positions: Vec<usize>,
+}
+
+impl MerkleTree {
+    pub fn from_secrets(secrets: &[[u8; 32]]) -> Self {
+        assert!(!secrets.is_empty(), "No secrets");
+        assert!(secrets.len() <= 1 << 16, "Too many secrets");
+        let leaves: Vec<[u8; 32]> = secrets
+            .iter()
+            .enumerate()
+            .map(|(index, secret)| leaf(index as u64, &Algorithm::Keccak256.hashlock(secret)))
+            .collect();
+
+        let mut sorted: Vec<usize> = (0..leaves.len()).collect();
+        sorted.sort_by_key(|&index| leaves[index]);
+        let mut tree = vec![[0u8; 32]; 2 * leaves.len() - 1];
+        let mut positions = vec![0; leaves.len()];
+        for (rank, index) in sorted.into_iter().enumerate() {
+            positions[index] = tree.len() - 1 - rank;
+            tree[positions[index]] = leaves[index];
+        }
+        for node in (0..tree.len() - leaves.len()).rev() {
+            tree[node] = hash_pair(&tree[2 * node + 1], &tree[2 * node + 2]);
+        }
+        Self { tree, positions }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.tree[0]
+    }
+
+    // The root tagged with the secret count, as HashLock.forMultipleFills
+    pub fn hashlock(&self) -> [u8; 32] {
+        let mut hashlock = self.root();
+        let count = (self.positions.len() - 1) as u16;
+        hashlock[..2].copy_from_slice(&count.to_be_bytes());
+        hashlock
+    }
+
+    pub fn leaf(&self, index: usize) -> [u8; 32] {
+        self.tree[self.positions[index]]
+    }
+
+    // Sibling hashes from the leaf up to the root
+    pub fn proof(&self, index: usize) -> Vec<[u8; 32]> {
+        let mut proof = Vec::new();
+        let mut node = self.positions[index];
+        while node > 0 {
+            let sibling = if node % 2 == 1 { node + 1 } else { node - 1 };
+            proof.push(self.tree[sibling]);
+            node = (node - 1) / 2;
+        }
+        proof
+    }
+}
+
+pub fn leaf(index: u64, secret_hash: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(index.to_be_bytes());
+    hasher.update(secret_hash);
+    hasher.finalize().into()
+}
+
+pub fn verify(root: &[u8; 32], leaf: &[u8; 32], proof: &[[u8; 32]]) -> bool {
+    let computed = proof
+        .iter()
+        .fold(*leaf, |node, sibling| hash_pair(&node, sibling));
+    &computed == root
+}
+
+fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    let mut hasher = Keccak256::new();
+    hasher.update(first);
+    hasher.update(second);
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secrets(count: u8) -> Vec<[u8; 32]> {
+        (0..count).map(|i| [i; 32]).collect()
+    }
+
+    #[test]
+    fn test_every_proof_verifies() {
+        for count in 1..=9 {
+            let tree = MerkleTree::from_secrets(&secrets(count));
+            for index in 0..count as usize {
+                assert!(
+                    verify(&tree.root(), &tree.leaf(index), &tree.proof(index)),
+                    "leaf {index} of {count}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_proof_is_bound_to_index() {
+        let secrets = secrets(4);
+        let tree = MerkleTree::from_secrets(&secrets);
+        let secret_hash = Algorithm::Keccak256.hashlock(&secrets[1]);
+
+        assert!(verify(&tree.root(), &leaf(1, &secret_hash), &tree.proof(1)));
+        // The right secret claimed for another part fails
+        assert!(!verify(
+            &tree.root(),
+            &leaf(2, &secret_hash),
+            &tree.proof(1)
+        ));
+    }
+
+    // Computed with an independent Python port of OpenZeppelin's
+    // SimpleMerkleTree and the SDK's HashLock.forMultipleFills; neither
+    // package could be run here
+    #[test]
+    fn test_sdk_vector() {
+        let tree = MerkleTree::from_secrets(&secrets(5));
+        assert_eq!(
+            hex::encode(tree.root()),
+            "fa2668390ee61195985031052cedab8cfaffe586c25c73abde54873bdac3f722"
+        );
+        assert_eq!(
+            hex::encode(tree.hashlock()),
+            "000468390ee61195985031052cedab8cfaffe586c25c73abde54873bdac3f722"
+        );
+        assert_eq!(
+            hex::encode(tree.leaf(1)),
+            "aa66fb03b4427eb51f9c7cb8a6d059140b8c48ddc89bfff2d57158b6a36f0e46"
+        );
+        let proof: Vec<_> = tree.proof(1).iter().map(hex::encode).collect();
+        assert_eq!(
+            proof,
+            [
+                "c2e4bea7d7dc97c237e4125e2ae1d50bd35fdc01b2fe49f80ebb593c2e0f4199",
+                "693e4ebf3a725b8605ea78e1f31c7b4aff892bb43a9693d93299982ee5eaee03",
+            ]
+        );
+        let proof: Vec<_> = tree.proof(4).iter().map(hex::encode).collect();
+        assert_eq!(
+            proof,
+            [
+                "9e64881148a76fae050a30f9f306279f53c16e06b01a4ac4e1e1efc6dfd1df28",
+                "7f004798723f58132ad3ac41071435b120091f69d326801b36cd5f33d6bd729d",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_single_secret_tree() {
+        let tree = MerkleTree::from_secrets(&secrets(1));
+        assert_eq!(tree.root(), tree.leaf(0));
+        assert!(tree.proof(0).is_empty());
+    }
+}
@@ -0,0 +1,297 @@
+// Off-chain indexer for FusionPlusHTLC events.
+//
+// Reads receipt outcomes block by block, picks out the EVENT_JSON logs the
+// HTLC contract emits, and folds them into one row per HTLC in SQLite. Only
+// successful receipts executed by a watched contract count: anyone can log a
+// string that looks like an event, and a failed receipt's logs survive even
+// though its state changes were rolled back.
+//
+// Each block is committed in one transaction together with the cursor, so an
+// interrupted run resumes cleanly and replaying the same blocks is a no-op.
+
+use near_sdk::AccountId;
+
+use fusion_plus_htlc::EventLog;
+
+pub mod source;
+pub mod store;
+
+pub use source::{
+    BlockOutcomes, BlockSource, ExecutionOutcome, ExecutionOutcomeWithId, ExecutionStatus,
+    FixtureSource, JsonLinesSource,
+};
+pub use store::{HtlcRecord, HtlcStatus, Store};
+
+use store::{HtlcUpdate, NewEvent};
+
+const EVENT_PREFIX: &str = "EVENT_JSON:";
+
+#[derive(Debug, thiserror::Error)]
+pub enum IndexerError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("database error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("bad block source: {0}")]
+    Source(String),
+    #[error("unknown HTLC status: {0}")]
+    InvalidStatus(String),
+}
+
+pub struct Indexer {
+    store: Store,
+    contracts: Vec<AccountId>,
+}
+
+impl Indexer {
+    pub fn new(store: Store, contracts: Vec<AccountId>) -> Self {
+        Self { store, contracts }
+    }
+
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    // Drains the source. Returns the number of blocks indexed; blocks at or
+    // below the stored cursor are skipped.
+    pub fn run(&mut self, source: &mut impl BlockSource) -> Result<u64, IndexerError> {
+        let mut indexed = 0;
+        while let Some(block) = source.next_block()? {
+            if self.process_block(&block)? {
+                indexed += 1;
+            }
+        }
+        Ok(indexed)
+    }
+
+    pub fn process_block(&mut self, block: &BlockOutcomes) -> Result<bool, IndexerError> {
+        if self
+            .store
+            .last_block_height()?
+            .is_some_and(|height| block.block_height <= height)
+        {
+            return Ok(false);
+        }
+
+        let tx = self.store.transaction()?;
+        for receipt in &block.receipt_outcomes {
+            let outcome = &receipt.outcome;
+            if !outcome.status.is_success() || !self.contracts.contains(&outcome.executor_id) {
+                continue;
+            }
+
+            for (log_index, log) in outcome.logs.iter().enumerate() {
+                let Some(data) = log.strip_prefix(EVENT_PREFIX) else {
+                    continue;
+                };
+                // Plain logs from the same contract may happen to share the
+                // prefix; they are not events we understand
+                let Ok(event) = serde_json::from_str::<EventLog>(data) else {
+                    continue;
+                };
+
+                let inserted = store::insert_event(
+                    &tx,
+                    &NewEvent {
+                        receipt_id: &receipt.id,
+                        log_index,
+                        block_height: block.block_height,
+                        contract_id: outcome.executor_id.as_str(),
+                        htlc_id: &event.htlc_id,
+                        event_type: &event.event_type,
+                        data,
+                    },
+                )?;
+                if !inserted {
+                    continue;
+                }
+                if let Some(update) = lifecycle_update(&event) {
+                    store::apply_update(&tx, outcome.executor_id.as_str(), &event.htlc_id, update)?;
+                }
+            }
+        }
+        store::set_last_block_height(&tx, block.block_height)?;
+        tx.commit()?;
+        Ok(true)
+    }
+}
+
+// Auction order events carry no HTLC and are only kept in the event log
+fn lifecycle_update(event: &EventLog) -> Option<HtlcUpdate> {
+    let timestamp = event.timestamp.0 as u64;
+    let account = |id: &Option<AccountId>| id.as_ref().map(|id| id.to_string());
+    let amount = event.amount.map(|amount| amount.0.to_string());
+
+    match event.event_type.as_str() {
+        "htlc_created" => Some(HtlcUpdate::Created {
+            sender: account(&event.sender),
+            receiver: account(&event.receiver),
+            amount,
+            hashlock: event.hashlock.as_ref().map(|h| hex::encode(&h.0)),
+            timelock: event.timelock.map(|t| t.0 as u64),
+            order_hash: event.order_hash.as_ref().map(|h| hex::encode(&h.0)),
+            created_at: timestamp,
+        }),
+        "secret_revealed" => Some(HtlcUpdate::SecretRevealed {
+            secret: hex::encode(&event.secret.as_ref()?.0),
+        }),
+        "htlc_withdrawn" | "htlc_refunded" => Some(HtlcUpdate::Finalized {
+            status: if event.event_type == "htlc_withdrawn" {
+                HtlcStatus::Withdrawn
+            } else {
+                HtlcStatus::Refunded
+            },
+            sender: account(&event.sender),
+            receiver: account(&event.receiver),
+            amount,
+            finalized_at: timestamp,
+        }),
+        "settlement_signed" => Some(HtlcUpdate::SettlementSigned {
+            signed_tx: event.signed_tx.clone()?,
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn htlc_contract() -> AccountId {
+        "htlc.testnet".parse().unwrap()
+    }
+
+    // Full EventLog with the given fields set
+    fn event_log(event_type: &str, htlc_id: &str, fields: Value) -> String {
+        let mut event = json!({
+            "event_type": event_type,
+            "htlc_id": htlc_id,
+            "sender": null,
+            "receiver": null,
+            "secret": null,
+            "amount": null,
+            "hashlock": null,
+            "timelock": null,
+            "order_hash": null,
+            "signed_tx": null,
+            "timestamp": "7",
+        });
+        for (key, value) in fields.as_object().unwrap() {
+            event[key] = value.clone();
+        }
+        format!("{EVENT_PREFIX}{event}")
+    }
+
+    fn block(
+        height: u64,
+        receipts: Vec<(&str, AccountId, ExecutionStatus, Vec<String>)>,
+    ) -> BlockOutcomes {
+        BlockOutcomes {
+            block_height: height,
+            block_hash: format!("hash{height}"),
+            block_timestamp: height * 1_000,
+            receipt_outcomes: receipts
+                .into_iter()
+                .map(|(id, executor_id, status, logs)| ExecutionOutcomeWithId {
+                    id: id.to_string(),
+                    outcome: ExecutionOutcome {
+                        executor_id,
+                        logs,
+                        status,
+                    },
+                })
+                .collect(),
+        }
+    }
+
+    fn success() -> ExecutionStatus {
+        ExecutionStatus::SuccessValue(String::new())
+    }
+
+    fn indexer() -> Indexer {
+        Indexer::new(Store::open_in_memory().unwrap(), vec![htlc_contract()])
+    }
+
+    #[test]
+    fn test_secret_before_creation_is_kept() {
+        // Indexing may start mid-lifecycle
+        let mut indexer = indexer();
+        let logs = vec![
+            event_log("secret_revealed", "htlc_3", json!({ "secret": "AQID" })),
+            "EVENT_JSON:not an event".to_string(),
+        ];
+        assert!(indexer
+            .process_block(&block(5, vec![("r1", htlc_contract(), success(), logs)]))
+            .unwrap());
+
+        let record = indexer
+            .store()
+            .htlc("htlc.testnet", "htlc_3")
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.status, HtlcStatus::Active);
+        assert_eq!(record.secret.as_deref(), Some("010203"));
+        assert_eq!(record.sender, None);
+        assert_eq!(indexer.store().event_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_ignores_foreign_and_failed_receipts() {
+        let mut indexer = indexer();
+        let logs = vec![event_log("htlc_refunded", "htlc_1", json!({}))];
+        indexer
+            .process_block(&block(
+                1,
+                vec![
+                    (
+                        "r1",
+                        "mallory.testnet".parse().unwrap(),
+                        success(),
+                        logs.clone(),
+                    ),
+                    (
+                        "r2",
+                        htlc_contract(),
+                        ExecutionStatus::Failure(json!({})),
+                        logs,
+                    ),
+                ],
+            ))
+            .unwrap();
+
+        assert_eq!(indexer.store().event_count().unwrap(), 0);
+        assert!(indexer
+            .store()
+            .htlc("htlc.testnet", "htlc_1")
+            .unwrap()
+            .is_none());
+        assert_eq!(indexer.store().last_block_height().unwrap(), Some(1));
+    }
+
+    #[test]
+    fn test_skips_blocks_below_cursor() {
+        let mut indexer = indexer();
+        let logs = vec![event_log("htlc_created", "htlc_1", json!({}))];
+        let created = block(3, vec![("r1", htlc_contract(), success(), logs)]);
+
+        assert!(indexer.process_block(&created).unwrap());
+        assert!(!indexer.process_block(&created).unwrap());
+        assert!(!indexer.process_block(&block(2, vec![])).unwrap());
+        assert_eq!(indexer.store().event_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_status_parse() {
+        for status in [
+            HtlcStatus::Active,
+            HtlcStatus::Withdrawn,
+            HtlcStatus::Refunded,
+        ] {
+            assert_eq!(status.as_str().parse::<HtlcStatus>().unwrap(), status);
+        }
+        assert!("pending".parse::<HtlcStatus>().is_err());
+    }
+}
@@ -0,0 +1,111 @@
+// htlc-indexer: index FusionPlusHTLC events into SQLite and query them.
+//
+//   htlc-indexer --db <path> --contract <account>... replay <dir>
+//   htlc-indexer --db <path> --contract <account>... stream
+//   htlc-indexer --db <path> order <hex order hash>
+//   htlc-indexer --db <path> account <account>
+//   htlc-indexer --db <path> status <active|withdrawn|refunded>
+//
+// replay reads one block per .json file of a fixture directory, stream reads
+// one block per line from stdin (e.g. piped from an RPC stand-in). Queries
+// print one JSON record per line.
+
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use htlc_indexer::{
+    FixtureSource, HtlcRecord, HtlcStatus, Indexer, IndexerError, JsonLinesSource, Store,
+};
+use near_sdk::AccountId;
+
+const USAGE: &str = "usage: htlc-indexer --db <path> [--contract <account>]... \
+                     <replay <dir> | stream | order <hex> | account <id> | status <status>>";
+
+fn main() -> ExitCode {
+    match run(std::env::args().skip(1).collect()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: Vec<String>) -> Result<(), String> {
+    let mut db = None;
+    let mut contracts: Vec<AccountId> = Vec::new();
+    let mut command = Vec::new();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--db" => db = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+            "--contract" => contracts.push(
+                args.next()
+                    .ok_or(USAGE)?
+                    .parse()
+                    .map_err(|err| format!("invalid contract account: {err}"))?,
+            ),
+            _ => command.push(arg),
+        }
+    }
+
+    let store = Store::open(&db.ok_or(USAGE)?).map_err(|err| err.to_string())?;
+    let command: Vec<&str> = command.iter().map(String::as_str).collect();
+    match command.as_slice() {
+        ["replay", dir] => {
+            let mut source = FixtureSource::open(dir.as_ref()).map_err(|err| err.to_string())?;
+            index(store, contracts, &mut source)
+        }
+        ["stream"] => index(
+            store,
+            contracts,
+            &mut JsonLinesSource::new(io::stdin().lock()),
+        ),
+        ["order", order_hash] => {
+            let order_hash = hex::decode(order_hash.trim_start_matches("0x"))
+                .map_err(|err| format!("invalid order hash: {err}"))?;
+            print(store.htlcs_by_order_hash(&order_hash))
+        }
+        ["account", account_id] => print(store.htlcs_by_account(account_id)),
+        ["status", status] => {
+            let status: HtlcStatus = status
+                .parse()
+                .map_err(|err: IndexerError| err.to_string())?;
+            print(store.htlcs_by_status(status))
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn index(
+    store: Store,
+    contracts: Vec<AccountId>,
+    source: &mut impl htlc_indexer::BlockSource,
+) -> Result<(), String> {
+    if contracts.is_empty() {
+        return Err("at least one --contract is required for indexing".to_string());
+    }
+    let mut indexer = Indexer::new(store, contracts);
+    let indexed = indexer.run(source).map_err(|err| err.to_string())?;
+    let height = indexer
+        .store()
+        .last_block_height()
+        .map_err(|err| err.to_string())?;
+    eprintln!(
+        "indexed {indexed} blocks, at height {}",
+        height.map_or("-".to_string(), |h| h.to_string())
+    );
+    Ok(())
+}
+
+fn print(records: Result<Vec<HtlcRecord>, IndexerError>) -> Result<(), String> {
+    for record in records.map_err(|err| err.to_string())? {
+        println!(
+            "{}",
+            serde_json::to_string(&record).map_err(|err| err.to_string())?
+        );
+    }
+    Ok(())
+}
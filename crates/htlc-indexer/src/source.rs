@@ -0,0 +1,135 @@
+// Block outcome data fed to the indexer.
+//
+// A block is the list of receipt execution outcomes it contains, in the
+// shape of the RPC's ExecutionOutcomeWithIdView (as in the receipts_outcome
+// field of EXPERIMENTAL_tx_status), plus the block header fields we keep.
+// Sources hand out blocks in ascending height order.
+
+use std::fs;
+use std::io::BufRead;
+use std::path::Path;
+
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::AccountId;
+
+use crate::IndexerError;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BlockOutcomes {
+    pub block_height: u64,
+    pub block_hash: String,
+    // Nanoseconds
+    pub block_timestamp: u64,
+    pub receipt_outcomes: Vec<ExecutionOutcomeWithId>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ExecutionOutcomeWithId {
+    // Receipt id
+    pub id: String,
+    pub outcome: ExecutionOutcome,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ExecutionOutcome {
+    pub executor_id: AccountId,
+    pub logs: Vec<String>,
+    pub status: ExecutionStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ExecutionStatus {
+    Unknown,
+    Failure(serde_json::Value),
+    SuccessValue(String),
+    SuccessReceiptId(String),
+}
+
+impl ExecutionStatus {
+    // Logs of failed receipts are kept by the RPC, but their state changes
+    // were rolled back
+    pub fn is_success(&self) -> bool {
+        matches!(self, Self::SuccessValue(_) | Self::SuccessReceiptId(_))
+    }
+}
+
+pub trait BlockSource {
+    fn next_block(&mut self) -> Result<Option<BlockOutcomes>, IndexerError>;
+}
+
+// Directory of <anything>.json files holding one block each. Files are
+// ordered by block height, not by name, so a replay does not depend on
+// directory listing order.
+pub struct FixtureSource {
+    blocks: std::vec::IntoIter<BlockOutcomes>,
+}
+
+impl FixtureSource {
+    pub fn open(dir: &Path) -> Result<Self, IndexerError> {
+        let mut blocks = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let block: BlockOutcomes = serde_json::from_slice(&fs::read(&path)?)
+                    .map_err(|err| IndexerError::Source(format!("{}: {err}", path.display())))?;
+                blocks.push(block);
+            }
+        }
+
+        blocks.sort_by_key(|block| block.block_height);
+        if let Some(pair) = blocks
+            .windows(2)
+            .find(|pair| pair[0].block_height == pair[1].block_height)
+        {
+            return Err(IndexerError::Source(format!(
+                "block {} appears twice in {}",
+                pair[0].block_height,
+                dir.display()
+            )));
+        }
+        Ok(Self {
+            blocks: blocks.into_iter(),
+        })
+    }
+}
+
+impl BlockSource for FixtureSource {
+    fn next_block(&mut self) -> Result<Option<BlockOutcomes>, IndexerError> {
+        Ok(self.blocks.next())
+    }
+}
+
+// One JSON block per line, e.g. piped from a node or an RPC stand-in
+pub struct JsonLinesSource<R> {
+    reader: R,
+    line: String,
+}
+
+impl<R: BufRead> JsonLinesSource<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+        }
+    }
+}
+
+impl<R: BufRead> BlockSource for JsonLinesSource<R> {
+    fn next_block(&mut self) -> Result<Option<BlockOutcomes>, IndexerError> {
+        loop {
+            self.line.clear();
+            if self.reader.read_line(&mut self.line)? == 0 {
+                return Ok(None);
+            }
+            if !self.line.trim().is_empty() {
+                return serde_json::from_str(&self.line)
+                    .map(Some)
+                    .map_err(|err| IndexerError::Source(err.to_string()));
+            }
+        }
+    }
+}
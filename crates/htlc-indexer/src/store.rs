@@ -0,0 +1,377 @@
+// SQLite persistence of indexed events and the HTLC lifecycles built from
+// them.
+//
+// Every event is keyed by (receipt id, log index) and applied to the htlcs
+// table only when first inserted, so feeding the same blocks twice leaves
+// the database unchanged. Binary fields (hashlock, order hash, secret) are
+// stored hex encoded.
+
+use std::path::Path;
+
+use fusion_plus_htlc::EventLog;
+use near_sdk::serde::Serialize;
+use rusqlite::{params, Connection, OptionalExtension, Row, Transaction};
+
+use crate::IndexerError;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS events (
+    receipt_id TEXT NOT NULL,
+    log_index INTEGER NOT NULL,
+    block_height INTEGER NOT NULL,
+    contract_id TEXT NOT NULL,
+    htlc_id TEXT NOT NULL,
+    event_type TEXT NOT NULL,
+    data TEXT NOT NULL,
+    PRIMARY KEY (receipt_id, log_index)
+);
+CREATE TABLE IF NOT EXISTS htlcs (
+    contract_id TEXT NOT NULL,
+    htlc_id TEXT NOT NULL,
+    status TEXT NOT NULL,
+    sender TEXT,
+    receiver TEXT,
+    amount TEXT,
+    hashlock TEXT,
+    timelock INTEGER,
+    order_hash TEXT,
+    secret TEXT,
+    signed_tx TEXT,
+    created_at INTEGER,
+    finalized_at INTEGER,
+    PRIMARY KEY (contract_id, htlc_id)
+);
+CREATE INDEX IF NOT EXISTS htlcs_order_hash ON htlcs (order_hash);
+CREATE INDEX IF NOT EXISTS htlcs_sender ON htlcs (sender);
+CREATE INDEX IF NOT EXISTS htlcs_receiver ON htlcs (receiver);
+CREATE INDEX IF NOT EXISTS htlcs_status ON htlcs (status);
+CREATE TABLE IF NOT EXISTS cursor (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    block_height INTEGER NOT NULL
+);
+";
+
+const HTLC_COLUMNS: &str = "contract_id, htlc_id, status, sender, receiver, amount, hashlock, \
+                            timelock, order_hash, secret, signed_tx, created_at, finalized_at";
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum HtlcStatus {
+    Active,
+    Withdrawn,
+    Refunded,
+}
+
+impl HtlcStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Withdrawn => "withdrawn",
+            Self::Refunded => "refunded",
+        }
+    }
+}
+
+impl std::str::FromStr for HtlcStatus {
+    type Err = IndexerError;
+
+    fn from_str(status: &str) -> Result<Self, Self::Err> {
+        match status {
+            "active" => Ok(Self::Active),
+            "withdrawn" => Ok(Self::Withdrawn),
+            "refunded" => Ok(Self::Refunded),
+            _ => Err(IndexerError::InvalidStatus(status.to_string())),
+        }
+    }
+}
+
+// An HTLC as reconstructed from its events. Fields stay None when the
+// indexer started after the event that carries them.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct HtlcRecord {
+    pub contract_id: String,
+    pub htlc_id: String,
+    pub status: HtlcStatus,
+    pub sender: Option<String>,
+    pub receiver: Option<String>,
+    // Decimal yoctoNEAR / token units
+    pub amount: Option<String>,
+    pub hashlock: Option<String>,
+    pub timelock: Option<u64>,
+    pub order_hash: Option<String>,
+    pub secret: Option<String>,
+    pub signed_tx: Option<String>,
+    pub created_at: Option<u64>,
+    pub finalized_at: Option<u64>,
+}
+
+impl HtlcRecord {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let status: String = row.get(2)?;
+        Ok(Self {
+            contract_id: row.get(0)?,
+            htlc_id: row.get(1)?,
+            status: status.parse().map_err(|err: IndexerError| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    2,
+                    rusqlite::types::Type::Text,
+                    err.into(),
+                )
+            })?,
+            sender: row.get(3)?,
+            receiver: row.get(4)?,
+            amount: row.get(5)?,
+            hashlock: row.get(6)?,
+            timelock: row.get::<_, Option<i64>>(7)?.map(|t| t as u64),
+            order_hash: row.get(8)?,
+            secret: row.get(9)?,
+            signed_tx: row.get(10)?,
+            created_at: row.get::<_, Option<i64>>(11)?.map(|t| t as u64),
+            finalized_at: row.get::<_, Option<i64>>(12)?.map(|t| t as u64),
+        })
+    }
+}
+
+// Lifecycle change carried by one event
+pub(crate) enum HtlcUpdate {
+    Created {
+        sender: Option<String>,
+        receiver: Option<String>,
+        amount: Option<String>,
+        hashlock: Option<String>,
+        timelock: Option<u64>,
+        order_hash: Option<String>,
+        created_at: u64,
+    },
+    SecretRevealed {
+        secret: String,
+    },
+    Finalized {
+        status: HtlcStatus,
+        sender: Option<String>,
+        receiver: Option<String>,
+        amount: Option<String>,
+        finalized_at: u64,
+    },
+    SettlementSigned {
+        signed_tx: String,
+    },
+}
+
+pub(crate) struct NewEvent<'a> {
+    pub receipt_id: &'a str,
+    pub log_index: usize,
+    pub block_height: u64,
+    pub contract_id: &'a str,
+    pub htlc_id: &'a str,
+    pub event_type: &'a str,
+    pub data: &'a str,
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: &Path) -> Result<Self, IndexerError> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, IndexerError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, IndexerError> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    // Height of the last fully indexed block
+    pub fn last_block_height(&self) -> Result<Option<u64>, IndexerError> {
+        Ok(self
+            .conn
+            .query_row("SELECT block_height FROM cursor WHERE id = 0", [], |row| {
+                row.get::<_, i64>(0)
+            })
+            .optional()?
+            .map(|height| height as u64))
+    }
+
+    pub(crate) fn transaction(&mut self) -> Result<Transaction<'_>, IndexerError> {
+        Ok(self.conn.transaction()?)
+    }
+
+    pub fn htlc(
+        &self,
+        contract_id: &str,
+        htlc_id: &str,
+    ) -> Result<Option<HtlcRecord>, IndexerError> {
+        Ok(self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT {HTLC_COLUMNS} FROM htlcs WHERE contract_id = ?1 AND htlc_id = ?2"
+                ),
+                params![contract_id, htlc_id],
+                HtlcRecord::from_row,
+            )
+            .optional()?)
+    }
+
+    pub fn htlcs_by_order_hash(&self, order_hash: &[u8]) -> Result<Vec<HtlcRecord>, IndexerError> {
+        self.query("order_hash = ?1", hex::encode(order_hash))
+    }
+
+    // HTLCs the account sent or receives
+    pub fn htlcs_by_account(&self, account_id: &str) -> Result<Vec<HtlcRecord>, IndexerError> {
+        self.query("sender = ?1 OR receiver = ?1", account_id.to_string())
+    }
+
+    pub fn htlcs_by_status(&self, status: HtlcStatus) -> Result<Vec<HtlcRecord>, IndexerError> {
+        self.query("status = ?1", status.as_str().to_string())
+    }
+
+    pub fn event_count(&self) -> Result<u64, IndexerError> {
+        Ok(self
+            .conn
+            .query_row("SELECT COUNT(*) FROM events", [], |row| {
+                row.get::<_, i64>(0)
+            })? as u64)
+    }
+
+    // Events of one contract indexed after sequence number `after`, oldest
+    // first, so a follower can resume from the last sequence number it saw
+    pub fn events_after(
+        &self,
+        contract_id: &str,
+        after: u64,
+        limit: u64,
+    ) -> Result<Vec<(u64, EventLog)>, IndexerError> {
+        let mut statement = self.conn.prepare(
+            "SELECT rowid, data FROM events WHERE contract_id = ?1 AND rowid > ?2
+             ORDER BY rowid LIMIT ?3",
+        )?;
+        let rows = statement
+            .query_map(params![contract_id, after as i64, limit as i64], |row| {
+                Ok((row.get::<_, i64>(0)? as u64, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.into_iter()
+            .map(|(seq, data)| Ok((seq, serde_json::from_str(&data)?)))
+            .collect()
+    }
+
+    // Insertion order follows block order, so results replay identically
+    fn query(&self, condition: &str, value: String) -> Result<Vec<HtlcRecord>, IndexerError> {
+        let mut statement = self.conn.prepare(&format!(
+            "SELECT {HTLC_COLUMNS} FROM htlcs WHERE {condition} ORDER BY rowid"
+        ))?;
+        let records = statement
+            .query_map([value], HtlcRecord::from_row)?
+            .collect::<Result<_, _>>()?;
+        Ok(records)
+    }
+}
+
+pub(crate) fn set_last_block_height(tx: &Transaction, height: u64) -> Result<(), IndexerError> {
+    tx.execute(
+        "INSERT INTO cursor (id, block_height) VALUES (0, ?1)
+         ON CONFLICT (id) DO UPDATE SET block_height = excluded.block_height",
+        [height as i64],
+    )?;
+    Ok(())
+}
+
+// Returns false if the event was already indexed
+pub(crate) fn insert_event(tx: &Transaction, event: &NewEvent) -> Result<bool, IndexerError> {
+    let inserted = tx.execute(
+        "INSERT OR IGNORE INTO events
+         (receipt_id, log_index, block_height, contract_id, htlc_id, event_type, data)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            event.receipt_id,
+            event.log_index as i64,
+            event.block_height as i64,
+            event.contract_id,
+            event.htlc_id,
+            event.event_type,
+            event.data,
+        ],
+    )?;
+    Ok(inserted == 1)
+}
+
+pub(crate) fn apply_update(
+    tx: &Transaction,
+    contract_id: &str,
+    htlc_id: &str,
+    update: HtlcUpdate,
+) -> Result<(), IndexerError> {
+    match update {
+        HtlcUpdate::Created {
+            sender,
+            receiver,
+            amount,
+            hashlock,
+            timelock,
+            order_hash,
+            created_at,
+        } => tx.execute(
+            "INSERT INTO htlcs (contract_id, htlc_id, status, sender, receiver, amount, hashlock,
+                                timelock, order_hash, created_at)
+             VALUES (?1, ?2, 'active', ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+             ON CONFLICT (contract_id, htlc_id) DO UPDATE SET
+                sender = excluded.sender, receiver = excluded.receiver,
+                amount = excluded.amount, hashlock = excluded.hashlock,
+                timelock = excluded.timelock, order_hash = excluded.order_hash,
+                created_at = excluded.created_at",
+            params![
+                contract_id,
+                htlc_id,
+                sender,
+                receiver,
+                amount,
+                hashlock,
+                timelock.map(|t| t as i64),
+                order_hash,
+                created_at as i64,
+            ],
+        )?,
+        HtlcUpdate::SecretRevealed { secret } => tx.execute(
+            "INSERT INTO htlcs (contract_id, htlc_id, status, secret) VALUES (?1, ?2, 'active', ?3)
+             ON CONFLICT (contract_id, htlc_id) DO UPDATE SET secret = excluded.secret",
+            params![contract_id, htlc_id, secret],
+        )?,
+        HtlcUpdate::Finalized {
+            status,
+            sender,
+            receiver,
+            amount,
+            finalized_at,
+        } => tx.execute(
+            "INSERT INTO htlcs (contract_id, htlc_id, status, sender, receiver, amount, finalized_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT (contract_id, htlc_id) DO UPDATE SET
+                status = excluded.status, finalized_at = excluded.finalized_at,
+                sender = COALESCE(sender, excluded.sender),
+                receiver = COALESCE(receiver, excluded.receiver),
+                amount = COALESCE(amount, excluded.amount)",
+            params![
+                contract_id,
+                htlc_id,
+                status.as_str(),
+                sender,
+                receiver,
+                amount,
+                finalized_at as i64,
+            ],
+        )?,
+        HtlcUpdate::SettlementSigned { signed_tx } => tx.execute(
+            "INSERT INTO htlcs (contract_id, htlc_id, status, signed_tx) VALUES (?1, ?2, 'active', ?3)
+             ON CONFLICT (contract_id, htlc_id) DO UPDATE SET signed_tx = excluded.signed_tx",
+            params![contract_id, htlc_id, signed_tx],
+        )?,
+    };
+    Ok(())
+}
@@ -0,0 +1,185 @@
+// Replays fixtures/replay into SQLite and checks the reconstructed lifecycles.
+
+use std::path::{Path, PathBuf};
+
+use htlc_indexer::{
+    BlockSource, FixtureSource, HtlcRecord, HtlcStatus, Indexer, JsonLinesSource, Store,
+};
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/replay")
+}
+
+fn replay(store: Store) -> Indexer {
+    let mut indexer = Indexer::new(store, vec!["htlc.testnet".parse().unwrap()]);
+    let mut source = FixtureSource::open(&fixtures()).unwrap();
+    assert_eq!(indexer.run(&mut source).unwrap(), 3);
+    indexer
+}
+
+// Everything the store holds, through the public queries
+fn snapshot(store: &Store) -> Vec<HtlcRecord> {
+    [
+        HtlcStatus::Active,
+        HtlcStatus::Withdrawn,
+        HtlcStatus::Refunded,
+    ]
+    .into_iter()
+    .flat_map(|status| store.htlcs_by_status(status).unwrap())
+    .collect()
+}
+
+#[test]
+fn test_lifecycles() {
+    let indexer = replay(Store::open_in_memory().unwrap());
+    let store = indexer.store();
+
+    let htlc_1 = store.htlc("htlc.testnet", "htlc_1").unwrap().unwrap();
+    assert_eq!(htlc_1.status, HtlcStatus::Withdrawn);
+    assert_eq!(htlc_1.sender.as_deref(), Some("alice.testnet"));
+    assert_eq!(htlc_1.amount.as_deref(), Some("1000000000000000000000000"));
+    assert_eq!(htlc_1.hashlock, Some(hex::encode([1u8; 32])));
+    assert_eq!(htlc_1.secret, Some(hex::encode(b"fixture secret")));
+    assert_eq!(htlc_1.signed_tx.as_deref(), Some("0x02f86c"));
+    assert_eq!(htlc_1.created_at, Some(1_700_000_100_000_000_000));
+    assert_eq!(htlc_1.finalized_at, Some(1_700_000_101_000_000_000));
+
+    // The failed refund in block 101 and the spoofed withdrawal in block 100
+    // leave htlc_2 to be refunded in block 102
+    let htlc_2 = store.htlc("htlc.testnet", "htlc_2").unwrap().unwrap();
+    assert_eq!(htlc_2.status, HtlcStatus::Refunded);
+    assert_eq!(htlc_2.secret, None);
+    assert_eq!(htlc_2.finalized_at, Some(1_700_000_102_000_000_000));
+
+    assert_eq!(store.event_count().unwrap(), 7);
+    assert_eq!(store.last_block_height().unwrap(), Some(102));
+}
+
+#[test]
+fn test_queries() {
+    let indexer = replay(Store::open_in_memory().unwrap());
+    let store = indexer.store();
+    let ids = |records: Vec<HtlcRecord>| {
+        records
+            .into_iter()
+            .map(|record| record.htlc_id)
+            .collect::<Vec<_>>()
+    };
+
+    // Partial fills of one order
+    assert_eq!(
+        ids(store.htlcs_by_order_hash(&[0xaa; 32]).unwrap()),
+        ["htlc_1", "htlc_3"]
+    );
+    assert_eq!(
+        ids(store.htlcs_by_order_hash(&[0xbb; 32]).unwrap()),
+        ["htlc_2"]
+    );
+    assert!(store.htlcs_by_order_hash(&[0xcc; 32]).unwrap().is_empty());
+
+    assert_eq!(
+        ids(store.htlcs_by_account("alice.testnet").unwrap()),
+        ["htlc_1", "htlc_2"]
+    );
+    assert_eq!(
+        ids(store.htlcs_by_account("resolver.testnet").unwrap()),
+        ["htlc_1", "htlc_2", "htlc_3"]
+    );
+    assert!(store
+        .htlcs_by_account("mallory.testnet")
+        .unwrap()
+        .is_empty());
+
+    assert_eq!(
+        ids(store.htlcs_by_status(HtlcStatus::Active).unwrap()),
+        ["htlc_3"]
+    );
+    assert_eq!(
+        ids(store.htlcs_by_status(HtlcStatus::Withdrawn).unwrap()),
+        ["htlc_1"]
+    );
+    assert_eq!(
+        ids(store.htlcs_by_status(HtlcStatus::Refunded).unwrap()),
+        ["htlc_2"]
+    );
+}
+
+#[test]
+fn test_replay_is_deterministic() {
+    let dir = std::env::temp_dir().join(format!("htlc-indexer-replay-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let db = dir.join("index.sqlite");
+    let _ = std::fs::remove_file(&db);
+
+    let first = snapshot(replay(Store::open(&db).unwrap()).store());
+    assert_eq!(
+        first,
+        snapshot(replay(Store::open_in_memory().unwrap()).store())
+    );
+
+    // Replaying into the same database finds every block already indexed
+    let mut indexer = Indexer::new(
+        Store::open(&db).unwrap(),
+        vec!["htlc.testnet".parse().unwrap()],
+    );
+    let mut source = FixtureSource::open(&fixtures()).unwrap();
+    assert_eq!(indexer.run(&mut source).unwrap(), 0);
+    assert_eq!(snapshot(indexer.store()), first);
+    assert_eq!(indexer.store().event_count().unwrap(), 7);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_json_lines_source_matches_fixtures() {
+    let mut lines = String::new();
+    let mut fixture_source = FixtureSource::open(&fixtures()).unwrap();
+    while let Some(block) = fixture_source.next_block().unwrap() {
+        lines.push_str(&serde_json::to_string(&block).unwrap());
+        lines.push_str("\n\n");
+    }
+
+    let mut indexer = Indexer::new(
+        Store::open_in_memory().unwrap(),
+        vec!["htlc.testnet".parse().unwrap()],
+    );
+    let mut source = JsonLinesSource::new(lines.as_bytes());
+    assert_eq!(indexer.run(&mut source).unwrap(), 3);
+    assert_eq!(
+        snapshot(indexer.store()),
+        snapshot(replay(Store::open_in_memory().unwrap()).store())
+    );
+}
+
+#[test]
+fn test_events_after() {
+    let indexer = replay(Store::open_in_memory().unwrap());
+    let store = indexer.store();
+
+    let events = store.events_after("htlc.testnet", 0, 100).unwrap();
+    let types: Vec<_> = events
+        .iter()
+        .map(|(_, event)| event.event_type.as_str())
+        .collect();
+    assert_eq!(
+        types,
+        [
+            "htlc_created",
+            "htlc_created",
+            "secret_revealed",
+            "htlc_withdrawn",
+            "htlc_refunded",
+            "settlement_signed",
+            "htlc_created"
+        ]
+    );
+
+    // Resuming from the third event's sequence number
+    let rest = store.events_after("htlc.testnet", events[2].0, 2).unwrap();
+    assert_eq!(rest.len(), 2);
+    assert_eq!(rest[0].1.event_type, "htlc_withdrawn");
+    assert!(store
+        .events_after("mallory.testnet", 0, 100)
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,448 @@
+// Watchtower for FusionPlusHTLC deployments.
+//
+// Acts on behalf of accounts that handed it a function-call key for the HTLC
+// contract. Such a key cannot attach a deposit; refund, withdraw and claim
+// need none, as the settlement sign deposit of base_escrow HTLCs is prepaid
+// at creation:
+//
+// - HTLCs sent by a registered account are refunded once their timelock has
+//   passed, so a maker who goes offline still gets their funds back.
+// - When a secret_revealed event shows up on any watched chain, every active
+//   HTLC of the same order whose hashlock the secret opens, and whose
+//   receiver is registered, is withdrawn. This is normally the counterpart
+//   escrow on the other chain. base_escrow HTLCs without a prepaid sign
+//   deposit (created on contracts that predate it, whose withdraw has to
+//   attach one) are skipped and reported once instead.
+// - When a payout_failed event names a registered account, its failed
+//   payouts are claimed again.
+//
+// Failed calls are retried after a delay that doubles with each failure.
+//
+// The watchtower is driven by tick(), which reads each chain through the
+// fusion-plus-client Transport and an EventSource, so both can be mocked.
+
+use std::collections::{HashMap, HashSet};
+
+use async_trait::async_trait;
+use fusion_plus_client::{ClientError, EventLog, HtlcClient, Transport};
+use htlc_indexer::{IndexerError, Store};
+use near_sdk::AccountId;
+use sha3::{Digest, Keccak256};
+
+// get_active_htlcs page size
+pub const DEFAULT_PAGE_SIZE: u64 = 100;
+// Events read from an indexer database per poll
+const EVENT_BATCH: u64 = 1_000;
+// Delay (ns) before retrying a failed call, doubled with each further failure
+pub const DEFAULT_RETRY_DELAY: u64 = 30_000_000_000;
+// Caps the retry delay at 64 times the initial one
+const MAX_BACKOFF_SHIFT: u32 = 6;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WatchtowerError {
+    #[error(transparent)]
+    Client(#[from] ClientError),
+    #[error(transparent)]
+    Indexer(#[from] IndexerError),
+    #[error("invalid config: {0}")]
+    Config(String),
+}
+
+// HTLC contract events of one chain, each handed out once, in emission order
+#[async_trait]
+pub trait EventSource: Send {
+    async fn poll(&mut self) -> Result<Vec<EventLog>, WatchtowerError>;
+}
+
+// Follows the events an htlc-indexer writes to its database
+pub struct IndexerEvents {
+    store: Store,
+    contract_id: AccountId,
+    after: u64,
+}
+
+impl IndexerEvents {
+    pub fn new(store: Store, contract_id: AccountId) -> Self {
+        Self {
+            store,
+            contract_id,
+            after: 0,
+        }
+    }
+}
+
+#[async_trait]
+impl EventSource for IndexerEvents {
+    async fn poll(&mut self) -> Result<Vec<EventLog>, WatchtowerError> {
+        let events = self
+            .store
+            .events_after(self.contract_id.as_str(), self.after, EVENT_BATCH)?;
+        if let Some((seq, _)) = events.last() {
+            self.after = *seq;
+        }
+        Ok(events.into_iter().map(|(_, event)| event).collect())
+    }
+}
+
+pub struct Chain<T> {
+    name: String,
+    client: HtlcClient<T>,
+    // Function-call keys of the accounts we act for
+    signers: HashMap<AccountId, HtlcClient<T>>,
+    events: Box<dyn EventSource>,
+}
+
+impl<T: Transport> Chain<T> {
+    // `viewer` only needs to serve view calls
+    pub fn new(
+        name: &str,
+        contract_id: AccountId,
+        viewer: T,
+        events: Box<dyn EventSource>,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            client: HtlcClient::new(viewer, contract_id),
+            signers: HashMap::new(),
+            events,
+        }
+    }
+
+    pub fn with_signer(mut self, account_id: AccountId, transport: T) -> Self {
+        let client = HtlcClient::new(transport, self.client.contract_id().clone());
+        self.signers.insert(account_id, client);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    Refunded {
+        chain: String,
+        htlc_id: String,
+    },
+    Withdrawn {
+        chain: String,
+        htlc_id: String,
+    },
+    // Left to the receiver; reported once per HTLC
+    Skipped {
+        chain: String,
+        htlc_id: String,
+        reason: String,
+    },
+    Claimed {
+        chain: String,
+        account_id: AccountId,
+        token: Option<AccountId>,
+    },
+    // Retried with backoff while the HTLC stays active
+    Failed {
+        chain: String,
+        htlc_id: String,
+        error: String,
+    },
+    // Retried with backoff while the account has something to claim
+    ClaimFailed {
+        chain: String,
+        account_id: AccountId,
+        token: Option<AccountId>,
+        error: String,
+    },
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Tick {
+    pub actions: Vec<Action>,
+    // Earliest timelock (ns) of a registered sender's HTLC not yet expired
+    pub next_refund_at: Option<u64>,
+}
+
+// A secret_revealed event whose order hash is not yet known
+struct Reveal {
+    chain: usize,
+    htlc_id: String,
+    secret: Vec<u8>,
+}
+
+// Failed payouts of a registered account, from a payout_failed event
+struct Claim {
+    chain: usize,
+    account_id: AccountId,
+    token: Option<AccountId>,
+    backoff: Backoff,
+}
+
+#[derive(Default)]
+struct Backoff {
+    failures: u32,
+    retry_at: u64,
+}
+
+impl Backoff {
+    fn failed(&mut self, now: u64, delay: u64) {
+        let delay = delay.saturating_mul(1 << self.failures.min(MAX_BACKOFF_SHIFT));
+        self.retry_at = now.saturating_add(delay);
+        self.failures += 1;
+    }
+}
+
+pub struct Watchtower<T> {
+    chains: Vec<Chain<T>>,
+    reveals: Vec<Reveal>,
+    // Revealed secrets by order hash; an order filled in parts reveals one
+    // secret per fill
+    secrets: HashMap<Vec<u8>, Vec<Vec<u8>>>,
+    claims: Vec<Claim>,
+    // Failed HTLCs by (chain, htlc id)
+    retries: HashMap<(usize, String), Backoff>,
+    skipped: HashSet<(usize, String)>,
+    page_size: u64,
+    retry_delay: u64,
+}
+
+impl<T: Transport> Watchtower<T> {
+    pub fn new(chains: Vec<Chain<T>>) -> Self {
+        Self {
+            chains,
+            reveals: Vec::new(),
+            secrets: HashMap::new(),
+            claims: Vec::new(),
+            retries: HashMap::new(),
+            skipped: HashSet::new(),
+            page_size: DEFAULT_PAGE_SIZE,
+            retry_delay: DEFAULT_RETRY_DELAY,
+        }
+    }
+
+    pub fn with_page_size(mut self, page_size: u64) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    pub fn with_retry_delay(mut self, retry_delay: u64) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    // One pass over all chains at wall-clock time `now` (ns, comparable to
+    // the contract's block timestamps). View errors abort the pass; it is
+    // safe to call again, nothing read so far is lost.
+    pub async fn tick(&mut self, now: u64) -> Result<Tick, WatchtowerError> {
+        for (index, chain) in self.chains.iter_mut().enumerate() {
+            for event in chain.events.poll().await? {
+                match event.event_type.as_str() {
+                    "secret_revealed" => {
+                        if let Some(secret) = event.secret {
+                            self.reveals.push(Reveal {
+                                chain: index,
+                                htlc_id: event.htlc_id,
+                                secret: secret.0,
+                            });
+                        }
+                    }
+                    "payout_failed" => {
+                        let Some(account_id) = event.receiver else {
+                            continue;
+                        };
+                        let pending = self.claims.iter().any(|claim| {
+                            claim.chain == index
+                                && claim.account_id == account_id
+                                && claim.token == event.token
+                        });
+                        if chain.signers.contains_key(&account_id) && !pending {
+                            self.claims.push(Claim {
+                                chain: index,
+                                account_id,
+                                token: event.token,
+                                backoff: Backoff::default(),
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        self.resolve_reveals().await?;
+
+        let mut tick = Tick::default();
+        self.claim_payouts(now, &mut tick).await?;
+
+        let mut active = Vec::with_capacity(self.chains.len());
+        for chain in &self.chains {
+            active.push(self.active_htlcs(chain).await?);
+        }
+
+        for (index, (chain, htlcs)) in self.chains.iter().zip(&active).enumerate() {
+            for htlc in htlcs {
+                let key = (index, htlc.htlc_id.clone());
+                if self
+                    .retries
+                    .get(&key)
+                    .is_some_and(|backoff| now < backoff.retry_at)
+                {
+                    continue;
+                }
+                let secret = self.secrets.get(&htlc.order_hash.0).and_then(|secrets| {
+                    secrets
+                        .iter()
+                        .find(|secret| Keccak256::digest(secret).as_slice() == htlc.hashlock.0)
+                });
+                let timelock = htlc.timelock.0 as u64;
+
+                let call = match (secret, chain.signers.get(&htlc.receiver)) {
+                    (Some(_), Some(_))
+                        if htlc.base_escrow.is_some() && htlc.settlement_deposit.0 == 0 =>
+                    {
+                        if self.skipped.insert(key) {
+                            tick.actions.push(Action::Skipped {
+                                chain: chain.name.clone(),
+                                htlc_id: htlc.htlc_id.clone(),
+                                reason: "withdraw needs the settlement sign deposit attached"
+                                    .to_string(),
+                            });
+                        }
+                        continue;
+                    }
+                    (Some(secret), Some(receiver)) => {
+                        let result = receiver.withdraw(&htlc.htlc_id, secret).await;
+                        let done = Action::Withdrawn {
+                            chain: chain.name.clone(),
+                            htlc_id: htlc.htlc_id.clone(),
+                        };
+                        Some((result, done))
+                    }
+                    _ => match chain.signers.get(&htlc.sender) {
+                        Some(sender) if now >= timelock => {
+                            let result = sender.refund(&htlc.htlc_id).await;
+                            let done = Action::Refunded {
+                                chain: chain.name.clone(),
+                                htlc_id: htlc.htlc_id.clone(),
+                            };
+                            Some((result, done))
+                        }
+                        Some(_) => {
+                            tick.next_refund_at =
+                                Some(tick.next_refund_at.map_or(timelock, |t| t.min(timelock)));
+                            None
+                        }
+                        None => None,
+                    },
+                };
+                match call {
+                    Some((Ok(_), done)) => {
+                        self.retries.remove(&key);
+                        tick.actions.push(done);
+                    }
+                    Some((Err(err), _)) => {
+                        self.retries
+                            .entry(key)
+                            .or_default()
+                            .failed(now, self.retry_delay);
+                        tick.actions.push(Action::Failed {
+                            chain: chain.name.clone(),
+                            htlc_id: htlc.htlc_id.clone(),
+                            error: err.to_string(),
+                        });
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        // Orders with nothing left to withdraw no longer need their secrets,
+        // nor finalized HTLCs their retry and skip state
+        self.secrets.retain(|order_hash, _| {
+            active
+                .iter()
+                .flatten()
+                .any(|htlc| &htlc.order_hash.0 == order_hash)
+        });
+        let is_active = |(index, htlc_id): &(usize, String)| {
+            active[*index].iter().any(|htlc| &htlc.htlc_id == htlc_id)
+        };
+        self.retries.retain(|key, _| is_active(key));
+        self.skipped.retain(is_active);
+        Ok(tick)
+    }
+
+    // Claims the failed payouts of registered accounts. A claim is dropped
+    // once the account has nothing left to claim, e.g. after claiming
+    // itself.
+    async fn claim_payouts(&mut self, now: u64, tick: &mut Tick) -> Result<(), WatchtowerError> {
+        let mut index = 0;
+        while index < self.claims.len() {
+            let claim = &mut self.claims[index];
+            if now < claim.backoff.retry_at {
+                index += 1;
+                continue;
+            }
+            let chain = &self.chains[claim.chain];
+            let claimable = chain
+                .client
+                .get_claimable(&claim.account_id, claim.token.as_ref())
+                .await?;
+            if claimable.0 > 0 {
+                let signer = &chain.signers[&claim.account_id];
+                match signer.claim(claim.token.as_ref()).await {
+                    Ok(_) => tick.actions.push(Action::Claimed {
+                        chain: chain.name.clone(),
+                        account_id: claim.account_id.clone(),
+                        token: claim.token.clone(),
+                    }),
+                    Err(err) => {
+                        claim.backoff.failed(now, self.retry_delay);
+                        tick.actions.push(Action::ClaimFailed {
+                            chain: chain.name.clone(),
+                            account_id: claim.account_id.clone(),
+                            token: claim.token.clone(),
+                            error: err.to_string(),
+                        });
+                        index += 1;
+                        continue;
+                    }
+                }
+            }
+            self.claims.remove(index);
+        }
+        Ok(())
+    }
+
+    // Looks up the order of each revealed HTLC. Kept across ticks until the
+    // lookup succeeds, since the event itself has already been consumed.
+    async fn resolve_reveals(&mut self) -> Result<(), WatchtowerError> {
+        while let Some(reveal) = self.reveals.first() {
+            let chain = &self.chains[reveal.chain];
+            if let Some(htlc) = chain.client.get_htlc(&reveal.htlc_id).await? {
+                let secrets = self.secrets.entry(htlc.order_hash.0).or_default();
+                if !secrets.contains(&reveal.secret) {
+                    secrets.push(reveal.secret.clone());
+                }
+            }
+            self.reveals.remove(0);
+        }
+        Ok(())
+    }
+
+    async fn active_htlcs(
+        &self,
+        chain: &Chain<T>,
+    ) -> Result<Vec<fusion_plus_client::HTLCView>, WatchtowerError> {
+        let mut htlcs = Vec::new();
+        loop {
+            let page = chain
+                .client
+                .get_active_htlcs(htlcs.len() as u64, self.page_size)
+                .await?;
+            let done = (page.len() as u64) < self.page_size;
+            htlcs.extend(page);
+            if done {
+                return Ok(htlcs);
+            }
+        }
+    }
+}
@@ -0,0 +1,167 @@
+// htlc-watchtower <config.json>
+//
+// Runs the watchtower against live networks. Each chain is reached over
+// JSON-RPC and followed through the database of an htlc-indexer running
+// against the same contract. See watchtower.example.json; the keys are
+// function-call access keys of the registered accounts, scoped to the HTLC
+// contract's refund, withdraw and claim methods.
+
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use htlc_indexer::Store;
+use htlc_watchtower::{Action, Chain, IndexerEvents, Watchtower, WatchtowerError};
+use near_sdk::serde::Deserialize;
+use near_sdk::AccountId;
+use near_workspaces::types::SecretKey;
+use near_workspaces::Account;
+
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+struct Config {
+    poll_interval_secs: u64,
+    chains: Vec<ChainConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+struct ChainConfig {
+    name: String,
+    rpc_url: String,
+    contract_id: AccountId,
+    indexer_db: String,
+    keys: Vec<KeyConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+struct KeyConfig {
+    account_id: AccountId,
+    secret_key: String,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let Some(path) = std::env::args().nth(1) else {
+        eprintln!("usage: htlc-watchtower <config.json>");
+        return ExitCode::FAILURE;
+    };
+    match run(Path::new(&path)).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(path: &Path) -> Result<(), WatchtowerError> {
+    let config: Config = std::fs::read(path)
+        .map_err(|err| err.to_string())
+        .and_then(|json| serde_json::from_slice(&json).map_err(|err| err.to_string()))
+        .map_err(|err| WatchtowerError::Config(format!("{}: {err}", path.display())))?;
+
+    let mut chains = Vec::new();
+    for chain in config.chains {
+        chains.push(connect(chain).await?);
+    }
+    let mut watchtower = Watchtower::new(chains);
+    let poll_interval = Duration::from_secs(config.poll_interval_secs);
+
+    loop {
+        let now = now();
+        let mut wait = poll_interval;
+        match watchtower.tick(now).await {
+            Ok(tick) => {
+                for action in &tick.actions {
+                    report(action);
+                }
+                // Wake up for the next expiry rather than up to a full
+                // interval after it
+                if let Some(at) = tick.next_refund_at {
+                    wait = wait.min(Duration::from_nanos(at.saturating_sub(now)));
+                }
+            }
+            Err(err) => eprintln!("tick failed: {err}"),
+        }
+        tokio::time::sleep(wait).await;
+    }
+}
+
+async fn connect(config: ChainConfig) -> Result<Chain<Account>, WatchtowerError> {
+    // The network type only picks defaults; the RPC URL decides where we go
+    let worker = near_workspaces::testnet()
+        .rpc_addr(&config.rpc_url)
+        .await
+        .map_err(|err| WatchtowerError::Config(format!("{}: {err}", config.name)))?;
+
+    let mut signers = Vec::new();
+    for key in config.keys {
+        let secret_key: SecretKey = key.secret_key.parse().map_err(|err| {
+            WatchtowerError::Config(format!("{}: key of {}: {err}", config.name, key.account_id))
+        })?;
+        signers.push(Account::from_secret_key(
+            key.account_id.clone(),
+            secret_key,
+            &worker,
+        ));
+    }
+    // Views are unsigned, any of the accounts can serve them
+    let viewer = signers
+        .first()
+        .cloned()
+        .ok_or_else(|| WatchtowerError::Config(format!("{}: no keys registered", config.name)))?;
+
+    let events = IndexerEvents::new(
+        Store::open(Path::new(&config.indexer_db))?,
+        config.contract_id.clone(),
+    );
+    let mut chain = Chain::new(&config.name, config.contract_id, viewer, Box::new(events));
+    for signer in signers {
+        chain = chain.with_signer(signer.id().clone(), signer);
+    }
+    Ok(chain)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock before 1970")
+        .as_nanos() as u64
+}
+
+fn report(action: &Action) {
+    match action {
+        Action::Refunded { chain, htlc_id } => println!("{chain}: refunded {htlc_id}"),
+        Action::Withdrawn { chain, htlc_id } => println!("{chain}: withdrew {htlc_id}"),
+        Action::Skipped {
+            chain,
+            htlc_id,
+            reason,
+        } => eprintln!("{chain}: skipped {htlc_id}: {reason}"),
+        Action::Claimed {
+            chain,
+            account_id,
+            token,
+        } => println!("{chain}: claimed {} for {account_id}", token_name(token)),
+        Action::Failed {
+            chain,
+            htlc_id,
+            error,
+        } => eprintln!("{chain}: {htlc_id} failed: {error}"),
+        Action::ClaimFailed {
+            chain,
+            account_id,
+            token,
+            error,
+        } => eprintln!(
+            "{chain}: claiming {} for {account_id} failed: {error}",
+            token_name(token)
+        ),
+    }
+}
+
+fn token_name(token: &Option<AccountId>) -> &str {
+    token.as_ref().map_or("NEAR", |token| token.as_str())
+}
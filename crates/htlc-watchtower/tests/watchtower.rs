@@ -0,0 +1,547 @@
+// Drives the watchtower against an in-memory stand-in for the HTLC contract's
+// RPC surface: the views it polls and the refund / withdraw / claim rules it
+// must satisfy.
+
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use fusion_plus_client::{CallOutcome, ClientError, EventLog, HtlcClient, Transport};
+use htlc_watchtower::{Action, Chain, EventSource, Tick, Watchtower, WatchtowerError};
+use near_sdk::json_types::{Base64VecU8, U128};
+use near_sdk::{AccountId, Gas, NearToken};
+use serde_json::{json, Value};
+use sha3::{Digest, Keccak256};
+
+const SECRET: &[u8] = b"secret of the first fill";
+
+#[derive(Default)]
+struct FakeContract {
+    htlcs: Vec<Value>,
+    block_timestamp: u64,
+    // (signer, method, htlc_id or claimed token) of every successful call
+    calls: Vec<(String, String, String)>,
+    // (account, token) of failed payouts
+    claimable: Vec<(String, Option<String>)>,
+    // Emitted but not yet polled
+    events: Vec<EventLog>,
+}
+
+impl FakeContract {
+    fn htlc_mut(&mut self, htlc_id: &str) -> Option<&mut Value> {
+        self.htlcs
+            .iter_mut()
+            .find(|htlc| htlc["htlc_id"] == htlc_id)
+    }
+}
+
+// Transport signing as `signer` against a shared FakeContract
+#[derive(Clone)]
+struct FakeRpc {
+    contract: Arc<Mutex<FakeContract>>,
+    signer: AccountId,
+}
+
+#[async_trait]
+impl Transport for FakeRpc {
+    async fn view(
+        &self,
+        _contract_id: &AccountId,
+        method: &str,
+        args: Vec<u8>,
+    ) -> Result<Vec<u8>, ClientError> {
+        let args: Value = serde_json::from_slice(&args).unwrap();
+        let contract = self.contract.lock().unwrap();
+        let result = match method {
+            "get_active_htlcs" => {
+                let from = args["from_index"].as_u64().unwrap() as usize;
+                let limit = args["limit"].as_u64().unwrap() as usize;
+                let active: Vec<_> = contract
+                    .htlcs
+                    .iter()
+                    .filter(|htlc| htlc["withdrawn"] == false && htlc["refunded"] == false)
+                    .skip(from)
+                    .take(limit)
+                    .collect();
+                json!(active)
+            }
+            "get_htlc" => contract
+                .htlcs
+                .iter()
+                .find(|htlc| htlc["htlc_id"] == args["htlc_id"])
+                .cloned()
+                .unwrap_or(Value::Null),
+            "get_claimable" => {
+                let key = (
+                    args["account_id"].as_str().unwrap().to_string(),
+                    args["token"].as_str().map(str::to_string),
+                );
+                let amount = if contract.claimable.contains(&key) {
+                    1_000
+                } else {
+                    0
+                };
+                json!(U128(amount))
+            }
+            _ => panic!("unexpected view {method}"),
+        };
+        Ok(serde_json::to_vec(&result).unwrap())
+    }
+
+    async fn call(
+        &self,
+        _contract_id: &AccountId,
+        method: &str,
+        args: Vec<u8>,
+        deposit: NearToken,
+        _gas: Gas,
+    ) -> Result<CallOutcome, ClientError> {
+        // Function-call keys cannot attach a deposit
+        assert!(deposit.is_zero());
+        let args: Value = serde_json::from_slice(&args).unwrap();
+        let signer = self.signer.to_string();
+        let fail = |message: &str| Err(ClientError::Execution(message.to_string()));
+
+        let mut contract = self.contract.lock().unwrap();
+        if method == "claim" {
+            let token = args["token"].as_str().map(str::to_string);
+            let key = (signer.clone(), token.clone());
+            let Some(index) = contract.claimable.iter().position(|k| *k == key) else {
+                return fail("Nothing to claim");
+            };
+            contract.claimable.remove(index);
+            contract
+                .calls
+                .push((signer, method.to_string(), token.unwrap_or_default()));
+            return Ok(CallOutcome {
+                value: b"null".to_vec(),
+                logs: vec![],
+            });
+        }
+        let htlc_id = args["htlc_id"].as_str().unwrap().to_string();
+        let now = contract.block_timestamp;
+        let htlc = contract.htlc_mut(&htlc_id).expect("HTLC does not exist");
+        if htlc["withdrawn"] == true || htlc["refunded"] == true {
+            return fail("Already finalized");
+        }
+        let mut logs = Vec::new();
+        match method {
+            "refund" => {
+                if htlc["sender"] != signer.as_str() {
+                    return fail("Only sender can refund");
+                }
+                let timelock: U128 = serde_json::from_value(htlc["timelock"].clone()).unwrap();
+                if (now as u128) < timelock.0 {
+                    return fail("Timelock not expired");
+                }
+                htlc["refunded"] = json!(true);
+            }
+            "withdraw" => {
+                if htlc["receiver"] != signer.as_str() {
+                    return fail("Only receiver can withdraw");
+                }
+                let secret: Base64VecU8 = serde_json::from_value(args["secret"].clone()).unwrap();
+                if json!(hashlock(&secret.0)) != htlc["hashlock"] {
+                    return fail("Invalid secret");
+                }
+                htlc["withdrawn"] = json!(true);
+                let event = secret_revealed(&htlc_id, &secret.0);
+                logs.push(format!(
+                    "EVENT_JSON:{}",
+                    serde_json::to_string(&event).unwrap()
+                ));
+                contract.events.push(event);
+            }
+            _ => panic!("unexpected call {method}"),
+        }
+        contract.calls.push((signer, method.to_string(), htlc_id));
+        Ok(CallOutcome {
+            value: b"null".to_vec(),
+            logs,
+        })
+    }
+}
+
+struct FakeEvents(Arc<Mutex<FakeContract>>);
+
+#[async_trait]
+impl EventSource for FakeEvents {
+    async fn poll(&mut self) -> Result<Vec<EventLog>, WatchtowerError> {
+        Ok(std::mem::take(&mut self.0.lock().unwrap().events))
+    }
+}
+
+fn account(id: &str) -> AccountId {
+    id.parse().unwrap()
+}
+
+fn hashlock(secret: &[u8]) -> Base64VecU8 {
+    Base64VecU8(Keccak256::digest(secret).to_vec())
+}
+
+fn secret_revealed(htlc_id: &str, secret: &[u8]) -> EventLog {
+    EventLog {
+        event_type: "secret_revealed".to_string(),
+        htlc_id: htlc_id.to_string(),
+        sender: None,
+        receiver: None,
+        secret: Some(Base64VecU8(secret.to_vec())),
+        amount: None,
+        token: None,
+        hashlock: None,
+        timelock: None,
+        order_hash: None,
+        signed_tx: None,
+        timestamp: U128(0),
+    }
+}
+
+fn payout_failed(account_id: &str, token: Option<&str>) -> EventLog {
+    EventLog {
+        event_type: "payout_failed".to_string(),
+        htlc_id: String::new(),
+        sender: None,
+        receiver: Some(account(account_id)),
+        secret: None,
+        amount: Some(U128(1_000)),
+        token: token.map(account),
+        hashlock: None,
+        timelock: None,
+        order_hash: None,
+        signed_tx: None,
+        timestamp: U128(0),
+    }
+}
+
+fn htlc(htlc_id: &str, sender: &str, receiver: &str, secret: &[u8], timelock: u64) -> Value {
+    json!({
+        "htlc_id": htlc_id,
+        "sender": sender,
+        "receiver": receiver,
+        "token": null,
+        "amount": "1000",
+        "hashlock": hashlock(secret),
+        "timelock": timelock.to_string(),
+        "order_hash": Base64VecU8(vec![0xaa; 32]),
+        "withdrawn": false,
+        "refunded": false,
+        "created_at": "0",
+        "base_escrow": null,
+    })
+}
+
+fn fake_contract(htlcs: Vec<Value>) -> Arc<Mutex<FakeContract>> {
+    Arc::new(Mutex::new(FakeContract {
+        htlcs,
+        ..Default::default()
+    }))
+}
+
+// A chain acting for `signers`
+fn chain(name: &str, contract: &Arc<Mutex<FakeContract>>, signers: &[&str]) -> Chain<FakeRpc> {
+    let rpc = |signer: &str| FakeRpc {
+        contract: contract.clone(),
+        signer: account(signer),
+    };
+    let mut chain = Chain::new(
+        name,
+        account(&format!("htlc.{name}")),
+        rpc("viewer.testnet"),
+        Box::new(FakeEvents(contract.clone())),
+    );
+    for signer in signers {
+        chain = chain.with_signer(account(signer), rpc(signer));
+    }
+    chain
+}
+
+fn calls(contract: &Arc<Mutex<FakeContract>>) -> Vec<(String, String, String)> {
+    contract.lock().unwrap().calls.clone()
+}
+
+fn refunded(chain: &str, htlc_id: &str) -> Action {
+    Action::Refunded {
+        chain: chain.to_string(),
+        htlc_id: htlc_id.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_refunds_registered_sender_at_expiry() {
+    let contract = fake_contract(vec![
+        htlc("htlc_1", "maker.testnet", "resolver.testnet", SECRET, 1_000),
+        htlc("htlc_2", "other.testnet", "resolver.testnet", SECRET, 500),
+    ]);
+    let mut watchtower = Watchtower::new(vec![chain("near", &contract, &["maker.testnet"])]);
+
+    // htlc_2 expired first, but its sender has not registered
+    let tick = watchtower.tick(600).await.unwrap();
+    assert_eq!(
+        tick,
+        Tick {
+            actions: vec![],
+            next_refund_at: Some(1_000),
+        }
+    );
+
+    contract.lock().unwrap().block_timestamp = 1_000;
+    let tick = watchtower.tick(1_000).await.unwrap();
+    assert_eq!(tick.actions, vec![refunded("near", "htlc_1")]);
+    assert_eq!(tick.next_refund_at, None);
+    assert_eq!(
+        calls(&contract),
+        vec![(
+            "maker.testnet".to_string(),
+            "refund".to_string(),
+            "htlc_1".to_string()
+        )]
+    );
+
+    assert_eq!(watchtower.tick(2_000).await.unwrap(), Tick::default());
+}
+
+#[tokio::test]
+async fn test_failed_refund_is_retried() {
+    let contract = fake_contract(vec![htlc(
+        "htlc_1",
+        "maker.testnet",
+        "resolver.testnet",
+        SECRET,
+        1_000,
+    )]);
+    let mut watchtower =
+        Watchtower::new(vec![chain("near", &contract, &["maker.testnet"])]).with_retry_delay(10);
+
+    // Our clock runs ahead of the chain's
+    contract.lock().unwrap().block_timestamp = 990;
+    let tick = watchtower.tick(1_000).await.unwrap();
+    assert_eq!(
+        tick.actions,
+        vec![Action::Failed {
+            chain: "near".to_string(),
+            htlc_id: "htlc_1".to_string(),
+            error: "transaction failed: Timelock not expired".to_string(),
+        }]
+    );
+
+    contract.lock().unwrap().block_timestamp = 1_010;
+    let tick = watchtower.tick(1_020).await.unwrap();
+    assert_eq!(tick.actions, vec![refunded("near", "htlc_1")]);
+}
+
+#[tokio::test]
+async fn test_failed_calls_back_off() {
+    let contract = fake_contract(vec![htlc(
+        "htlc_1",
+        "maker.testnet",
+        "resolver.testnet",
+        SECRET,
+        1_000,
+    )]);
+    let mut watchtower =
+        Watchtower::new(vec![chain("near", &contract, &["maker.testnet"])]).with_retry_delay(100);
+    let failed = |tick: Tick| {
+        tick.actions
+            .iter()
+            .filter(|action| matches!(action, Action::Failed { .. }))
+            .count()
+    };
+
+    // The chain's clock lags behind; each failure doubles the delay
+    assert_eq!(failed(watchtower.tick(1_000).await.unwrap()), 1);
+    assert_eq!(failed(watchtower.tick(1_099).await.unwrap()), 0);
+    assert_eq!(failed(watchtower.tick(1_100).await.unwrap()), 1);
+    assert_eq!(failed(watchtower.tick(1_299).await.unwrap()), 0);
+    assert_eq!(failed(watchtower.tick(1_300).await.unwrap()), 1);
+
+    contract.lock().unwrap().block_timestamp = 1_700;
+    assert_eq!(failed(watchtower.tick(1_699).await.unwrap()), 0);
+    let tick = watchtower.tick(1_700).await.unwrap();
+    assert_eq!(tick.actions, vec![refunded("near", "htlc_1")]);
+}
+
+#[tokio::test]
+async fn test_skips_withdraw_needing_deposit() {
+    let mut escrowed = htlc("src_1", "maker.testnet", "resolver.testnet", SECRET, 10_000);
+    escrowed["base_escrow"] = json!({
+        "escrow": format!("0x{}", "11".repeat(20)),
+        "maker": format!("0x{}", "22".repeat(20)),
+        "taker": format!("0x{}", "33".repeat(20)),
+        "token": format!("0x{}", "00".repeat(20)),
+        "amount": "1000",
+        "safety_deposit": "0",
+        "timelocks": format!("0x{}", "00".repeat(32)),
+    });
+    let source = fake_contract(vec![escrowed]);
+    source
+        .lock()
+        .unwrap()
+        .events
+        .push(secret_revealed("src_1", SECRET));
+    let mut watchtower = Watchtower::new(vec![chain("near", &source, &["resolver.testnet"])]);
+
+    // Created without a prepaid sign deposit, so the receiver withdraws
+    let tick = watchtower.tick(100).await.unwrap();
+    assert_eq!(
+        tick.actions,
+        vec![Action::Skipped {
+            chain: "near".to_string(),
+            htlc_id: "src_1".to_string(),
+            reason: "withdraw needs the settlement sign deposit attached".to_string(),
+        }]
+    );
+    assert!(calls(&source).is_empty());
+
+    // Reported once
+    assert_eq!(watchtower.tick(200).await.unwrap(), Tick::default());
+
+    // With the deposit prepaid it is withdrawn as usual
+    source.lock().unwrap().htlcs[0]["settlement_deposit"] = json!("500");
+    let tick = watchtower.tick(300).await.unwrap();
+    assert_eq!(
+        tick.actions,
+        vec![Action::Withdrawn {
+            chain: "near".to_string(),
+            htlc_id: "src_1".to_string(),
+        }]
+    );
+}
+
+#[tokio::test]
+async fn test_claims_failed_payouts() {
+    let contract = fake_contract(vec![]);
+    {
+        let mut contract = contract.lock().unwrap();
+        contract.claimable = vec![
+            (
+                "maker.testnet".to_string(),
+                Some("usdc.testnet".to_string()),
+            ),
+            ("stranger.testnet".to_string(), None),
+        ];
+        contract.events = vec![
+            payout_failed("maker.testnet", Some("usdc.testnet")),
+            // Not registered
+            payout_failed("stranger.testnet", None),
+            // Already claimed by the account itself
+            payout_failed("maker.testnet", None),
+        ];
+    }
+    let mut watchtower = Watchtower::new(vec![chain("near", &contract, &["maker.testnet"])]);
+
+    let tick = watchtower.tick(100).await.unwrap();
+    assert_eq!(
+        tick.actions,
+        vec![Action::Claimed {
+            chain: "near".to_string(),
+            account_id: account("maker.testnet"),
+            token: Some(account("usdc.testnet")),
+        }]
+    );
+    assert_eq!(
+        calls(&contract),
+        vec![(
+            "maker.testnet".to_string(),
+            "claim".to_string(),
+            "usdc.testnet".to_string()
+        )]
+    );
+    assert_eq!(watchtower.tick(200).await.unwrap(), Tick::default());
+}
+
+#[tokio::test]
+async fn test_relays_secret_to_counterpart() {
+    let other_secret = b"secret of the second fill";
+    let source = fake_contract(vec![
+        htlc("src_1", "maker.testnet", "resolver.testnet", SECRET, 10_000),
+        // Another fill of the same order, locked by a secret not yet revealed
+        htlc(
+            "src_2",
+            "maker.testnet",
+            "resolver.testnet",
+            other_secret,
+            10_000,
+        ),
+        // Same secret, but its receiver has not registered
+        htlc("src_3", "maker.testnet", "stranger.testnet", SECRET, 10_000),
+    ]);
+    let destination = fake_contract(vec![htlc(
+        "dst_1",
+        "resolver.testnet",
+        "maker.testnet",
+        SECRET,
+        5_000,
+    )]);
+    let mut watchtower = Watchtower::new(vec![
+        chain("near-src", &source, &["resolver.testnet"]),
+        chain("near-dst", &destination, &[]),
+    ]);
+    assert_eq!(watchtower.tick(100).await.unwrap(), Tick::default());
+
+    // The maker claims the destination escrow, revealing the secret
+    let maker = HtlcClient::new(
+        FakeRpc {
+            contract: destination.clone(),
+            signer: account("maker.testnet"),
+        },
+        account("htlc.near-dst"),
+    );
+    maker.withdraw("dst_1", SECRET).await.unwrap();
+
+    let tick = watchtower.tick(200).await.unwrap();
+    assert_eq!(
+        tick.actions,
+        vec![Action::Withdrawn {
+            chain: "near-src".to_string(),
+            htlc_id: "src_1".to_string(),
+        }]
+    );
+    assert_eq!(
+        calls(&source),
+        vec![(
+            "resolver.testnet".to_string(),
+            "withdraw".to_string(),
+            "src_1".to_string()
+        )]
+    );
+
+    // The second fill goes through once its own secret is revealed
+    destination
+        .lock()
+        .unwrap()
+        .events
+        .push(secret_revealed("dst_1", other_secret));
+    let tick = watchtower.tick(300).await.unwrap();
+    assert_eq!(
+        tick.actions,
+        vec![Action::Withdrawn {
+            chain: "near-src".to_string(),
+            htlc_id: "src_2".to_string(),
+        }]
+    );
+    assert_eq!(watchtower.tick(400).await.unwrap(), Tick::default());
+}
+
+#[tokio::test]
+async fn test_pages_through_active_htlcs() {
+    let htlcs = (0..5)
+        .map(|i| {
+            htlc(
+                &format!("htlc_{i}"),
+                "maker.testnet",
+                "resolver.testnet",
+                SECRET,
+                100,
+            )
+        })
+        .collect();
+    let contract = fake_contract(htlcs);
+    contract.lock().unwrap().block_timestamp = 100;
+    let mut watchtower =
+        Watchtower::new(vec![chain("near", &contract, &["maker.testnet"])]).with_page_size(2);
+
+    let tick = watchtower.tick(100).await.unwrap();
+    let expected: Vec<_> = (0..5)
+        .map(|i| refunded("near", &format!("htlc_{i}")))
+        .collect();
+    assert_eq!(tick.actions, expected);
+}
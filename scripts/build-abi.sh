@@ -0,0 +1,28 @@
+#!/bin/bash
+set -e
+
+# JSON ABIs of the NEAR contracts (near-abi format), for the TypeScript
+# solver and the BASE orchestrator. Requires cargo-near:
+#   cargo install cargo-near
+#
+# `cargo near build` embeds the same ABI, zstd-compressed, in the wasm;
+# deployed contracts serve it from the `__contract_abi` view. The release
+# build (scripts/build-contract.sh) does not embed it, but records its sha256
+# in the `contract_source_metadata` link so clients can check the JSON they
+# were given against the deployed code.
+
+OUT_DIR="${OUT_DIR:-target/near/abi}"
+
+if ! cargo near --version &> /dev/null; then
+    echo "❌ cargo-near not found. Install with: cargo install cargo-near"
+    exit 1
+fi
+
+mkdir -p "$OUT_DIR"
+for contract in fusion-plus-htlc solver-registry; do
+    echo "📄 Generating ABI for $contract..."
+    cargo near abi --manifest-path "contracts/$contract/Cargo.toml" --out-dir "$OUT_DIR"
+done
+
+echo "✅ ABIs written to $OUT_DIR"
+ls -lh "$OUT_DIR"/*_abi.json
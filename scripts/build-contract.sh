@@ -84,6 +84,16 @@ check_dependencies
 echo ""
 echo "📦 Building contracts..."
 
+# JSON ABIs, whose hashes go into contract_source_metadata
+if cargo near --version &> /dev/null; then
+    ./scripts/build-abi.sh
+else
+    echo -e "${YELLOW}⚠️  cargo-near not found; reusing any ABIs already in target/near/abi${NC}"
+fi
+
+# Repository, commit, build info and ABI hashes for contract_source_metadata (NEP-330)
+source scripts/nep330-env.sh
+
 # Build all contracts
 cargo build --target wasm32-unknown-unknown --release
 
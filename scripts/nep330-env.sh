@@ -0,0 +1,38 @@
+#!/bin/bash
+# Source before building release wasm to fill in the NEP-330 source
+# metadata served by `contract_source_metadata`. Values already set in the
+# environment win, so CI can pin e.g. the repository link or build image.
+#
+#   source scripts/nep330-env.sh
+
+commit=$(git rev-parse HEAD 2>/dev/null || true)
+remote=$(git remote get-url origin 2>/dev/null || true)
+# git@github.com:org/repo.git -> https://github.com/org/repo
+remote=$(echo "$remote" | sed -E 's#^git@([^:]+):#https://\1/#; s#\.git$##')
+
+export NEP330_LINK="${NEP330_LINK:-$remote}"
+if [ -n "$NEP330_LINK" ] && [ -n "$commit" ]; then
+    if ! git diff --quiet HEAD 2>/dev/null; then
+        echo "⚠️  Working tree has uncommitted changes; build info will not match $commit"
+    fi
+    export NEP330_BUILD_INFO_BUILD_ENVIRONMENT="${NEP330_BUILD_INFO_BUILD_ENVIRONMENT:-$(rustc --version | cut -d' ' -f1-2)}"
+    export NEP330_BUILD_INFO_BUILD_COMMAND="${NEP330_BUILD_INFO_BUILD_COMMAND:-[\"cargo\",\"build\",\"--target\",\"wasm32-unknown-unknown\",\"--release\"]}"
+    export NEP330_BUILD_INFO_SOURCE_CODE_SNAPSHOT="${NEP330_BUILD_INFO_SOURCE_CODE_SNAPSHOT:-git+$NEP330_LINK?rev=$commit}"
+else
+    echo "⚠️  No git remote or commit found; contract_source_metadata will carry no build info"
+fi
+
+# NEP-330 has no ABI field, so each contract's build.rs appends the sha256 of
+# its JSON ABI (scripts/build-abi.sh) to the link as `#abi-sha256=<hex>`.
+abi_dir="${ABI_DIR:-target/near/abi}"
+for contract in fusion_plus_htlc solver_registry; do
+    var="NEP330_ABI_SHA256_$(echo "$contract" | tr '[:lower:]' '[:upper:]')"
+    abi="$abi_dir/${contract}_abi.json"
+    if [ -n "${!var}" ]; then
+        export "$var"
+    elif [ -f "$abi" ]; then
+        export "$var=$( (sha256sum "$abi" 2>/dev/null || shasum -a 256 "$abi") | cut -d' ' -f1)"
+    else
+        echo "⚠️  No ABI at $abi; contract_source_metadata will carry no ABI hash for $contract"
+    fi
+done
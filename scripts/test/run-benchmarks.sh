@@ -0,0 +1,35 @@
+#!/bin/bash
+set -e
+
+# Gas and storage benchmarks for the HTLC (near-workspaces), checked against
+# contracts/fusion-plus-htlc/tests/gas_baseline.json. Pass --update to
+# record the measured values as the new baseline. While no value has been
+# recorded yet, the first run records them and fails until they are
+# committed.
+
+BASELINE=contracts/fusion-plus-htlc/tests/gas_baseline.json
+
+echo "Building contracts..."
+cargo build --target wasm32-unknown-unknown --release
+
+if [ -z "$NEAR_SANDBOX_BIN_PATH" ]; then
+    echo "⚠️  NEAR_SANDBOX_BIN_PATH not set; near-workspaces will download a sandbox binary"
+fi
+
+BOOTSTRAP=0
+if [ "$1" = "--update" ]; then
+    export BENCH_UPDATE_BASELINE=1
+elif ! grep -q '"gas_burnt"' "$BASELINE"; then
+    echo "⚠️  $BASELINE has no recorded values; recording them now"
+    export BENCH_UPDATE_BASELINE=1
+    BOOTSTRAP=1
+fi
+
+echo "Running HTLC benchmarks..."
+cargo test -p fusion-plus-htlc --test gas_benchmarks -- --ignored --nocapture
+
+if [ "$BOOTSTRAP" = "1" ]; then
+    echo "❌ Baseline recorded in $BASELINE; review and commit it, then run again"
+    exit 1
+fi
+echo "✅ Benchmarks complete"
@@ -1,6 +1,16 @@
 #!/bin/bash
 set -e
 
+# Sandbox tests for the NEAR contracts (near-workspaces). They deploy the
+# release wasm builds, so build those first.
+
+echo "Building contracts..."
+cargo build --target wasm32-unknown-unknown --release
+
+if [ -z "$NEAR_SANDBOX_BIN_PATH" ]; then
+    echo "⚠️  NEAR_SANDBOX_BIN_PATH not set; near-workspaces will download a sandbox binary"
+fi
+
 echo "Running NEAR integration tests..."
-echo "⚠️  Integration tests not yet implemented"
-echo "✅ Test suite complete"
\ No newline at end of file
+cargo test -p fusion-plus-htlc --test sandbox -- --ignored
+echo "✅ Test suite complete"